@@ -0,0 +1,2 @@
+DROP TABLE "model_derived_source";
+DROP TABLE "model_derived";
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS "model_derived" (
+  "model_id" uuid NOT NULL,
+  "index" smallint NOT NULL,
+  "expression" text NOT NULL,
+  PRIMARY KEY ("model_id","index"),
+  FOREIGN KEY ("model_id")
+    REFERENCES "model" ("model_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "model_derived_source" (
+  "model_id" uuid NOT NULL,
+  "source_index" smallint NOT NULL,
+  "source_model_id" uuid NOT NULL,
+  "source_field" smallint NOT NULL,
+  PRIMARY KEY ("model_id","source_index"),
+  FOREIGN KEY ("model_id")
+    REFERENCES "model" ("model_id") ON UPDATE CASCADE ON DELETE CASCADE,
+  FOREIGN KEY ("source_model_id")
+    REFERENCES "model" ("model_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS "alarm" (
+  "device_id" uuid NOT NULL,
+  "model_id" uuid NOT NULL,
+  "index" smallint NOT NULL,
+  "kind" smallint NOT NULL DEFAULT 0,
+  "value" double precision NOT NULL,
+  "timestamp" timestamptz NOT NULL,
+  PRIMARY KEY ("device_id","model_id","index"),
+  FOREIGN KEY ("device_id")
+    REFERENCES "device" ("device_id") ON UPDATE CASCADE ON DELETE CASCADE,
+  FOREIGN KEY ("model_id")
+    REFERENCES "model" ("model_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
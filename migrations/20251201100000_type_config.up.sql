@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS "device_type_config" (
+  "id" serial NOT NULL,
+  "type_id" uuid NOT NULL,
+  "name" varchar(128) NOT NULL,
+  "value" bytea NOT NULL,
+  "type" smallint NOT NULL DEFAULT 0,
+  "category" varchar(64) NOT NULL,
+  PRIMARY KEY ("id"),
+  FOREIGN KEY ("type_id")
+    REFERENCES "device_type" ("type_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
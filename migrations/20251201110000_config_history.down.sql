@@ -0,0 +1,2 @@
+DROP TABLE "device_config_history";
+DROP TABLE "model_config_history";
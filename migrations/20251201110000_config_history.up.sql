@@ -0,0 +1,41 @@
+CREATE TABLE IF NOT EXISTS "model_config_history" (
+  "id" serial NOT NULL,
+  "config_id" integer NOT NULL,
+  "model_id" uuid NOT NULL,
+  "index" smallint NOT NULL,
+  "name" varchar(128) NOT NULL,
+  "value" bytea NOT NULL,
+  "type" smallint NOT NULL DEFAULT 0,
+  "category" varchar(64) NOT NULL,
+  "valid_from" timestamptz NOT NULL,
+  "valid_to" timestamptz,
+  PRIMARY KEY ("id"),
+  FOREIGN KEY ("model_id")
+    REFERENCES "model" ("model_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "model_config_history_model_id_idx" ON "model_config_history" ("model_id","valid_from");
+
+CREATE TABLE IF NOT EXISTS "device_config_history" (
+  "id" serial NOT NULL,
+  "config_id" integer NOT NULL,
+  "device_id" uuid NOT NULL,
+  "name" varchar(128) NOT NULL,
+  "value" bytea NOT NULL,
+  "type" smallint NOT NULL DEFAULT 0,
+  "category" varchar(64) NOT NULL,
+  "valid_from" timestamptz NOT NULL,
+  "valid_to" timestamptz,
+  PRIMARY KEY ("id"),
+  FOREIGN KEY ("device_id")
+    REFERENCES "device" ("device_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "device_config_history_device_id_idx" ON "device_config_history" ("device_id","valid_from");
+
+-- existing configs become the first version which is valid since epoch
+INSERT INTO "model_config_history" ("config_id","model_id","index","name","value","type","category","valid_from")
+SELECT "id","model_id","index","name","value","type","category",to_timestamp(0) FROM "model_config";
+
+INSERT INTO "device_config_history" ("config_id","device_id","name","value","type","category","valid_from")
+SELECT "id","device_id","name","value","type","category",to_timestamp(0) FROM "device_config";
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS "device_lifecycle_idx";
+
+ALTER TABLE "device" DROP COLUMN IF EXISTS "lifecycle_timestamp";
+ALTER TABLE "device" DROP COLUMN IF EXISTS "lifecycle";
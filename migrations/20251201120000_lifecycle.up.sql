@@ -0,0 +1,4 @@
+ALTER TABLE "device" ADD COLUMN IF NOT EXISTS "lifecycle" smallint NOT NULL DEFAULT 1;
+ALTER TABLE "device" ADD COLUMN IF NOT EXISTS "lifecycle_timestamp" timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS "device_lifecycle_idx" ON "device" ("lifecycle");
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS "device_parent_id_idx";
+
+ALTER TABLE "device" DROP COLUMN IF EXISTS "parent_id";
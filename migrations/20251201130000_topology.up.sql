@@ -0,0 +1,10 @@
+ALTER TABLE "device" ADD COLUMN IF NOT EXISTS "parent_id" uuid;
+
+UPDATE "device" SET "parent_id" = "gateway_id" WHERE "device_id" <> "gateway_id"
+  AND "gateway_id" IN (SELECT "device_id" FROM "device");
+
+-- deferred so a device and its parent can be written in any order inside a transaction
+ALTER TABLE "device" ADD CONSTRAINT "device_parent_id_fkey" FOREIGN KEY ("parent_id")
+  REFERENCES "device" ("device_id") ON UPDATE CASCADE ON DELETE SET NULL DEFERRABLE INITIALLY DEFERRED;
+
+CREATE INDEX IF NOT EXISTS "device_parent_id_idx" ON "device" ("parent_id");
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS "device_status" (
+  "device_id" uuid NOT NULL,
+  "last_data" timestamptz,
+  "last_buffer" timestamptz,
+  "last_log" timestamptz,
+  PRIMARY KEY ("device_id"),
+  FOREIGN KEY ("device_id")
+    REFERENCES "device" ("device_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
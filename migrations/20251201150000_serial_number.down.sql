@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS "device_serial_number_idx";
+
+DROP INDEX IF EXISTS "device_type_serial_number_idx";
@@ -0,0 +1,27 @@
+-- serial numbers must be unique within a device type before the unique index is created
+DO $$
+DECLARE
+  conflicts text;
+BEGIN
+  SELECT string_agg(format('%s (type %s, %s devices)', "serial_number", "type_id", "count"), ', ') INTO conflicts
+  FROM (
+    SELECT "type_id", "serial_number", count(*) AS "count" FROM "device"
+    GROUP BY "type_id", "serial_number" HAVING count(*) > 1
+  ) AS "duplicate";
+  IF conflicts IS NOT NULL THEN
+    RAISE EXCEPTION 'duplicate device serial numbers: %', conflicts;
+  END IF;
+  -- serial numbers shared across types only conflict when uniqueness is not scoped per type
+  SELECT string_agg(format('%s (%s devices)', "serial_number", "count"), ', ') INTO conflicts
+  FROM (
+    SELECT "serial_number", count(*) AS "count" FROM "device"
+    GROUP BY "serial_number" HAVING count(*) > 1
+  ) AS "duplicate";
+  IF conflicts IS NOT NULL THEN
+    RAISE WARNING 'device serial numbers shared across types: %', conflicts;
+  END IF;
+END $$;
+
+CREATE UNIQUE INDEX IF NOT EXISTS "device_type_serial_number_idx" ON "device" ("type_id", "serial_number");
+
+CREATE INDEX IF NOT EXISTS "device_serial_number_idx" ON "device" ("serial_number");
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS "set_label";
+DROP TABLE IF EXISTS "model_label";
+DROP TABLE IF EXISTS "device_label";
@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS "device_label" (
+  "device_id" uuid NOT NULL,
+  "key" varchar(64) NOT NULL,
+  "value" varchar(255) NOT NULL DEFAULT '',
+  PRIMARY KEY ("device_id","key"),
+  FOREIGN KEY ("device_id")
+    REFERENCES "device" ("device_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "model_label" (
+  "model_id" uuid NOT NULL,
+  "key" varchar(64) NOT NULL,
+  "value" varchar(255) NOT NULL DEFAULT '',
+  PRIMARY KEY ("model_id","key"),
+  FOREIGN KEY ("model_id")
+    REFERENCES "model" ("model_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "set_label" (
+  "set_id" uuid NOT NULL,
+  "key" varchar(64) NOT NULL,
+  "value" varchar(255) NOT NULL DEFAULT '',
+  PRIMARY KEY ("set_id","key"),
+  FOREIGN KEY ("set_id")
+    REFERENCES "set" ("set_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "device_label_key_value_idx" ON "device_label" ("key", "value");
+CREATE INDEX IF NOT EXISTS "model_label_key_value_idx" ON "model_label" ("key", "value");
+CREATE INDEX IF NOT EXISTS "set_label_key_value_idx" ON "set_label" ("key", "value");
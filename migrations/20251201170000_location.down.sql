@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS "device_location";
+
+DROP INDEX IF EXISTS "device_latitude_longitude_idx";
+
+ALTER TABLE "device" DROP COLUMN IF EXISTS "altitude";
+ALTER TABLE "device" DROP COLUMN IF EXISTS "longitude";
+ALTER TABLE "device" DROP COLUMN IF EXISTS "latitude";
@@ -0,0 +1,16 @@
+ALTER TABLE "device" ADD COLUMN IF NOT EXISTS "latitude" double precision;
+ALTER TABLE "device" ADD COLUMN IF NOT EXISTS "longitude" double precision;
+ALTER TABLE "device" ADD COLUMN IF NOT EXISTS "altitude" double precision;
+
+CREATE INDEX IF NOT EXISTS "device_latitude_longitude_idx" ON "device" ("latitude", "longitude");
+
+CREATE TABLE IF NOT EXISTS "device_location" (
+  "device_id" uuid NOT NULL,
+  "timestamp" timestamptz NOT NULL,
+  "latitude" double precision NOT NULL,
+  "longitude" double precision NOT NULL,
+  "altitude" double precision,
+  PRIMARY KEY ("device_id","timestamp"),
+  FOREIGN KEY ("device_id")
+    REFERENCES "device" ("device_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS "device_type_config_key";
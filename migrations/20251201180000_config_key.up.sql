@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS "device_type_config_key" (
+  "type_id" uuid NOT NULL,
+  "name" varchar(128) NOT NULL,
+  "category" varchar(64) NOT NULL,
+  "type" smallint NOT NULL DEFAULT 0,
+  "required" boolean NOT NULL DEFAULT false,
+  "default_value" bytea,
+  "min" double precision,
+  "max" double precision,
+  PRIMARY KEY ("type_id","category","name"),
+  FOREIGN KEY ("type_id")
+    REFERENCES "device_type" ("type_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
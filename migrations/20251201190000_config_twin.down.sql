@@ -0,0 +1,4 @@
+ALTER TABLE "device_config" DROP COLUMN IF EXISTS "reported_timestamp";
+ALTER TABLE "device_config" DROP COLUMN IF EXISTS "reported_type";
+ALTER TABLE "device_config" DROP COLUMN IF EXISTS "reported_value";
+ALTER TABLE "device_config" DROP COLUMN IF EXISTS "desired_timestamp";
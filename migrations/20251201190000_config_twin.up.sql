@@ -0,0 +1,4 @@
+ALTER TABLE "device_config" ADD COLUMN IF NOT EXISTS "desired_timestamp" timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE "device_config" ADD COLUMN IF NOT EXISTS "reported_value" bytea;
+ALTER TABLE "device_config" ADD COLUMN IF NOT EXISTS "reported_type" smallint;
+ALTER TABLE "device_config" ADD COLUMN IF NOT EXISTS "reported_timestamp" timestamptz;
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS "group_model_child" (
+  "group_id" uuid NOT NULL,
+  "child_id" uuid NOT NULL,
+  PRIMARY KEY ("group_id","child_id"),
+  CHECK ("group_id" <> "child_id"),
+  FOREIGN KEY ("group_id")
+    REFERENCES "group_model" ("group_id") ON UPDATE CASCADE ON DELETE CASCADE,
+  FOREIGN KEY ("child_id")
+    REFERENCES "group_model" ("group_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "group_device_child" (
+  "group_id" uuid NOT NULL,
+  "child_id" uuid NOT NULL,
+  PRIMARY KEY ("group_id","child_id"),
+  CHECK ("group_id" <> "child_id"),
+  FOREIGN KEY ("group_id")
+    REFERENCES "group_device" ("group_id") ON UPDATE CASCADE ON DELETE CASCADE,
+  FOREIGN KEY ("child_id")
+    REFERENCES "group_device" ("group_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "group_model_child_child_id_idx" ON "group_model_child" ("child_id");
+CREATE INDEX IF NOT EXISTS "group_device_child_child_id_idx" ON "group_device_child" ("child_id");
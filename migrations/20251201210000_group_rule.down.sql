@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS "group_device_rule";
+DROP TABLE IF EXISTS "group_model_rule";
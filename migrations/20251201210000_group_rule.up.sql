@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS "group_model_rule" (
+  "group_id" uuid NOT NULL,
+  "type_id" uuid,
+  "name" varchar(128),
+  "category" varchar(64),
+  "label" text NOT NULL DEFAULT '',
+  PRIMARY KEY ("group_id"),
+  FOREIGN KEY ("group_id")
+    REFERENCES "group_model" ("group_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "group_device_rule" (
+  "group_id" uuid NOT NULL,
+  "type_id" uuid,
+  "gateway_id" uuid,
+  "name" varchar(128),
+  "label" text NOT NULL DEFAULT '',
+  PRIMARY KEY ("group_id"),
+  FOREIGN KEY ("group_id")
+    REFERENCES "group_device" ("group_id") ON UPDATE CASCADE ON DELETE CASCADE
+);
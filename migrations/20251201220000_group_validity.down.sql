@@ -0,0 +1,11 @@
+DELETE FROM "group_device_map" WHERE "valid_to" IS NOT NULL;
+DROP INDEX IF EXISTS "group_device_map_current_idx";
+ALTER TABLE "group_device_map" DROP CONSTRAINT "group_device_map_pkey";
+ALTER TABLE "group_device_map" ADD PRIMARY KEY ("group_id","device_id");
+ALTER TABLE "group_device_map" DROP COLUMN "valid_to", DROP COLUMN "valid_from";
+
+DELETE FROM "group_model_map" WHERE "valid_to" IS NOT NULL;
+DROP INDEX IF EXISTS "group_model_map_current_idx";
+ALTER TABLE "group_model_map" DROP CONSTRAINT "group_model_map_pkey";
+ALTER TABLE "group_model_map" ADD PRIMARY KEY ("group_id","model_id");
+ALTER TABLE "group_model_map" DROP COLUMN "valid_to", DROP COLUMN "valid_from";
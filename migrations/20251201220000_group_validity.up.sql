@@ -0,0 +1,15 @@
+ALTER TABLE "group_model_map"
+  ADD COLUMN "valid_from" timestamptz NOT NULL DEFAULT '1970-01-01 00:00:00+00',
+  ADD COLUMN "valid_to" timestamptz;
+ALTER TABLE "group_model_map" ALTER COLUMN "valid_from" SET DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE "group_model_map" DROP CONSTRAINT "group_model_map_pkey";
+ALTER TABLE "group_model_map" ADD PRIMARY KEY ("group_id","model_id","valid_from");
+CREATE UNIQUE INDEX IF NOT EXISTS "group_model_map_current_idx" ON "group_model_map" ("group_id","model_id") WHERE "valid_to" IS NULL;
+
+ALTER TABLE "group_device_map"
+  ADD COLUMN "valid_from" timestamptz NOT NULL DEFAULT '1970-01-01 00:00:00+00',
+  ADD COLUMN "valid_to" timestamptz;
+ALTER TABLE "group_device_map" ALTER COLUMN "valid_from" SET DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE "group_device_map" DROP CONSTRAINT "group_device_map_pkey";
+ALTER TABLE "group_device_map" ADD PRIMARY KEY ("group_id","device_id","valid_from");
+CREATE UNIQUE INDEX IF NOT EXISTS "group_device_map_current_idx" ON "group_device_map" ("group_id","device_id") WHERE "valid_to" IS NULL;
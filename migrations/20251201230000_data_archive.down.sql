@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS "slice_data_archive";
+DROP TABLE IF EXISTS "data_buffer_archive";
+DROP TABLE IF EXISTS "data_archive";
@@ -0,0 +1,34 @@
+-- data of purged devices and models kept when purged with archive
+CREATE TABLE IF NOT EXISTS "data_archive" (
+  "device_id" uuid NOT NULL,
+  "model_id" uuid NOT NULL,
+  "timestamp" timestamptz NOT NULL,
+  "tag" smallint NOT NULL DEFAULT 0,
+  "data" bytea NOT NULL,
+  "archived_at" timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS "data_archive_device_model_idx" ON "data_archive" ("device_id","model_id","timestamp");
+
+CREATE TABLE IF NOT EXISTS "data_buffer_archive" (
+  "device_id" uuid NOT NULL,
+  "model_id" uuid NOT NULL,
+  "timestamp" timestamptz NOT NULL,
+  "tag" smallint NOT NULL DEFAULT 0,
+  "data" bytea NOT NULL,
+  "archived_at" timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS "data_buffer_archive_device_model_idx" ON "data_buffer_archive" ("device_id","model_id","timestamp");
+
+CREATE TABLE IF NOT EXISTS "slice_data_archive" (
+  "device_id" uuid NOT NULL,
+  "model_id" uuid NOT NULL,
+  "timestamp_begin" timestamptz NOT NULL,
+  "timestamp_end" timestamptz NOT NULL,
+  "name" varchar(128) NOT NULL,
+  "description" text NOT NULL DEFAULT '',
+  "archived_at" timestamptz NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS "slice_data_archive_device_model_idx" ON "slice_data_archive" ("device_id","model_id");
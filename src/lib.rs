@@ -17,21 +17,44 @@ use operation::data;
 use operation::buffer;
 use operation::slice;
 use operation::log;
+use operation::bundle;
+use operation::calibration;
+use operation::derived;
+use operation::alarm;
+use operation::purge;
+use operation::status;
+use operation::provision;
+use operation::label;
+use operation::location;
+use operation::aggregate;
 pub use schema::value::{DataType, DataValue, ArrayDataValue};
-pub use schema::model::{ModelSchema, TagSchema, ModelConfigSchema};
-pub use schema::device::{DeviceSchema, GatewaySchema, TypeSchema, DeviceConfigSchema, GatewayConfigSchema};
-use schema::device::DeviceKind;
-pub use schema::group::{GroupModelSchema, GroupDeviceSchema, GroupGatewaySchema};
+pub use schema::model::{ModelSchema, TagSchema, ModelConfigSchema, ModelConfigHistorySchema};
+pub use schema::derived::{DerivedModelSchema, DerivedSource};
+pub use schema::device::{DeviceModelIncompatible, DeviceSchema, GatewaySchema, TypeSchema, DeviceConfigSchema, GatewayConfigSchema, TypeConfigSchema, EffectiveConfigSchema, ConfigSource, DeviceConfigHistorySchema, DeviceLifecycle, SerialScope, CompatibilityMode, DeviceTreeSchema, TypeConfigKeySchema, ConfigViolation, ConfigViolationSchema};
+use schema::device::{DeviceKind, lifecycle_filter};
+use device::{DeviceInsert, DeviceFilter};
+pub use schema::group::{GroupModelSchema, GroupDeviceSchema, GroupGatewaySchema, GroupRuleSchema, GroupMemberDiff};
 use schema::group::GroupKind;
-pub use schema::set::{SetSchema, SetTemplateSchema, SetMember, SetTemplateMember};
+pub use schema::set::{SetSchema, SetTemplateSchema, SetMember, SetTemplateMember, SetTemplateBinding};
 pub use schema::data::{DataSchema, DataSetSchema};
-use data::DataSelector;
+pub use schema::calibration::{Calibration, DataCalibratedSchema};
+use data::{DataSelector, IngestCheck};
 pub use schema::buffer::{BufferSchema, BufferSetSchema};
 use buffer::BufferSelector;
 pub use schema::slice::{SliceSchema, SliceSetSchema};
 use slice::SliceSelector;
 pub use schema::log::LogSchema;
+pub use schema::alarm::{AlarmSchema, AlarmKind};
+pub use schema::purge::PurgeReportSchema;
+pub use schema::status::DeviceStatusSchema;
+pub use schema::provision::{DeviceManifest, DeviceManifestRow, DeviceManifestConfig, ProvisionReportSchema, ProvisionStatus};
 use log::LogSelector;
+use status::StatusKind;
+pub use schema::label::{LabelSchema, LabelSelector, LabelRequirement};
+use schema::label::LabelKind;
+pub use schema::location::DeviceLocationSchema;
+pub use schema::aggregate::{AggregatePeriod, AggregateFunction, GroupAggregate};
+pub use schema::bundle::{BundleSchema, BundleDiffSchema, BundleKind, BundleChange, BUNDLE_VERSION};
 pub use utility::tag;
 
 #[derive(Debug, Clone)]
@@ -44,7 +67,11 @@ pub struct Resource {
 pub struct ResourceOptions {
     limit: usize,
     with_description: bool,
-    order: Vec<OrderOption>
+    order: Vec<OrderOption>,
+    alarm: bool,
+    serial_scope: SerialScope,
+    compatibility: CompatibilityMode,
+    lifecycle_exclude: Vec<DeviceLifecycle>
 }
 
 #[derive(Debug, Clone)]
@@ -60,7 +87,11 @@ impl Default for ResourceOptions {
         ResourceOptions { 
             limit: 10000, 
             with_description: false, 
-            order: vec![] 
+            order: vec![],
+            alarm: false,
+            serial_scope: SerialScope::Type,
+            compatibility: CompatibilityMode::Disabled,
+            lifecycle_exclude: vec![]
         }
     }
 }
@@ -103,6 +134,22 @@ impl Resource {
         self.options.order = order;
     }
 
+    pub fn set_alarm(&mut self, alarm: bool) {
+        self.options.alarm = alarm;
+    }
+
+    pub fn set_serial_scope(&mut self, scope: SerialScope) {
+        self.options.serial_scope = scope;
+    }
+
+    pub fn set_compatibility(&mut self, mode: CompatibilityMode) {
+        self.options.compatibility = mode;
+    }
+
+    pub fn set_lifecycle_exclude(&mut self, lifecycles: &[DeviceLifecycle]) {
+        self.options.lifecycle_exclude = lifecycles.to_vec();
+    }
+
     pub async fn read_model(&self, id: Uuid)
         -> Result<ModelSchema, Error>
     {
@@ -169,6 +216,56 @@ impl Resource {
         .await
     }
 
+    pub async fn purge_model(&self, id: Uuid, dry_run: bool)
+        -> Result<PurgeReportSchema, Error>
+    {
+        purge::purge_model(&self.pool, &[id], dry_run, false)
+        .await
+    }
+
+    pub async fn purge_model_with_archive(&self, id: Uuid, dry_run: bool)
+        -> Result<PurgeReportSchema, Error>
+    {
+        purge::purge_model(&self.pool, &[id], dry_run, true)
+        .await
+    }
+
+    pub async fn list_model_label(&self, id: Uuid)
+        -> Result<Vec<LabelSchema>, Error>
+    {
+        label::select_label(&self.pool, LabelKind::Model, id)
+        .await
+    }
+
+    pub async fn add_model_label(&self, id: Uuid, key: &str, value: &str)
+        -> Result<(), Error>
+    {
+        label::insert_label(&self.pool, LabelKind::Model, id, key, value)
+        .await
+    }
+
+    pub async fn remove_model_label(&self, id: Uuid, key: &str)
+        -> Result<(), Error>
+    {
+        label::delete_label(&self.pool, LabelKind::Model, id, key)
+        .await
+    }
+
+    pub async fn list_model_by_label(&self, selector: &LabelSelector)
+        -> Result<Vec<ModelSchema>, Error>
+    {
+        let ids = label::select_label_ids(&self.pool, LabelKind::Model, selector).await?;
+        model::select_model(&self.pool, None, Some(&ids), None, None, None)
+        .await
+    }
+
+    pub async fn clone_model(&self, source_id: Uuid, id: Uuid, category: Option<&str>, name: Option<&str>, description: Option<&str>, with_type: bool)
+        -> Result<Uuid, Error>
+    {
+        model::clone_model(&self.pool, source_id, id, category, name, description, with_type)
+        .await
+    }
+
     pub async fn read_model_config(&self, id: i32)
         -> Result<ModelConfigSchema, Error>
     {
@@ -186,6 +283,22 @@ impl Resource {
         .await
     }
 
+    pub async fn read_model_config_at(&self, model_id: Uuid, timestamp: DateTime<Utc>)
+        -> Result<Vec<ModelConfigSchema>, Error>
+    {
+        match model::select_model_config_history(&self.pool, &[model_id], Some(timestamp)).await {
+            Ok(value) => Ok(value.into_iter().map(|el| el.into_config()).collect()),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_model_config_history(&self, model_id: Uuid)
+        -> Result<Vec<ModelConfigHistorySchema>, Error>
+    {
+        model::select_model_config_history(&self.pool, &[model_id], None)
+        .await
+    }
+
     pub async fn create_model_config(&self, model_id: Uuid, index: i32, name: &str, value: DataValue, category: &str)
         -> Result<i32, Error>
     {
@@ -245,333 +358,801 @@ impl Resource {
         .await
     }
 
-    pub async fn read_device(&self, id: Uuid)
-        -> Result<DeviceSchema, Error>
+    pub async fn read_derived_model(&self, model_id: Uuid)
+        -> Result<DerivedModelSchema, Error>
     {
-        match device::select_device(&self.pool, DeviceKind::Device, Some(id), None, None, None, None, None).await?
-        .into_iter().next() {
-            Some(value) => Ok(value),
-            None => Err(Error::RowNotFound)
-        }
+        derived::select_derived(&self.pool, &[model_id]).await?.into_iter().next()
+            .ok_or(Error::RowNotFound)
     }
 
-    pub async fn read_device_by_sn(&self, serial_number: &str)
+    pub async fn create_derived_model(&self, model_id: Uuid, sources: &[DerivedSource], expressions: &[&str])
+        -> Result<(), Error>
+    {
+        derived::insert_derived(&self.pool, model_id, sources, expressions)
+        .await
+    }
+
+    pub async fn delete_derived_model(&self, model_id: Uuid)
+        -> Result<(), Error>
+    {
+        derived::delete_derived(&self.pool, model_id)
+        .await
+    }
+
+    pub async fn read_device(&self, id: Uuid)
         -> Result<DeviceSchema, Error>
     {
-        match device::select_device(&self.pool, DeviceKind::Device, None, Some(serial_number), None, None, None, None).await?
+        match device::select_device(&self.pool, DeviceKind::Device, Some(id), None, None, &DeviceFilter::default()).await?
         .into_iter().next() {
             Some(value) => Ok(value),
             None => Err(Error::RowNotFound)
         }
     }
 
-    pub async fn list_device_by_ids(&self, ids: &[Uuid])
-        -> Result<Vec<DeviceSchema>, Error>
+    pub async fn read_device_by_sn(&self, serial_number: &str)
+        -> Result<DeviceSchema, Error>
     {
-        device::select_device(&self.pool, DeviceKind::Device, None, None, Some(ids), None, None, None)
+        device::select_device_by_sn(&self.pool, DeviceKind::Device, serial_number, None)
         .await
     }
 
-    pub async fn list_device_by_gateway(&self, gateway_id: Uuid)
-        -> Result<Vec<DeviceSchema>, Error>
+    pub async fn read_device_by_type_sn(&self, type_id: Uuid, serial_number: &str)
+        -> Result<DeviceSchema, Error>
     {
-        device::select_device(&self.pool, DeviceKind::Device, None, None, None, Some(gateway_id), None, None)
+        device::select_device_by_sn(&self.pool, DeviceKind::Device, serial_number, Some(type_id))
         .await
     }
 
-    pub async fn list_device_by_type(&self, type_id: Uuid)
+    pub async fn list_device_by_ids(&self, ids: &[Uuid])
         -> Result<Vec<DeviceSchema>, Error>
     {
-        device::select_device(&self.pool, DeviceKind::Device, None, None, None, None, Some(type_id), None)
+        device::select_device(&self.pool, DeviceKind::Device, None, None, Some(ids), &DeviceFilter { lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() })
         .await
     }
 
-    pub async fn list_device_by_name(&self, name: &str)
+    pub async fn list_device_by_gateway(&self, gateway_id: Uuid)
         -> Result<Vec<DeviceSchema>, Error>
     {
-        device::select_device(&self.pool, DeviceKind::Device, None, None, None, None, None, Some(name))
+        device::select_device(&self.pool, DeviceKind::Device, None, None, None, &DeviceFilter { gateway_id: Some(gateway_id), lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() })
         .await
     }
 
-    pub async fn list_device_option(&self, gateway_id: Option<Uuid>, type_id: Option<Uuid>, name: Option<&str>)
+    pub async fn list_device_by_gateway_descendant(&self, gateway_id: Uuid)
         -> Result<Vec<DeviceSchema>, Error>
     {
-        device::select_device(&self.pool, DeviceKind::Device, None, None, None, gateway_id, type_id, name)
+        device::select_device_descendant(&self.pool, gateway_id, None, lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref())
         .await
     }
 
-    pub async fn create_device(&self, id: Uuid, gateway_id: Uuid, type_id: Uuid, serial_number: &str, name: &str, description: Option<&str>)
-        -> Result<Uuid, Error>
+    pub async fn list_device_tree(&self, root_id: Uuid, max_depth: Option<u32>)
+        -> Result<DeviceTreeSchema, Error>
     {
-        device::insert_device(&self.pool, id, gateway_id, type_id, serial_number, name, description)
+        device::select_device_tree(&self.pool, root_id, max_depth)
         .await
     }
 
-    pub async fn update_device(&self, id: Uuid, gateway_id: Option<Uuid>, type_id: Option<Uuid>, serial_number: Option<&str>, name: Option<&str>, description: Option<&str>)
-        -> Result<(), Error>
+    pub async fn list_device_by_type(&self, type_id: Uuid)
+        -> Result<Vec<DeviceSchema>, Error>
     {
-        device::update_device(&self.pool, DeviceKind::Device, id, gateway_id, type_id, serial_number, name, description)
+        device::select_device(&self.pool, DeviceKind::Device, None, None, None, &DeviceFilter { type_id: Some(type_id), lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() })
         .await
     }
 
-    pub async fn delete_device(&self, id: Uuid)
-        -> Result<(), Error>
+    pub async fn list_device_by_name(&self, name: &str)
+        -> Result<Vec<DeviceSchema>, Error>
     {
-        device::delete_device(&self.pool, DeviceKind::Device, id)
+        device::select_device(&self.pool, DeviceKind::Device, None, None, None, &DeviceFilter { name: Some(name), lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() })
         .await
     }
 
-    pub async fn read_gateway(&self, id: Uuid)
-        -> Result<GatewaySchema, Error>
+    pub async fn list_device_by_lifecycle(&self, lifecycles: &[DeviceLifecycle])
+        -> Result<Vec<DeviceSchema>, Error>
     {
-        match device::select_device(&self.pool, DeviceKind::Gateway, Some(id), None, None, None, None, None).await?
-        .into_iter().next() {
-            Some(value) => Ok(value.into_gateway()),
-            None => Err(Error::RowNotFound)
-        }
+        device::select_device(&self.pool, DeviceKind::Device, None, None, None, &DeviceFilter { lifecycles: Some(lifecycles), ..Default::default() })
+        .await
     }
 
-    pub async fn read_gateway_by_sn(&self, serial_number: &str)
-        -> Result<GatewaySchema, Error>
+    pub async fn list_device_option(&self, gateway_id: Option<Uuid>, type_id: Option<Uuid>, name: Option<&str>)
+        -> Result<Vec<DeviceSchema>, Error>
     {
-        match device::select_device(&self.pool, DeviceKind::Gateway, None, Some(serial_number), None, None, None, None).await?
-        .into_iter().next() {
-            Some(value) => Ok(value.into_gateway()),
-            None => Err(Error::RowNotFound)
-        }
+        device::select_device(&self.pool, DeviceKind::Device, None, None, None, &DeviceFilter { gateway_id, type_id, name, lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref() })
+        .await
     }
 
-    pub async fn list_gateway_by_ids(&self, ids: &[Uuid])
-        -> Result<Vec<GatewaySchema>, Error>
+    pub async fn list_device_option_lifecycle(&self, gateway_id: Option<Uuid>, type_id: Option<Uuid>, name: Option<&str>, include: Option<&[DeviceLifecycle]>, exclude: &[DeviceLifecycle])
+        -> Result<Vec<DeviceSchema>, Error>
     {
-        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, Some(ids), None, None, None).await {
-            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
-            Err(error) => Err(error)
-        }
+        device::select_device(&self.pool, DeviceKind::Device, None, None, None, &DeviceFilter { gateway_id, type_id, name, lifecycles: lifecycle_filter(include, exclude).as_deref() })
+        .await
     }
 
-    pub async fn list_gateway_by_type(&self, type_id: Uuid)
-        -> Result<Vec<GatewaySchema>, Error>
+    pub async fn create_device(&self, id: Uuid, gateway_id: Uuid, type_id: Uuid, serial_number: &str, name: &str, description: Option<&str>)
+        -> Result<Uuid, Error>
     {
-        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, None, None, Some(type_id), None).await {
-            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
-            Err(error) => Err(error)
-        }
+        let device = DeviceInsert { id, gateway_id, type_id, serial_number, name, description };
+        device::insert_device(&self.pool, self.options.serial_scope, &device)
+        .await
     }
 
-    pub async fn list_gateway_by_name(&self, name: &str)
-        -> Result<Vec<GatewaySchema>, Error>
+    pub async fn upsert_device_by_sn(&self, id: Uuid, gateway_id: Uuid, type_id: Uuid, serial_number: &str, name: &str, description: Option<&str>)
+        -> Result<Uuid, Error>
     {
-        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, None, None, None, Some(name)).await {
-            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
-            Err(error) => Err(error)
-        }
+        let device = DeviceInsert { id, gateway_id, type_id, serial_number, name, description };
+        device::upsert_device_by_sn(&self.pool, self.options.serial_scope, &device)
+        .await
     }
 
-    pub async fn list_gateway_option(&self, type_id: Option<Uuid>, name: Option<&str>)
-        -> Result<Vec<GatewaySchema>, Error>
+    pub async fn update_device(&self, id: Uuid, gateway_id: Option<Uuid>, type_id: Option<Uuid>, serial_number: Option<&str>, name: Option<&str>, description: Option<&str>)
+        -> Result<(), Error>
     {
-        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, None, None, type_id, name).await {
-            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
-            Err(error) => Err(error)
-        }
+        let serial_number = serial_number.map(|value| (value, self.options.serial_scope));
+        device::update_device(&self.pool, DeviceKind::Device, id, gateway_id, type_id, serial_number, name, description)
+        .await
     }
 
-    pub async fn create_gateway(&self, id: Uuid, type_id: Uuid, serial_number: &str, name: &str, description: Option<&str>)
-        -> Result<Uuid, Error>
+    pub async fn update_device_lifecycle(&self, id: Uuid, lifecycle: DeviceLifecycle)
+        -> Result<(), Error>
     {
-        device::insert_device(&self.pool, id, id, type_id, serial_number, name, description)
+        device::update_device_lifecycle(&self.pool, DeviceKind::Device, id, lifecycle, Utc::now())
         .await
     }
 
-    pub async fn update_gateway(&self, id: Uuid, type_id: Option<Uuid>, serial_number: Option<&str>, name: Option<&str>, description: Option<&str>)
+    pub async fn decommission_device(&self, id: Uuid)
         -> Result<(), Error>
     {
-        device::update_device(&self.pool, DeviceKind::Gateway, id, None, type_id, serial_number, name, description)
+        device::update_device_lifecycle(&self.pool, DeviceKind::Device, id, DeviceLifecycle::Decommissioned, Utc::now())
         .await
     }
 
-    pub async fn delete_gateway(&self, id: Uuid)
+    pub async fn update_device_parent(&self, id: Uuid, parent_id: Option<Uuid>)
         -> Result<(), Error>
     {
-        device::delete_device(&self.pool, DeviceKind::Gateway, id)
+        device::update_device_parent(&self.pool, id, parent_id)
         .await
     }
 
-    pub async fn read_device_config(&self, id: i32)
-        -> Result<DeviceConfigSchema, Error>
+    pub async fn read_device_status(&self, id: Uuid)
+        -> Result<DeviceStatusSchema, Error>
     {
-        match device::select_device_config(&self.pool, DeviceKind::Device, Some(id), None).await?
-        .into_iter().next() {
-            Some(value) => Ok(value),
-            None => Err(Error::RowNotFound)
-        }
+        status::select_device_status(&self.pool, Some(id)).await?
+        .into_iter().next().ok_or(Error::RowNotFound)
     }
 
-    pub async fn list_device_config_by_device(&self, device_id: Uuid)
-        -> Result<Vec<DeviceConfigSchema>, Error>
+    pub async fn list_device_stale(&self, since: DateTime<Utc>)
+        -> Result<Vec<DeviceStatusSchema>, Error>
     {
-        device::select_device_config(&self.pool, DeviceKind::Device, None, Some(device_id))
+        status::select_device_stale(&self.pool, since, Utc::now())
         .await
     }
 
-    pub async fn create_device_config(&self, device_id: Uuid, name: &str, value: DataValue, category: &str)
-        -> Result<i32, Error>
+    pub async fn list_device_location(&self, id: Uuid)
+        -> Result<Vec<DeviceLocationSchema>, Error>
     {
-        device::insert_device_config(&self.pool, device_id, name, value, category)
+        location::select_device_location(&self.pool, id, None, None)
         .await
     }
 
-    pub async fn update_device_config(&self, id: i32, name: Option<&str>, value: Option<DataValue>, category: Option<&str>)
-        -> Result<(), Error>
+    pub async fn list_device_location_by_range(&self, id: Uuid, begin: DateTime<Utc>, end: DateTime<Utc>)
+        -> Result<Vec<DeviceLocationSchema>, Error>
     {
-        device::update_device_config(&self.pool, id, name, value, category)
+        location::select_device_location(&self.pool, id, Some(begin), Some(end))
         .await
     }
 
-    pub async fn delete_device_config(&self, id: i32)
+    pub async fn create_device_location(&self, id: Uuid, timestamp: DateTime<Utc>, latitude: f64, longitude: f64, altitude: Option<f64>)
         -> Result<(), Error>
     {
-        device::delete_device_config(&self.pool, id)
+        location::update_device_location(&self.pool, id, timestamp, latitude, longitude, altitude)
         .await
     }
 
-    pub async fn read_gateway_config(&self, id: i32)
-        -> Result<GatewayConfigSchema, Error>
-    {
-        match device::select_device_config(&self.pool, DeviceKind::Gateway, Some(id), None).await?
-        .into_iter().next() {
-            Some(value) => Ok(value.into_gateway_config()),
-            None => Err(Error::RowNotFound)
-        }
-    }
-
-    pub async fn list_gateway_config_by_gateway(&self, gateway_id: Uuid)
-        -> Result<Vec<GatewayConfigSchema>, Error>
+    pub async fn update_device_location(&self, id: Uuid, latitude: f64, longitude: f64, altitude: Option<f64>)
+        -> Result<(), Error>
     {
-        match device::select_device_config(&self.pool, DeviceKind::Gateway, None, Some(gateway_id)).await {
-            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway_config())).collect(),
-            Err(error) => Err(error)
-        }
+        location::update_device_location(&self.pool, id, Utc::now(), latitude, longitude, altitude)
+        .await
     }
 
-    pub async fn create_gateway_config(&self, gateway_id: Uuid, name: &str, value: DataValue, category: &str)
-        -> Result<i32, Error>
+    pub async fn remove_device_location(&self, id: Uuid)
+        -> Result<(), Error>
     {
-        device::insert_device_config(&self.pool, gateway_id, name, value, category)
+        location::delete_device_location(&self.pool, id)
         .await
     }
 
-    pub async fn update_gateway_config(&self, id: i32, name: Option<&str>, value: Option<DataValue>, category: Option<&str>)
-        -> Result<(), Error>
+    pub async fn list_device_within_radius(&self, latitude: f64, longitude: f64, meters: f64)
+        -> Result<Vec<DeviceSchema>, Error>
     {
-        device::update_device_config(&self.pool, id, name, value, category)
-        .await
+        let ids = location::select_device_id_within_radius(&self.pool, latitude, longitude, meters).await?;
+        let mut devices = device::select_device(&self.pool, DeviceKind::Device, None, None, Some(&ids), &DeviceFilter { lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() }).await?;
+        devices.sort_by_key(|device| ids.iter().position(|id| *id == device.id));
+        Ok(devices)
     }
 
-    pub async fn delete_gateway_config(&self, id: i32)
-        -> Result<(), Error>
+    pub async fn list_device_in_bbox(&self, min_latitude: f64, min_longitude: f64, max_latitude: f64, max_longitude: f64)
+        -> Result<Vec<DeviceSchema>, Error>
     {
-        device::delete_device_config(&self.pool, id)
+        let ids = location::select_device_id_in_bbox(&self.pool, min_latitude, min_longitude, max_latitude, max_longitude).await?;
+        device::select_device(&self.pool, DeviceKind::Device, None, None, Some(&ids), &DeviceFilter { lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() })
         .await
     }
 
-    pub async fn read_type(&self, id: Uuid)
-        -> Result<TypeSchema, Error>
+    pub async fn provision_devices(&self, manifest: &DeviceManifest)
+        -> Result<Vec<ProvisionReportSchema>, Error>
     {
-        match types::select_device_type(&self.pool, Some(id), None, None).await?
-        .into_iter().next() {
-            Some(value) => Ok(value),
-            None => Err(Error::RowNotFound)
-        }
+        provision::provision_device(&self.pool, manifest, self.options.serial_scope)
+        .await
     }
 
-    pub async fn list_type_by_ids(&self, ids: &[Uuid])
-        -> Result<Vec<TypeSchema>, Error>
+    pub async fn delete_device(&self, id: Uuid)
+        -> Result<(), Error>
     {
-        types::select_device_type(&self.pool, None, Some(ids), None)
+        device::delete_device(&self.pool, DeviceKind::Device, id)
         .await
     }
 
-    pub async fn list_type_by_name(&self, name: &str)
-        -> Result<Vec<TypeSchema>, Error>
+    pub async fn purge_device(&self, id: Uuid, dry_run: bool)
+        -> Result<PurgeReportSchema, Error>
     {
-        types::select_device_type(&self.pool, None, None, Some(name))
+        purge::purge_device(&self.pool, &[id], dry_run, false)
         .await
     }
 
-    pub async fn list_type_option(&self, name: Option<&str>)
-        -> Result<Vec<TypeSchema>, Error>
+    pub async fn purge_device_with_archive(&self, id: Uuid, dry_run: bool)
+        -> Result<PurgeReportSchema, Error>
     {
-        types::select_device_type(&self.pool, None, None, name)
+        purge::purge_device(&self.pool, &[id], dry_run, true)
         .await
     }
 
-    pub async fn create_type(&self, id: Uuid, name: &str, description: Option<&str>)
-        -> Result<Uuid, Error>
+    pub async fn list_device_label(&self, id: Uuid)
+        -> Result<Vec<LabelSchema>, Error>
     {
-        types::insert_device_type(&self.pool, id, name, description)
+        label::select_label(&self.pool, LabelKind::Device, id)
         .await
     }
 
-    pub async fn update_type(&self, id: Uuid, name: Option<&str>, description: Option<&str>)
+    pub async fn add_device_label(&self, id: Uuid, key: &str, value: &str)
         -> Result<(), Error>
     {
-        types::update_device_type(&self.pool, id, name, description)
+        label::insert_label(&self.pool, LabelKind::Device, id, key, value)
         .await
     }
 
-    pub async fn delete_type(&self, id: Uuid)
+    pub async fn remove_device_label(&self, id: Uuid, key: &str)
         -> Result<(), Error>
     {
-        types::delete_device_type(&self.pool, id)
+        label::delete_label(&self.pool, LabelKind::Device, id, key)
         .await
     }
 
-    pub async fn add_type_model(&self, id: Uuid, model_id: Uuid)
-        -> Result<(), Error>
+    pub async fn list_device_by_label(&self, selector: &LabelSelector)
+        -> Result<Vec<DeviceSchema>, Error>
     {
-        types::insert_device_type_model(&self.pool, id, model_id)
+        let ids = label::select_label_ids(&self.pool, LabelKind::Device, selector).await?;
+        device::select_device(&self.pool, DeviceKind::Device, None, None, Some(&ids), &DeviceFilter { lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() })
         .await
     }
 
-    pub async fn remove_type_model(&self, id: Uuid, model_id: Uuid)
-        -> Result<(), Error>
+    pub async fn list_device_id_by_label(&self, selector: &LabelSelector)
+        -> Result<Vec<Uuid>, Error>
     {
-        types::delete_device_type_model(&self.pool, id, model_id)
+        label::select_label_ids(&self.pool, LabelKind::Device, selector)
         .await
     }
 
-    pub async fn read_group_model(&self, id: Uuid)
-        -> Result<GroupModelSchema, Error>
+    pub async fn read_gateway(&self, id: Uuid)
+        -> Result<GatewaySchema, Error>
     {
-        match group::select_group(&self.pool, GroupKind::Model, Some(id), None, None, None).await?
+        match device::select_device(&self.pool, DeviceKind::Gateway, Some(id), None, None, &DeviceFilter::default()).await?
         .into_iter().next() {
-            Some(value) => Ok(value.into_group_model()),
+            Some(value) => Ok(value.into_gateway()),
             None => Err(Error::RowNotFound)
         }
     }
 
-    pub async fn list_group_model_by_ids(&self, ids: &[Uuid])
-        -> Result<Vec<GroupModelSchema>, Error>
+    pub async fn read_gateway_by_sn(&self, serial_number: &str)
+        -> Result<GatewaySchema, Error>
     {
-        match group::select_group(&self.pool, GroupKind::Model, None, Some(ids), None, None).await {
-            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_model())).collect(),
-            Err(error) => Err(error)
-        }
+        device::select_device_by_sn(&self.pool, DeviceKind::Gateway, serial_number, None).await
+        .map(|value| value.into_gateway())
     }
 
-    pub async fn list_group_model_by_name(&self, name: &str)
-        -> Result<Vec<GroupModelSchema>, Error>
+    pub async fn read_gateway_by_type_sn(&self, type_id: Uuid, serial_number: &str)
+        -> Result<GatewaySchema, Error>
     {
-        match group::select_group(&self.pool, GroupKind::Model, None, None, Some(name), None).await {
-            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_model())).collect(),
-            Err(error) => Err(error)
-        }
+        device::select_device_by_sn(&self.pool, DeviceKind::Gateway, serial_number, Some(type_id)).await
+        .map(|value| value.into_gateway())
     }
 
-    pub async fn list_group_model_by_category(&self, category: &str)
-        -> Result<Vec<GroupModelSchema>, Error>
+    pub async fn list_gateway_by_ids(&self, ids: &[Uuid])
+        -> Result<Vec<GatewaySchema>, Error>
+    {
+        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, Some(ids), &DeviceFilter { lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() }).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_gateway_by_type(&self, type_id: Uuid)
+        -> Result<Vec<GatewaySchema>, Error>
+    {
+        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, None, &DeviceFilter { type_id: Some(type_id), lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() }).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_gateway_by_name(&self, name: &str)
+        -> Result<Vec<GatewaySchema>, Error>
+    {
+        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, None, &DeviceFilter { name: Some(name), lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() }).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_gateway_by_lifecycle(&self, lifecycles: &[DeviceLifecycle])
+        -> Result<Vec<GatewaySchema>, Error>
+    {
+        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, None, &DeviceFilter { lifecycles: Some(lifecycles), ..Default::default() }).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_gateway_option(&self, type_id: Option<Uuid>, name: Option<&str>)
+        -> Result<Vec<GatewaySchema>, Error>
+    {
+        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, None, &DeviceFilter { type_id, name, lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() }).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_gateway_option_lifecycle(&self, type_id: Option<Uuid>, name: Option<&str>, include: Option<&[DeviceLifecycle]>, exclude: &[DeviceLifecycle])
+        -> Result<Vec<GatewaySchema>, Error>
+    {
+        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, None, &DeviceFilter { type_id, name, lifecycles: lifecycle_filter(include, exclude).as_deref(), ..Default::default() }).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_gateway_label(&self, id: Uuid)
+        -> Result<Vec<LabelSchema>, Error>
+    {
+        label::select_label(&self.pool, LabelKind::Device, id)
+        .await
+    }
+
+    pub async fn add_gateway_label(&self, id: Uuid, key: &str, value: &str)
+        -> Result<(), Error>
+    {
+        label::insert_label(&self.pool, LabelKind::Device, id, key, value)
+        .await
+    }
+
+    pub async fn remove_gateway_label(&self, id: Uuid, key: &str)
+        -> Result<(), Error>
+    {
+        label::delete_label(&self.pool, LabelKind::Device, id, key)
+        .await
+    }
+
+    pub async fn list_gateway_by_label(&self, selector: &LabelSelector)
+        -> Result<Vec<GatewaySchema>, Error>
+    {
+        let ids = label::select_label_ids(&self.pool, LabelKind::Device, selector).await?;
+        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, Some(&ids), &DeviceFilter { lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() }).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn update_gateway_location(&self, id: Uuid, latitude: f64, longitude: f64, altitude: Option<f64>)
+        -> Result<(), Error>
+    {
+        location::update_device_location(&self.pool, id, Utc::now(), latitude, longitude, altitude)
+        .await
+    }
+
+    pub async fn remove_gateway_location(&self, id: Uuid)
+        -> Result<(), Error>
+    {
+        location::delete_device_location(&self.pool, id)
+        .await
+    }
+
+    pub async fn list_gateway_within_radius(&self, latitude: f64, longitude: f64, meters: f64)
+        -> Result<Vec<GatewaySchema>, Error>
+    {
+        let ids = location::select_device_id_within_radius(&self.pool, latitude, longitude, meters).await?;
+        let mut devices = device::select_device(&self.pool, DeviceKind::Gateway, None, None, Some(&ids), &DeviceFilter { lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() }).await?;
+        devices.sort_by_key(|device| ids.iter().position(|id| *id == device.id));
+        Ok(devices.into_iter().map(|el| el.into_gateway()).collect())
+    }
+
+    pub async fn list_gateway_in_bbox(&self, min_latitude: f64, min_longitude: f64, max_latitude: f64, max_longitude: f64)
+        -> Result<Vec<GatewaySchema>, Error>
+    {
+        let ids = location::select_device_id_in_bbox(&self.pool, min_latitude, min_longitude, max_latitude, max_longitude).await?;
+        match device::select_device(&self.pool, DeviceKind::Gateway, None, None, Some(&ids), &DeviceFilter { lifecycles: lifecycle_filter(None, &self.options.lifecycle_exclude).as_deref(), ..Default::default() }).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn create_gateway(&self, id: Uuid, type_id: Uuid, serial_number: &str, name: &str, description: Option<&str>)
+        -> Result<Uuid, Error>
+    {
+        let gateway = DeviceInsert { id, gateway_id: id, type_id, serial_number, name, description };
+        device::insert_device(&self.pool, self.options.serial_scope, &gateway)
+        .await
+    }
+
+    pub async fn upsert_gateway_by_sn(&self, id: Uuid, type_id: Uuid, serial_number: &str, name: &str, description: Option<&str>)
+        -> Result<Uuid, Error>
+    {
+        let gateway = DeviceInsert { id, gateway_id: id, type_id, serial_number, name, description };
+        device::upsert_device_by_sn(&self.pool, self.options.serial_scope, &gateway)
+        .await
+    }
+
+    pub async fn update_gateway(&self, id: Uuid, type_id: Option<Uuid>, serial_number: Option<&str>, name: Option<&str>, description: Option<&str>)
+        -> Result<(), Error>
+    {
+        let serial_number = serial_number.map(|value| (value, self.options.serial_scope));
+        device::update_device(&self.pool, DeviceKind::Gateway, id, None, type_id, serial_number, name, description)
+        .await
+    }
+
+    pub async fn update_gateway_lifecycle(&self, id: Uuid, lifecycle: DeviceLifecycle)
+        -> Result<(), Error>
+    {
+        device::update_device_lifecycle(&self.pool, DeviceKind::Gateway, id, lifecycle, Utc::now())
+        .await
+    }
+
+    pub async fn decommission_gateway(&self, id: Uuid)
+        -> Result<(), Error>
+    {
+        device::update_device_lifecycle(&self.pool, DeviceKind::Gateway, id, DeviceLifecycle::Decommissioned, Utc::now())
+        .await
+    }
+
+    pub async fn delete_gateway(&self, id: Uuid)
+        -> Result<(), Error>
+    {
+        device::delete_device(&self.pool, DeviceKind::Gateway, id)
+        .await
+    }
+
+    pub async fn read_device_config(&self, id: i32)
+        -> Result<DeviceConfigSchema, Error>
+    {
+        match device::select_device_config(&self.pool, DeviceKind::Device, Some(id), None).await?
+        .into_iter().next() {
+            Some(value) => Ok(value),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn list_device_config_by_device(&self, device_id: Uuid)
+        -> Result<Vec<DeviceConfigSchema>, Error>
+    {
+        device::select_device_config(&self.pool, DeviceKind::Device, None, Some(device_id))
+        .await
+    }
+
+    pub async fn list_device_config_drift(&self)
+        -> Result<Vec<DeviceConfigSchema>, Error>
+    {
+        device::select_device_config_drift(&self.pool, DeviceKind::Device, None)
+        .await
+    }
+
+    pub async fn list_device_config_drift_by_device(&self, device_id: Uuid)
+        -> Result<Vec<DeviceConfigSchema>, Error>
+    {
+        device::select_device_config_drift(&self.pool, DeviceKind::Device, Some(device_id))
+        .await
+    }
+
+    pub async fn report_device_config(&self, device_id: Uuid, name: &str, value: DataValue, category: &str)
+        -> Result<(), Error>
+    {
+        device::update_device_config_reported(&self.pool, device_id, name, value, category, Utc::now())
+        .await
+    }
+
+    pub async fn create_device_config(&self, device_id: Uuid, name: &str, value: DataValue, category: &str)
+        -> Result<i32, Error>
+    {
+        device::check_device_config(&self.pool, device_id, name, &value, category).await?;
+        device::insert_device_config(&self.pool, device_id, name, value, category)
+        .await
+    }
+
+    pub async fn update_device_config(&self, id: i32, name: Option<&str>, value: Option<DataValue>, category: Option<&str>)
+        -> Result<(), Error>
+    {
+        device::check_device_config_update(&self.pool, id, name, value.as_ref(), category).await?;
+        device::update_device_config(&self.pool, id, name, value, category)
+        .await
+    }
+
+    pub async fn delete_device_config(&self, id: i32)
+        -> Result<(), Error>
+    {
+        device::delete_device_config(&self.pool, id)
+        .await
+    }
+
+    pub async fn read_device_effective_config(&self, device_id: Uuid)
+        -> Result<Vec<EffectiveConfigSchema>, Error>
+    {
+        device::select_device_effective_config(&self.pool, device_id, None)
+        .await
+    }
+
+    pub async fn read_device_effective_config_at(&self, device_id: Uuid, timestamp: DateTime<Utc>)
+        -> Result<Vec<EffectiveConfigSchema>, Error>
+    {
+        device::select_device_effective_config(&self.pool, device_id, Some(timestamp))
+        .await
+    }
+
+    pub async fn read_device_config_at(&self, device_id: Uuid, timestamp: DateTime<Utc>)
+        -> Result<Vec<DeviceConfigSchema>, Error>
+    {
+        match device::select_device_config_history(&self.pool, &[device_id], Some(timestamp)).await {
+            Ok(value) => Ok(value.into_iter().map(|el| el.into_config()).collect()),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_device_config_history(&self, device_id: Uuid)
+        -> Result<Vec<DeviceConfigHistorySchema>, Error>
+    {
+        device::select_device_config_history(&self.pool, &[device_id], None)
+        .await
+    }
+
+    pub async fn read_gateway_config(&self, id: i32)
+        -> Result<GatewayConfigSchema, Error>
+    {
+        match device::select_device_config(&self.pool, DeviceKind::Gateway, Some(id), None).await?
+        .into_iter().next() {
+            Some(value) => Ok(value.into_gateway_config()),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn list_gateway_config_by_gateway(&self, gateway_id: Uuid)
+        -> Result<Vec<GatewayConfigSchema>, Error>
+    {
+        match device::select_device_config(&self.pool, DeviceKind::Gateway, None, Some(gateway_id)).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway_config())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_gateway_config_drift(&self)
+        -> Result<Vec<GatewayConfigSchema>, Error>
+    {
+        match device::select_device_config_drift(&self.pool, DeviceKind::Gateway, None).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_gateway_config())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn report_gateway_config(&self, gateway_id: Uuid, name: &str, value: DataValue, category: &str)
+        -> Result<(), Error>
+    {
+        device::update_device_config_reported(&self.pool, gateway_id, name, value, category, Utc::now())
+        .await
+    }
+
+    pub async fn create_gateway_config(&self, gateway_id: Uuid, name: &str, value: DataValue, category: &str)
+        -> Result<i32, Error>
+    {
+        device::check_device_config(&self.pool, gateway_id, name, &value, category).await?;
+        device::insert_device_config(&self.pool, gateway_id, name, value, category)
+        .await
+    }
+
+    pub async fn update_gateway_config(&self, id: i32, name: Option<&str>, value: Option<DataValue>, category: Option<&str>)
+        -> Result<(), Error>
+    {
+        device::check_device_config_update(&self.pool, id, name, value.as_ref(), category).await?;
+        device::update_device_config(&self.pool, id, name, value, category)
+        .await
+    }
+
+    pub async fn delete_gateway_config(&self, id: i32)
+        -> Result<(), Error>
+    {
+        device::delete_device_config(&self.pool, id)
+        .await
+    }
+
+    pub async fn read_type(&self, id: Uuid)
+        -> Result<TypeSchema, Error>
+    {
+        match types::select_device_type(&self.pool, Some(id), None, None).await?
+        .into_iter().next() {
+            Some(value) => Ok(value),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn list_type_by_ids(&self, ids: &[Uuid])
+        -> Result<Vec<TypeSchema>, Error>
+    {
+        types::select_device_type(&self.pool, None, Some(ids), None)
+        .await
+    }
+
+    pub async fn list_type_by_name(&self, name: &str)
+        -> Result<Vec<TypeSchema>, Error>
+    {
+        types::select_device_type(&self.pool, None, None, Some(name))
+        .await
+    }
+
+    pub async fn list_type_option(&self, name: Option<&str>)
+        -> Result<Vec<TypeSchema>, Error>
+    {
+        types::select_device_type(&self.pool, None, None, name)
+        .await
+    }
+
+    pub async fn create_type(&self, id: Uuid, name: &str, description: Option<&str>)
+        -> Result<Uuid, Error>
+    {
+        types::insert_device_type(&self.pool, id, name, description)
+        .await
+    }
+
+    pub async fn update_type(&self, id: Uuid, name: Option<&str>, description: Option<&str>)
+        -> Result<(), Error>
+    {
+        types::update_device_type(&self.pool, id, name, description)
+        .await
+    }
+
+    pub async fn delete_type(&self, id: Uuid)
+        -> Result<(), Error>
+    {
+        types::delete_device_type(&self.pool, id)
+        .await
+    }
+
+    pub async fn purge_type(&self, id: Uuid, dry_run: bool)
+        -> Result<PurgeReportSchema, Error>
+    {
+        purge::purge_type(&self.pool, &[id], dry_run, false)
+        .await
+    }
+
+    pub async fn purge_type_with_archive(&self, id: Uuid, dry_run: bool)
+        -> Result<PurgeReportSchema, Error>
+    {
+        purge::purge_type(&self.pool, &[id], dry_run, true)
+        .await
+    }
+
+    pub async fn add_type_model(&self, id: Uuid, model_id: Uuid)
+        -> Result<(), Error>
+    {
+        types::insert_device_type_model(&self.pool, id, model_id)
+        .await
+    }
+
+    pub async fn remove_type_model(&self, id: Uuid, model_id: Uuid)
+        -> Result<(), Error>
+    {
+        types::delete_device_type_model(&self.pool, id, model_id)
+        .await
+    }
+
+    pub async fn read_type_config(&self, id: i32)
+        -> Result<TypeConfigSchema, Error>
+    {
+        match types::select_device_type_config(&self.pool, Some(id), None).await?
+        .into_iter().next() {
+            Some(value) => Ok(value),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn list_type_config_by_type(&self, type_id: Uuid)
+        -> Result<Vec<TypeConfigSchema>, Error>
+    {
+        types::select_device_type_config(&self.pool, None, Some(type_id))
+        .await
+    }
+
+    pub async fn create_type_config(&self, type_id: Uuid, name: &str, value: DataValue, category: &str)
+        -> Result<i32, Error>
+    {
+        types::insert_device_type_config(&self.pool, type_id, name, value, category)
+        .await
+    }
+
+    pub async fn update_type_config(&self, id: i32, name: Option<&str>, value: Option<DataValue>, category: Option<&str>)
+        -> Result<(), Error>
+    {
+        types::update_device_type_config(&self.pool, id, name, value, category)
+        .await
+    }
+
+    pub async fn delete_type_config(&self, id: i32)
+        -> Result<(), Error>
+    {
+        types::delete_device_type_config(&self.pool, id)
+        .await
+    }
+
+    pub async fn list_type_config_key(&self, type_id: Uuid)
+        -> Result<Vec<TypeConfigKeySchema>, Error>
+    {
+        types::select_device_type_config_key(&self.pool, type_id)
+        .await
+    }
+
+    pub async fn set_type_config_key(&self, key: &TypeConfigKeySchema)
+        -> Result<(), Error>
+    {
+        types::insert_device_type_config_key(&self.pool, key)
+        .await
+    }
+
+    pub async fn remove_type_config_key(&self, type_id: Uuid, name: &str, category: &str)
+        -> Result<(), Error>
+    {
+        types::delete_device_type_config_key(&self.pool, type_id, name, category)
+        .await
+    }
+
+    pub async fn validate_device_configs(&self, type_id: Uuid)
+        -> Result<Vec<ConfigViolationSchema>, Error>
+    {
+        device::select_device_config_violation(&self.pool, type_id)
+        .await
+    }
+
+    pub async fn read_group_model(&self, id: Uuid)
+        -> Result<GroupModelSchema, Error>
+    {
+        match group::select_group(&self.pool, GroupKind::Model, Some(id), None, None, None).await?
+        .into_iter().next() {
+            Some(value) => Ok(value.into_group_model()),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn list_group_model_by_ids(&self, ids: &[Uuid])
+        -> Result<Vec<GroupModelSchema>, Error>
+    {
+        match group::select_group(&self.pool, GroupKind::Model, None, Some(ids), None, None).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_model())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_group_model_by_name(&self, name: &str)
+        -> Result<Vec<GroupModelSchema>, Error>
+    {
+        match group::select_group(&self.pool, GroupKind::Model, None, None, Some(name), None).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_model())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_group_model_by_category(&self, category: &str)
+        -> Result<Vec<GroupModelSchema>, Error>
     {
         match group::select_group(&self.pool, GroupKind::Model, None, None, None, Some(category)).await {
             Ok(value) => value.into_iter().map(|el| Ok(el.into_group_model())).collect(),
@@ -612,14 +1193,108 @@ impl Resource {
     pub async fn add_group_model_member(&self, id: Uuid, model_id: Uuid)
         -> Result<(), Error>
     {
-        group::insert_group_map(&self.pool, GroupKind::Model, id, model_id)
+        group::insert_group_map(&self.pool, GroupKind::Model, id, model_id, None)
+        .await
+    }
+
+    pub async fn add_group_model_member_at(&self, id: Uuid, model_id: Uuid, valid_from: DateTime<Utc>)
+        -> Result<(), Error>
+    {
+        group::insert_group_map(&self.pool, GroupKind::Model, id, model_id, Some(valid_from))
         .await
     }
 
     pub async fn remove_group_model_member(&self, id: Uuid, model_id: Uuid)
         -> Result<(), Error>
     {
-        group::delete_group_map(&self.pool, GroupKind::Model, id, model_id)
+        group::delete_group_map(&self.pool, GroupKind::Model, id, model_id, None)
+        .await
+    }
+
+    pub async fn remove_group_model_member_at(&self, id: Uuid, model_id: Uuid, valid_to: DateTime<Utc>)
+        -> Result<(), Error>
+    {
+        group::delete_group_map(&self.pool, GroupKind::Model, id, model_id, Some(valid_to))
+        .await
+    }
+
+    pub async fn set_group_model_members(&self, id: Uuid, model_ids: &[Uuid])
+        -> Result<GroupMemberDiff, Error>
+    {
+        group::update_group_map(&self.pool, GroupKind::Model, id, model_ids)
+        .await
+    }
+
+    pub async fn read_group_model_at(&self, id: Uuid, timestamp: DateTime<Utc>)
+        -> Result<GroupModelSchema, Error>
+    {
+        match group::select_group_at(&self.pool, GroupKind::Model, id, timestamp).await? {
+            Some(value) => Ok(value.into_group_model()),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn read_group_model_expanded(&self, id: Uuid)
+        -> Result<GroupModelSchema, Error>
+    {
+        match group::select_group_expanded(&self.pool, GroupKind::Model, id).await? {
+            Some(value) => Ok(value.into_group_model()),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn list_group_model_children(&self, id: Uuid)
+        -> Result<Vec<GroupModelSchema>, Error>
+    {
+        let ids = group::select_group_child(&self.pool, GroupKind::Model, id).await?;
+        match group::select_group(&self.pool, GroupKind::Model, None, Some(&ids), None, None).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_model())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn add_group_model_child(&self, id: Uuid, child_id: Uuid)
+        -> Result<(), Error>
+    {
+        group::insert_group_child(&self.pool, GroupKind::Model, id, child_id)
+        .await
+    }
+
+    pub async fn remove_group_model_child(&self, id: Uuid, child_id: Uuid)
+        -> Result<(), Error>
+    {
+        group::delete_group_child(&self.pool, GroupKind::Model, id, child_id)
+        .await
+    }
+
+    pub async fn read_group_model_rule(&self, id: Uuid)
+        -> Result<GroupRuleSchema, Error>
+    {
+        match group::select_group_rule(&self.pool, GroupKind::Model, &[id]).await?
+        .into_iter().next() {
+            Some((_, value)) => Ok(value),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn set_group_model_rule(&self, id: Uuid, rule: &GroupRuleSchema)
+        -> Result<(), Error>
+    {
+        group::insert_group_rule(&self.pool, GroupKind::Model, id, rule)
+        .await
+    }
+
+    pub async fn remove_group_model_rule(&self, id: Uuid)
+        -> Result<(), Error>
+    {
+        group::delete_group_rule(&self.pool, GroupKind::Model, id)
+        .await
+    }
+
+    pub async fn materialize_group_model(&self, id: Uuid)
+        -> Result<Vec<Uuid>, Error>
+    {
+        group::materialize_group_rule(&self.pool, GroupKind::Model, id)
         .await
     }
 
@@ -693,14 +1368,108 @@ impl Resource {
     pub async fn add_group_device_member(&self, id: Uuid, device_id: Uuid)
         -> Result<(), Error>
     {
-        group::insert_group_map(&self.pool, GroupKind::Device, id, device_id)
+        group::insert_group_map(&self.pool, GroupKind::Device, id, device_id, None)
+        .await
+    }
+
+    pub async fn add_group_device_member_at(&self, id: Uuid, device_id: Uuid, valid_from: DateTime<Utc>)
+        -> Result<(), Error>
+    {
+        group::insert_group_map(&self.pool, GroupKind::Device, id, device_id, Some(valid_from))
         .await
     }
 
     pub async fn remove_group_device_member(&self, id: Uuid, device_id: Uuid)
         -> Result<(), Error>
     {
-        group::delete_group_map(&self.pool, GroupKind::Device, id, device_id)
+        group::delete_group_map(&self.pool, GroupKind::Device, id, device_id, None)
+        .await
+    }
+
+    pub async fn remove_group_device_member_at(&self, id: Uuid, device_id: Uuid, valid_to: DateTime<Utc>)
+        -> Result<(), Error>
+    {
+        group::delete_group_map(&self.pool, GroupKind::Device, id, device_id, Some(valid_to))
+        .await
+    }
+
+    pub async fn set_group_device_members(&self, id: Uuid, device_ids: &[Uuid])
+        -> Result<GroupMemberDiff, Error>
+    {
+        group::update_group_map(&self.pool, GroupKind::Device, id, device_ids)
+        .await
+    }
+
+    pub async fn read_group_device_at(&self, id: Uuid, timestamp: DateTime<Utc>)
+        -> Result<GroupDeviceSchema, Error>
+    {
+        match group::select_group_at(&self.pool, GroupKind::Device, id, timestamp).await? {
+            Some(value) => Ok(value.into_group_device()),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn read_group_device_expanded(&self, id: Uuid)
+        -> Result<GroupDeviceSchema, Error>
+    {
+        match group::select_group_expanded(&self.pool, GroupKind::Device, id).await? {
+            Some(value) => Ok(value.into_group_device()),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn list_group_device_children(&self, id: Uuid)
+        -> Result<Vec<GroupDeviceSchema>, Error>
+    {
+        let ids = group::select_group_child(&self.pool, GroupKind::Device, id).await?;
+        match group::select_group(&self.pool, GroupKind::Device, None, Some(&ids), None, None).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_device())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn add_group_device_child(&self, id: Uuid, child_id: Uuid)
+        -> Result<(), Error>
+    {
+        group::insert_group_child(&self.pool, GroupKind::Device, id, child_id)
+        .await
+    }
+
+    pub async fn remove_group_device_child(&self, id: Uuid, child_id: Uuid)
+        -> Result<(), Error>
+    {
+        group::delete_group_child(&self.pool, GroupKind::Device, id, child_id)
+        .await
+    }
+
+    pub async fn read_group_device_rule(&self, id: Uuid)
+        -> Result<GroupRuleSchema, Error>
+    {
+        match group::select_group_rule(&self.pool, GroupKind::Device, &[id]).await?
+        .into_iter().next() {
+            Some((_, value)) => Ok(value),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn set_group_device_rule(&self, id: Uuid, rule: &GroupRuleSchema)
+        -> Result<(), Error>
+    {
+        group::insert_group_rule(&self.pool, GroupKind::Device, id, rule)
+        .await
+    }
+
+    pub async fn remove_group_device_rule(&self, id: Uuid)
+        -> Result<(), Error>
+    {
+        group::delete_group_rule(&self.pool, GroupKind::Device, id)
+        .await
+    }
+
+    pub async fn materialize_group_device(&self, id: Uuid)
+        -> Result<Vec<Uuid>, Error>
+    {
+        group::materialize_group_rule(&self.pool, GroupKind::Device, id)
         .await
     }
 
@@ -726,62 +1495,156 @@ impl Resource {
     pub async fn list_group_gateway_by_name(&self, name: &str)
         -> Result<Vec<GroupGatewaySchema>, Error>
     {
-        match group::select_group(&self.pool, GroupKind::Gateway, None, None, Some(name), None).await {
-            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_gateway())).collect(),
-            Err(error) => Err(error)
+        match group::select_group(&self.pool, GroupKind::Gateway, None, None, Some(name), None).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_group_gateway_by_category(&self, category: &str)
+        -> Result<Vec<GroupGatewaySchema>, Error>
+    {
+        match group::select_group(&self.pool, GroupKind::Gateway, None, None, None, Some(category)).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn list_group_gateway_option(&self, name: Option<&str>, category: Option<&str>)
+        -> Result<Vec<GroupGatewaySchema>, Error>
+    {
+        match group::select_group(&self.pool, GroupKind::Gateway, None, None, name, category).await {
+            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_gateway())).collect(),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub async fn create_group_gateway(&self, id: Uuid, name: &str, category: &str, description: Option<&str>)
+        -> Result<Uuid, Error>
+    {
+        group::insert_group(&self.pool, GroupKind::Gateway, id, name, category, description)
+        .await
+    }
+
+    pub async fn update_group_gateway(&self, id: Uuid, name: Option<&str>, category: Option<&str>, description: Option<&str>)
+        -> Result<(), Error>
+    {
+        group::update_group(&self.pool, GroupKind::Gateway, id, name, category, description)
+        .await
+    }
+
+    pub async fn delete_group_gateway(&self, id: Uuid)
+        -> Result<(), Error>
+    {
+        group::delete_group(&self.pool, GroupKind::Gateway, id)
+        .await
+    }
+
+    pub async fn add_group_gateway_member(&self, id: Uuid, gateway_id: Uuid)
+        -> Result<(), Error>
+    {
+        group::insert_group_map(&self.pool, GroupKind::Gateway, id, gateway_id, None)
+        .await
+    }
+
+    pub async fn add_group_gateway_member_at(&self, id: Uuid, gateway_id: Uuid, valid_from: DateTime<Utc>)
+        -> Result<(), Error>
+    {
+        group::insert_group_map(&self.pool, GroupKind::Gateway, id, gateway_id, Some(valid_from))
+        .await
+    }
+
+    pub async fn remove_group_gateway_member(&self, id: Uuid, gateway_id: Uuid)
+        -> Result<(), Error>
+    {
+        group::delete_group_map(&self.pool, GroupKind::Gateway, id, gateway_id, None)
+        .await
+    }
+
+    pub async fn remove_group_gateway_member_at(&self, id: Uuid, gateway_id: Uuid, valid_to: DateTime<Utc>)
+        -> Result<(), Error>
+    {
+        group::delete_group_map(&self.pool, GroupKind::Gateway, id, gateway_id, Some(valid_to))
+        .await
+    }
+
+    pub async fn set_group_gateway_members(&self, id: Uuid, gateway_ids: &[Uuid])
+        -> Result<GroupMemberDiff, Error>
+    {
+        group::update_group_map(&self.pool, GroupKind::Gateway, id, gateway_ids)
+        .await
+    }
+
+    pub async fn read_group_gateway_at(&self, id: Uuid, timestamp: DateTime<Utc>)
+        -> Result<GroupGatewaySchema, Error>
+    {
+        match group::select_group_at(&self.pool, GroupKind::Gateway, id, timestamp).await? {
+            Some(value) => Ok(value.into_group_gateway()),
+            None => Err(Error::RowNotFound)
         }
     }
 
-    pub async fn list_group_gateway_by_category(&self, category: &str)
-        -> Result<Vec<GroupGatewaySchema>, Error>
+    pub async fn read_group_gateway_expanded(&self, id: Uuid)
+        -> Result<GroupGatewaySchema, Error>
     {
-        match group::select_group(&self.pool, GroupKind::Gateway, None, None, None, Some(category)).await {
-            Ok(value) => value.into_iter().map(|el| Ok(el.into_group_gateway())).collect(),
-            Err(error) => Err(error)
+        match group::select_group_expanded(&self.pool, GroupKind::Gateway, id).await? {
+            Some(value) => Ok(value.into_group_gateway()),
+            None => Err(Error::RowNotFound)
         }
     }
 
-    pub async fn list_group_gateway_option(&self, name: Option<&str>, category: Option<&str>)
+    pub async fn list_group_gateway_children(&self, id: Uuid)
         -> Result<Vec<GroupGatewaySchema>, Error>
     {
-        match group::select_group(&self.pool, GroupKind::Gateway, None, None, name, category).await {
+        let ids = group::select_group_child(&self.pool, GroupKind::Gateway, id).await?;
+        match group::select_group(&self.pool, GroupKind::Gateway, None, Some(&ids), None, None).await {
             Ok(value) => value.into_iter().map(|el| Ok(el.into_group_gateway())).collect(),
             Err(error) => Err(error)
         }
     }
 
-    pub async fn create_group_gateway(&self, id: Uuid, name: &str, category: &str, description: Option<&str>)
-        -> Result<Uuid, Error>
+    pub async fn add_group_gateway_child(&self, id: Uuid, child_id: Uuid)
+        -> Result<(), Error>
     {
-        group::insert_group(&self.pool, GroupKind::Gateway, id, name, category, description)
+        group::insert_group_child(&self.pool, GroupKind::Gateway, id, child_id)
         .await
     }
 
-    pub async fn update_group_gateway(&self, id: Uuid, name: Option<&str>, category: Option<&str>, description: Option<&str>)
+    pub async fn remove_group_gateway_child(&self, id: Uuid, child_id: Uuid)
         -> Result<(), Error>
     {
-        group::update_group(&self.pool, GroupKind::Gateway, id, name, category, description)
+        group::delete_group_child(&self.pool, GroupKind::Gateway, id, child_id)
         .await
     }
 
-    pub async fn delete_group_gateway(&self, id: Uuid)
+    pub async fn read_group_gateway_rule(&self, id: Uuid)
+        -> Result<GroupRuleSchema, Error>
+    {
+        match group::select_group_rule(&self.pool, GroupKind::Gateway, &[id]).await?
+        .into_iter().next() {
+            Some((_, value)) => Ok(value),
+            None => Err(Error::RowNotFound)
+        }
+    }
+
+    pub async fn set_group_gateway_rule(&self, id: Uuid, rule: &GroupRuleSchema)
         -> Result<(), Error>
     {
-        group::delete_group(&self.pool, GroupKind::Gateway, id)
+        group::insert_group_rule(&self.pool, GroupKind::Gateway, id, rule)
         .await
     }
 
-    pub async fn add_group_gateway_member(&self, id: Uuid, gateway_id: Uuid)
+    pub async fn remove_group_gateway_rule(&self, id: Uuid)
         -> Result<(), Error>
     {
-        group::insert_group_map(&self.pool, GroupKind::Gateway, id, gateway_id)
+        group::delete_group_rule(&self.pool, GroupKind::Gateway, id)
         .await
     }
 
-    pub async fn remove_group_gateway_member(&self, id: Uuid, gateway_id: Uuid)
-        -> Result<(), Error>
+    pub async fn materialize_group_gateway(&self, id: Uuid)
+        -> Result<Vec<Uuid>, Error>
     {
-        group::delete_group_map(&self.pool, GroupKind::Gateway, id, gateway_id)
+        group::materialize_group_rule(&self.pool, GroupKind::Gateway, id)
         .await
     }
 
@@ -823,6 +1686,14 @@ impl Resource {
         .await
     }
 
+    pub async fn list_set_by_label(&self, selector: &LabelSelector)
+        -> Result<Vec<SetSchema>, Error>
+    {
+        let ids = label::select_label_ids(&self.pool, LabelKind::Set, selector).await?;
+        set::select_set(&self.pool, None, Some(&ids), None, None)
+        .await
+    }
+
     pub async fn create_set(&self, id: Uuid, template_id: Uuid, name: &str, description: Option<&str>)
         -> Result<Uuid, Error>
     {
@@ -830,6 +1701,18 @@ impl Resource {
         .await
     }
 
+    pub async fn create_set_from_template(&self, id: Uuid, template_id: Uuid, name: &str, description: Option<&str>, bindings: &[SetTemplateBinding], gateway_id: Option<Uuid>)
+        -> Result<Uuid, Error>
+    {
+        let members = set::select_set_template_binding(&self.pool, template_id, bindings, gateway_id).await?;
+        let device_ids: Vec<Uuid> = members.iter().map(|member| member.device_id).collect();
+        let model_ids: Vec<Uuid> = members.iter().map(|member| member.model_id).collect();
+        let incompatible = types::check_device_model(&self.pool, self.options.compatibility, &device_ids, &model_ids).await?;
+        let id = set::insert_set_from_template(&self.pool, id, template_id, name, description, &members).await?;
+        types::log_device_model(&self.pool, &incompatible).await?;
+        Ok(id)
+    }
+
     pub async fn update_set(&self, id: Uuid, template_id: Option<Uuid>, name: Option<&str>, description: Option<&str>)
         -> Result<(), Error>
     {
@@ -844,13 +1727,35 @@ impl Resource {
         .await
     }
 
-    pub async fn add_set_member(&self, id: Uuid, device_id: Uuid, model_id: Uuid, data_index: &[u8])
+    pub async fn list_set_label(&self, id: Uuid)
+        -> Result<Vec<LabelSchema>, Error>
+    {
+        label::select_label(&self.pool, LabelKind::Set, id)
+        .await
+    }
+
+    pub async fn add_set_label(&self, id: Uuid, key: &str, value: &str)
         -> Result<(), Error>
     {
-        set::insert_set_member(&self.pool, id, device_id, model_id, data_index)
+        label::insert_label(&self.pool, LabelKind::Set, id, key, value)
         .await
     }
 
+    pub async fn remove_set_label(&self, id: Uuid, key: &str)
+        -> Result<(), Error>
+    {
+        label::delete_label(&self.pool, LabelKind::Set, id, key)
+        .await
+    }
+
+    pub async fn add_set_member(&self, id: Uuid, device_id: Uuid, model_id: Uuid, data_index: &[u8])
+        -> Result<(), Error>
+    {
+        let incompatible = types::check_device_model(&self.pool, self.options.compatibility, &[device_id], &[model_id]).await?;
+        set::insert_set_member(&self.pool, id, device_id, model_id, data_index).await?;
+        types::log_device_model(&self.pool, &incompatible).await
+    }
+
     pub async fn remove_set_member(&self, id: Uuid, device_id: Uuid, model_id: Uuid)
         -> Result<(), Error>
     {
@@ -1025,15 +1930,20 @@ impl Resource {
     pub async fn create_slice(&self, device_id: Uuid, model_id: Uuid, timestamp_begin: DateTime<Utc>, timestamp_end: DateTime<Utc>, name: &str, description: Option<&str>)
         -> Result<i32, Error>
     {
-        slice::insert_slice(&self.pool, device_id, model_id, timestamp_begin, timestamp_end, name, description)
-        .await
+        let incompatible = types::check_device_model(&self.pool, self.options.compatibility, &[device_id], &[model_id]).await?;
+        let id = slice::insert_slice(&self.pool, device_id, model_id, timestamp_begin, timestamp_end, name, description).await?;
+        types::log_device_model(&self.pool, &incompatible).await?;
+        Ok(id)
     }
 
     pub async fn update_slice(&self, id: i32, timestamp_begin: Option<DateTime<Utc>>, timestamp_end: Option<DateTime<Utc>>, name: Option<&str>, description: Option<&str>)
         -> Result<(), Error>
     {
-        slice::update_slice(&self.pool, id, timestamp_begin, timestamp_end, name, description)
-        .await
+        let slice = slice::select_slice(&self.pool, SliceSelector::None, Some(&[id]), None, None, None).await?
+            .into_iter().next().ok_or(Error::RowNotFound)?;
+        let incompatible = types::check_device_model(&self.pool, self.options.compatibility, &[slice.device_id], &[slice.model_id]).await?;
+        slice::update_slice(&self.pool, id, timestamp_begin, timestamp_end, name, description).await?;
+        types::log_device_model(&self.pool, &incompatible).await
     }
 
     pub async fn delete_slice(&self, id: i32)
@@ -1207,6 +2117,134 @@ impl Resource {
         .await
     }
 
+    pub async fn list_data_by_group_time(&self, group_device_id: Uuid, group_model_id: Uuid, timestamp: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DataSchema>, Error>
+    {
+        let selector = DataSelector::Time(timestamp);
+        data::select_data_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_data_by_group_latest(&self, group_device_id: Uuid, group_model_id: Uuid, latest: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DataSchema>, Error>
+    {
+        let selector = DataSelector::Latest(latest);
+        data::select_data_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_data_by_group_range(&self, group_device_id: Uuid, group_model_id: Uuid, begin: DateTime<Utc>, end: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DataSchema>, Error>
+    {
+        let selector = DataSelector::Range(begin, end);
+        data::select_data_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_data_by_group_number_before(&self, group_device_id: Uuid, group_model_id: Uuid, before: DateTime<Utc>, number: usize, tag: Option<i16>)
+        -> Result<Vec<DataSchema>, Error>
+    {
+        let selector = DataSelector::NumberBefore(before, number);
+        data::select_data_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_data_by_group_number_after(&self, group_device_id: Uuid, group_model_id: Uuid, after: DateTime<Utc>, number: usize, tag: Option<i16>)
+        -> Result<Vec<DataSchema>, Error>
+    {
+        let selector = DataSelector::NumberAfter(after, number);
+        data::select_data_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn read_data_calibrated(&self, device_id: Uuid, model_id: Uuid, timestamp: DateTime<Utc>, tag: Option<i16>)
+        -> Result<DataCalibratedSchema, Error>
+    {
+        let selector = DataSelector::Time(timestamp);
+        calibration::select_data_calibrated(&self.pool, selector, &[device_id], &[model_id], tag).await?.into_iter().next()
+            .ok_or(Error::RowNotFound)
+    }
+
+    pub async fn list_data_by_time_calibrated(&self, device_id: Uuid, model_id: Uuid, timestamp: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::Time(timestamp);
+        calibration::select_data_calibrated(&self.pool, selector, &[device_id], &[model_id], tag)
+        .await
+    }
+
+    pub async fn list_data_by_latest_calibrated(&self, device_id: Uuid, model_id: Uuid, latest: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::Latest(latest);
+        calibration::select_data_calibrated(&self.pool, selector, &[device_id], &[model_id], tag)
+        .await
+    }
+
+    pub async fn list_data_by_range_calibrated(&self, device_id: Uuid, model_id: Uuid, begin: DateTime<Utc>, end: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::Range(begin, end);
+        calibration::select_data_calibrated(&self.pool, selector, &[device_id], &[model_id], tag)
+        .await
+    }
+
+    pub async fn list_data_by_number_before_calibrated(&self, device_id: Uuid, model_id: Uuid, before: DateTime<Utc>, number: usize, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::NumberBefore(before, number);
+        calibration::select_data_calibrated(&self.pool, selector, &[device_id], &[model_id], tag)
+        .await
+    }
+
+    pub async fn list_data_by_number_after_calibrated(&self, device_id: Uuid, model_id: Uuid, after: DateTime<Utc>, number: usize, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::NumberAfter(after, number);
+        calibration::select_data_calibrated(&self.pool, selector, &[device_id], &[model_id], tag)
+        .await
+    }
+
+    pub async fn list_data_group_by_time_calibrated(&self, device_ids: &[Uuid], model_ids: &[Uuid], timestamp: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::Time(timestamp);
+        calibration::select_data_calibrated(&self.pool, selector, device_ids, model_ids, tag)
+        .await
+    }
+
+    pub async fn list_data_group_by_latest_calibrated(&self, device_ids: &[Uuid], model_ids: &[Uuid], latest: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::Latest(latest);
+        calibration::select_data_calibrated(&self.pool, selector, device_ids, model_ids, tag)
+        .await
+    }
+
+    pub async fn list_data_group_by_range_calibrated(&self, device_ids: &[Uuid], model_ids: &[Uuid], begin: DateTime<Utc>, end: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::Range(begin, end);
+        calibration::select_data_calibrated(&self.pool, selector, device_ids, model_ids, tag)
+        .await
+    }
+
+    pub async fn list_data_group_by_number_before_calibrated(&self, device_ids: &[Uuid], model_ids: &[Uuid], before: DateTime<Utc>, number: usize, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::NumberBefore(before, number);
+        calibration::select_data_calibrated(&self.pool, selector, device_ids, model_ids, tag)
+        .await
+    }
+
+    pub async fn list_data_group_by_number_after_calibrated(&self, device_ids: &[Uuid], model_ids: &[Uuid], after: DateTime<Utc>, number: usize, tag: Option<i16>)
+        -> Result<Vec<DataCalibratedSchema>, Error>
+    {
+        let selector = DataSelector::NumberAfter(after, number);
+        calibration::select_data_calibrated(&self.pool, selector, device_ids, model_ids, tag)
+        .await
+    }
+
     pub async fn read_data_set(&self, set_id: Uuid, timestamp: DateTime<Utc>, tag: Option<i16>)
         -> Result<DataSetSchema, Error>
     {
@@ -1243,14 +2281,36 @@ impl Resource {
     pub async fn create_data(&self, device_id: Uuid, model_id: Uuid, timestamp: DateTime<Utc>, data: &[DataValue], tag: Option<i16>)
         -> Result<(), Error>
     {
-        data::insert_data(&self.pool, device_id, model_id, timestamp, data, tag)
+        let check = IngestCheck {
+            incompatible: types::check_device_model(&self.pool, self.options.compatibility, &[device_id], &[model_id]).await?,
+            thresholds: alarm::select_threshold_history(&self.pool, self.options.alarm, &[device_id], &[model_id]).await?
+        };
+        data::insert_data(&self.pool, device_id, model_id, timestamp, data, tag, &check)
         .await
     }
 
     pub async fn create_data_multiple(&self, device_ids: &[Uuid], model_ids: &[Uuid], timestamps: &[DateTime<Utc>], data: &[&[DataValue]], tags: Option<&[i16]>)
         -> Result<(), Error>
     {
-        data::insert_data_multiple(&self.pool, device_ids, model_ids, timestamps, data, tags)
+        let check = IngestCheck {
+            incompatible: types::check_device_model(&self.pool, self.options.compatibility, device_ids, model_ids).await?,
+            thresholds: alarm::select_threshold_history(&self.pool, self.options.alarm, device_ids, model_ids).await?
+        };
+        data::insert_data_multiple(&self.pool, device_ids, model_ids, timestamps, data, tags, &check)
+        .await
+    }
+
+    pub async fn materialize_derived_data(&self, device_id: Uuid, model_id: Uuid, begin: DateTime<Utc>, end: DateTime<Utc>, tag: i16)
+        -> Result<usize, Error>
+    {
+        derived::materialize_derived(&self.pool, device_id, model_id, begin, end, tag)
+        .await
+    }
+
+    pub async fn aggregate_group_data(&self, aggregate: &GroupAggregate, begin: DateTime<Utc>, end: DateTime<Utc>)
+        -> Result<usize, Error>
+    {
+        aggregate::aggregate_group_data(&self.pool, aggregate, begin, end)
         .await
     }
 
@@ -1309,6 +2369,30 @@ impl Resource {
         .await
     }
 
+    pub async fn read_data_timestamp_by_group(&self, group_device_id: Uuid, group_model_id: Uuid, timestamp: DateTime<Utc>, tag: Option<i16>)
+        -> Result<DateTime<Utc>, Error>
+    {
+        let selector = DataSelector::Time(timestamp);
+        data::select_timestamp_group(&self.pool, selector, group_device_id, group_model_id, tag).await?.into_iter().next()
+            .ok_or(Error::RowNotFound)
+    }
+
+    pub async fn list_data_timestamp_by_group_latest(&self, group_device_id: Uuid, group_model_id: Uuid, latest: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DateTime<Utc>>, Error>
+    {
+        let selector = DataSelector::Latest(latest);
+        data::select_timestamp_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_data_timestamp_by_group_range(&self, group_device_id: Uuid, group_model_id: Uuid, begin: DateTime<Utc>, end: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<DateTime<Utc>>, Error>
+    {
+        let selector = DataSelector::Range(begin, end);
+        data::select_timestamp_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
     pub async fn count_data(&self, device_id: Uuid, model_id: Uuid, tag: Option<i16>)
         -> Result<usize, Error>
     {
@@ -1351,6 +2435,27 @@ impl Resource {
         .await
     }
 
+    pub async fn count_data_by_group(&self, group_device_id: Uuid, group_model_id: Uuid, tag: Option<i16>)
+        -> Result<usize, Error>
+    {
+        data::count_data_group(&self.pool, DataSelector::Time(DateTime::default()), group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn count_data_by_group_latest(&self, group_device_id: Uuid, group_model_id: Uuid, latest: DateTime<Utc>, tag: Option<i16>)
+        -> Result<usize, Error>
+    {
+        data::count_data_group(&self.pool, DataSelector::Latest(latest), group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn count_data_by_group_range(&self, group_device_id: Uuid, group_model_id: Uuid, begin: DateTime<Utc>, end: DateTime<Utc>, tag: Option<i16>)
+        -> Result<usize, Error>
+    {
+        data::count_data_group(&self.pool, DataSelector::Range(begin, end), group_device_id, group_model_id, tag)
+        .await
+    }
+
     pub async fn read_buffer(&self, id: i32)
         -> Result<BufferSchema, Error>
     {
@@ -1501,6 +2606,62 @@ impl Resource {
         .await
     }
 
+    pub async fn list_buffer_by_group_time(&self, group_device_id: Uuid, group_model_id: Uuid, timestamp: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<BufferSchema>, Error>
+    {
+        let selector = BufferSelector::Time(timestamp);
+        buffer::select_buffer_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_buffer_by_group_latest(&self, group_device_id: Uuid, group_model_id: Uuid, latest: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<BufferSchema>, Error>
+    {
+        let selector = BufferSelector::Latest(latest);
+        buffer::select_buffer_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_buffer_by_group_range(&self, group_device_id: Uuid, group_model_id: Uuid, begin: DateTime<Utc>, end: DateTime<Utc>, tag: Option<i16>)
+        -> Result<Vec<BufferSchema>, Error>
+    {
+        let selector = BufferSelector::Range(begin, end);
+        buffer::select_buffer_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_buffer_by_group_number_before(&self, group_device_id: Uuid, group_model_id: Uuid, before: DateTime<Utc>, number: usize, tag: Option<i16>)
+        -> Result<Vec<BufferSchema>, Error>
+    {
+        let selector = BufferSelector::NumberBefore(before, number);
+        buffer::select_buffer_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_buffer_by_group_number_after(&self, group_device_id: Uuid, group_model_id: Uuid, after: DateTime<Utc>, number: usize, tag: Option<i16>)
+        -> Result<Vec<BufferSchema>, Error>
+    {
+        let selector = BufferSelector::NumberAfter(after, number);
+        buffer::select_buffer_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_buffer_by_group_first(&self, number: usize, group_device_id: Uuid, group_model_id: Uuid, tag: Option<i16>)
+        -> Result<Vec<BufferSchema>, Error>
+    {
+        let selector = BufferSelector::First(number, 0);
+        buffer::select_buffer_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
+    pub async fn list_buffer_by_group_last(&self, number: usize, group_device_id: Uuid, group_model_id: Uuid, tag: Option<i16>)
+        -> Result<Vec<BufferSchema>, Error>
+    {
+        let selector = BufferSelector::Last(number, 0);
+        buffer::select_buffer_group(&self.pool, selector, group_device_id, group_model_id, tag)
+        .await
+    }
+
     pub async fn read_buffer_group_first(&self, device_ids: Option<&[Uuid]>, model_ids: Option<&[Uuid]>, tag: Option<i16>)
         -> Result<BufferSchema, Error>
     {
@@ -1585,14 +2746,22 @@ impl Resource {
     pub async fn create_buffer(&self, device_id: Uuid, model_id: Uuid, timestamp: DateTime<Utc>, data: &[DataValue], tag: Option<i16>)
         -> Result<i32, Error>
     {
-        buffer::insert_buffer(&self.pool, device_id, model_id, timestamp, data, tag)
+        let check = IngestCheck {
+            incompatible: types::check_device_model(&self.pool, self.options.compatibility, &[device_id], &[model_id]).await?,
+            thresholds: alarm::select_threshold_history(&self.pool, self.options.alarm, &[device_id], &[model_id]).await?
+        };
+        buffer::insert_buffer(&self.pool, device_id, model_id, timestamp, data, tag, &check)
         .await
     }
 
     pub async fn create_buffer_multiple(&self, device_ids: &[Uuid], model_ids: &[Uuid], timestamps: &[DateTime<Utc>], data: &[&[DataValue]], tags: Option<&[i16]>)
         -> Result<Vec<i32>, Error>
     {
-        buffer::insert_buffer_multiple(&self.pool, device_ids, model_ids, timestamps, data, tags)
+        let check = IngestCheck {
+            incompatible: types::check_device_model(&self.pool, self.options.compatibility, device_ids, model_ids).await?,
+            thresholds: alarm::select_threshold_history(&self.pool, self.options.alarm, device_ids, model_ids).await?
+        };
+        buffer::insert_buffer_multiple(&self.pool, device_ids, model_ids, timestamps, data, tags, &check)
         .await
     }
 
@@ -1913,8 +3082,12 @@ impl Resource {
     pub async fn create_log(&self, timestamp: DateTime<Utc>, device_id: Option<Uuid>, model_id: Option<Uuid>, value: DataValue, tag: Option<i16>)
         -> Result<i32, Error>
     {
-        log::insert_log(&self.pool, timestamp, device_id, model_id, value, tag)
-        .await
+        let id = log::insert_log(&self.pool, timestamp, device_id, model_id, value, tag)
+            .await?;
+        if let Some(device_id) = device_id {
+            status::update_device_status(&self.pool, StatusKind::Log, &[device_id], &[timestamp]).await?;
+        }
+        Ok(id)
     }
 
     pub async fn update_log(&self, id: i32, value: Option<DataValue>, tag: Option<i16>)
@@ -1943,4 +3116,39 @@ impl Resource {
         log::delete_log(&self.pool, None, Some(timestamp), device_id, model_id, tag).await
     }
 
+    pub async fn list_alarm_active_by_device(&self, device_id: Uuid)
+        -> Result<Vec<AlarmSchema>, Error>
+    {
+        alarm::select_alarm(&self.pool, Some(&[device_id]), None)
+        .await
+    }
+
+    pub async fn list_alarm_active_by_device_ids(&self, device_ids: &[Uuid])
+        -> Result<Vec<AlarmSchema>, Error>
+    {
+        alarm::select_alarm(&self.pool, Some(device_ids), None)
+        .await
+    }
+
+    pub async fn list_alarm_active(&self)
+        -> Result<Vec<AlarmSchema>, Error>
+    {
+        alarm::select_alarm(&self.pool, None, None)
+        .await
+    }
+
+    pub async fn export_bundle(&self, model_ids: &[Uuid], type_ids: &[Uuid], template_ids: &[Uuid], group_model_ids: &[Uuid])
+        -> Result<BundleSchema, Error>
+    {
+        bundle::select_bundle(&self.pool, model_ids, type_ids, template_ids, group_model_ids)
+        .await
+    }
+
+    pub async fn import_bundle(&self, bundle: &BundleSchema)
+        -> Result<Vec<BundleDiffSchema>, Error>
+    {
+        bundle::import_bundle(&self.pool, bundle)
+        .await
+    }
+
 }
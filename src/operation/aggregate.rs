@@ -0,0 +1,113 @@
+use sqlx::{Pool, Row, Error};
+use sqlx::postgres::{Postgres, PgRow};
+use sqlx::types::chrono::{DateTime, Utc};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, OnConflict};
+use sea_query_binder::SqlxBinder;
+use uuid::Uuid;
+
+use crate::schema::value::{DataValue, ArrayDataValue};
+use crate::schema::data::Data;
+use crate::schema::group::GroupKind;
+use crate::schema::aggregate::{GroupAggregate, aggregate_data};
+use crate::operation::data::select_data_types;
+use crate::operation::group::group_member_condition;
+use crate::utility::tag as Tag;
+use super::{MODEL_NOT_EXISTS, DATA_TYPE_UNMATCH, AGGREGATE_FUNCTION_UNMATCH, DATA_INSERT_BATCH};
+
+pub(crate) async fn aggregate_group_data(pool: &Pool<Postgres>,
+    aggregate: &GroupAggregate,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>
+) -> Result<usize, Error>
+{
+    let GroupAggregate { group_device_id, model_id, output_device_id, period, .. } = *aggregate;
+    let functions = &aggregate.functions;
+    let types = select_data_types(pool, &[model_id]).await?.into_iter().next()
+        .ok_or(Error::InvalidArgument(MODEL_NOT_EXISTS.to_string()))?;
+    if functions.len() != types.len() {
+        return Err(Error::InvalidArgument(AGGREGATE_FUNCTION_UNMATCH.to_string()));
+    }
+    // range is extended to whole periods so a partial range doesn't overwrite a complete aggregate
+    let begin = period.truncate(begin);
+    let end = period.next(end);
+
+    // default tagged data of all group members except the output device
+    let (sql, values) = Query::select()
+        .columns([Data::Timestamp, Data::DeviceId, Data::Data])
+        .from(Data::Table)
+        .and_where(group_member_condition(pool, GroupKind::Device, group_device_id, (Data::Table, Data::DeviceId), (Data::Table, Data::Timestamp)).await?)
+        .and_where(Expr::col(Data::DeviceId).ne(output_device_id))
+        .and_where(Expr::col(Data::ModelId).eq(model_id))
+        .and_where(Expr::col(Data::Tag).eq(Tag::DEFAULT))
+        .and_where(Expr::col(Data::Timestamp).gte(begin))
+        .and_where(Expr::col(Data::Timestamp).lt(end))
+        .order_by(Data::Timestamp, Order::Asc)
+        .order_by(Data::DeviceId, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let mut rows: Vec<(DateTime<Utc>, Uuid, Vec<DataValue>)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes: Vec<u8> = row.get(2);
+            (period.truncate(row.get(0)), row.get(1), ArrayDataValue::from_bytes(&bytes, &types).to_vec())
+        })
+        .fetch_all(pool)
+        .await?;
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    // sort by period then device so every period and every device within it is a contiguous chunk
+    rows.sort_by_key(|row| (row.0, row.1));
+    let mut series: Vec<(DateTime<Utc>, Vec<DataValue>)> = Vec::new();
+    for chunk in rows.chunk_by(|a, b| a.0 == b.0) {
+        let devices: Vec<Vec<Vec<DataValue>>> = chunk.chunk_by(|a, b| a.1 == b.1)
+            .map(|device| device.iter().map(|(_, _, data)| data.clone()).collect())
+            .collect();
+        series.push((chunk[0].0, aggregate_data(&devices, functions, &types)));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    for batch in series.chunks(DATA_INSERT_BATCH) {
+        let mut stmt = Query::insert()
+            .into_table(Data::Table)
+            .columns([
+                Data::DeviceId,
+                Data::ModelId,
+                Data::Timestamp,
+                Data::Tag,
+                Data::Data
+            ])
+            .to_owned();
+        for (timestamp, data) in batch {
+            let bytes = match ArrayDataValue::from_vec(data).convert(&types) {
+                Some(value) => value.to_bytes(),
+                None => return Err(Error::InvalidArgument(DATA_TYPE_UNMATCH.to_string()))
+            };
+            stmt = stmt.values([
+                output_device_id.into(),
+                model_id.into(),
+                (*timestamp).into(),
+                period.tag().into(),
+                bytes.into()
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .to_owned();
+        }
+        // aggregate again overwrite previous values
+        let (sql, values) = stmt
+            .on_conflict(OnConflict::columns([Data::Timestamp, Data::ModelId, Data::DeviceId, Data::Tag])
+                .update_column(Data::Data)
+                .to_owned()
+            )
+            .build_sqlx(PostgresQueryBuilder);
+
+        sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(series.len())
+}
@@ -0,0 +1,218 @@
+use sqlx::{Pool, Row, Error};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
+use sqlx::types::chrono::{DateTime, Utc};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, OnConflict};
+use sea_query_binder::SqlxBinder;
+use uuid::Uuid;
+
+use crate::schema::value::DataValue;
+use crate::schema::device::EffectiveConfigHistory;
+use crate::schema::alarm::{Alarm, AlarmSchema, AlarmKind, AlarmTransition, Threshold, ThresholdHistory};
+use crate::operation::model::select_model_config_history;
+use crate::operation::device::select_device_effective_history;
+use crate::operation::log::write_log;
+use crate::utility::tag as Tag;
+
+pub(crate) async fn select_alarm(pool: &Pool<Postgres>,
+    device_ids: Option<&[Uuid]>,
+    model_id: Option<Uuid>
+) -> Result<Vec<AlarmSchema>, Error>
+{
+    let mut stmt = Query::select()
+        .columns([
+            Alarm::DeviceId,
+            Alarm::ModelId,
+            Alarm::Index,
+            Alarm::Kind,
+            Alarm::Value,
+            Alarm::Timestamp
+        ])
+        .from(Alarm::Table)
+        .to_owned();
+
+    if let Some(ids) = device_ids {
+        stmt = stmt.and_where(Expr::col(Alarm::DeviceId).is_in(ids.to_vec())).to_owned();
+    }
+    if let Some(id) = model_id {
+        stmt = stmt.and_where(Expr::col(Alarm::ModelId).eq(id)).to_owned();
+    }
+    let (sql, values) = stmt
+        .order_by(Alarm::DeviceId, Order::Asc)
+        .order_by(Alarm::ModelId, Order::Asc)
+        .order_by(Alarm::Index, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            AlarmSchema {
+                device_id: row.get(0),
+                model_id: row.get(1),
+                index: row.get::<i16,_>(2) as u8,
+                kind: AlarmKind::from(row.get::<i16,_>(3)),
+                value: row.get(4),
+                timestamp: row.get(5)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+// load threshold configs in every version once for each device and model pair of a write
+pub(crate) async fn select_threshold_history(pool: &Pool<Postgres>,
+    alarm: bool,
+    device_ids: &[Uuid],
+    model_ids: &[Uuid]
+) -> Result<Vec<ThresholdHistory>, Error>
+{
+    if !alarm {
+        return Ok(Vec::new());
+    }
+    let mut pairs: Vec<(Uuid, Uuid)> = device_ids.iter().copied().zip(model_ids.iter().copied()).collect();
+    pairs.sort();
+    pairs.dedup();
+    let mut model_ids_unique = model_ids.to_vec();
+    model_ids_unique.sort();
+    model_ids_unique.dedup();
+    let mut device_ids_unique = device_ids.to_vec();
+    device_ids_unique.sort();
+    device_ids_unique.dedup();
+
+    let model_configs = select_model_config_history(pool, &model_ids_unique, None).await?;
+    let mut device_configs: Vec<EffectiveConfigHistory> = Vec::new();
+    for device_id in device_ids_unique {
+        device_configs.push(select_device_effective_history(pool, device_id).await?);
+    }
+    let histories = pairs.into_iter()
+        .map(|(device_id, model_id)| ThresholdHistory {
+            device_id,
+            model_id,
+            model_configs: model_configs.iter()
+                .filter(|cfg| cfg.model_id == model_id && cfg.category == Threshold::CATEGORY)
+                .cloned()
+                .collect(),
+            device_configs: device_configs.iter()
+                .find(|history| history.device_id == device_id)
+                .cloned()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    Ok(histories)
+}
+
+pub(crate) async fn evaluate_alarm(conn: &mut PgConnection,
+    histories: &[ThresholdHistory],
+    device_id: Uuid,
+    model_id: Uuid,
+    timestamp: DateTime<Utc>,
+    data: &[DataValue]
+) -> Result<(), Error>
+{
+    // use thresholds which were in effect at the data timestamp
+    let thresholds = match histories.iter().find(|history| history.device_id == device_id && history.model_id == model_id) {
+        Some(history) => history.at(timestamp, data.len()),
+        None => return Ok(())
+    };
+    if thresholds.iter().all(|threshold| threshold.is_none()) {
+        return Ok(());
+    }
+    let (sql, values) = Query::select()
+        .columns([Alarm::Index, Alarm::Kind])
+        .from(Alarm::Table)
+        .and_where(Expr::col(Alarm::DeviceId).eq(device_id))
+        .and_where(Expr::col(Alarm::ModelId).eq(model_id))
+        .build_sqlx(PostgresQueryBuilder);
+    let alarms: Vec<(usize, AlarmKind)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get::<i16,_>(0) as usize, AlarmKind::from(row.get::<i16,_>(1))))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for (index, (value, threshold)) in data.iter().zip(thresholds.iter()).enumerate() {
+        let (value, threshold) = match (value.to_f64(), threshold) {
+            (Some(value), Some(threshold)) => (value, threshold),
+            _ => continue
+        };
+        let active = alarms.iter().find(|alarm| alarm.0 == index).map(|alarm| alarm.1);
+        match threshold.evaluate(value, active) {
+            AlarmTransition::Raise(kind) => {
+                insert_alarm(conn, device_id, model_id, index, kind, value, timestamp).await?;
+                let (name, limit) = match kind {
+                    AlarmKind::Upper => (Threshold::UPPER, threshold.upper),
+                    AlarmKind::Lower => (Threshold::LOWER, threshold.lower)
+                };
+                let message = format!("field {} value {} crosses {} {}", index, value, name, limit.unwrap_or_default());
+                write_log(conn, timestamp, Some(device_id), Some(model_id), DataValue::String(message), Some(Tag::ERROR_THRESHOLD)).await?;
+            },
+            AlarmTransition::Clear => {
+                delete_alarm(conn, device_id, model_id, index).await?;
+            },
+            AlarmTransition::None => {}
+        }
+    }
+
+    Ok(())
+}
+
+async fn insert_alarm(conn: &mut PgConnection,
+    device_id: Uuid,
+    model_id: Uuid,
+    index: usize,
+    kind: AlarmKind,
+    value: f64,
+    timestamp: DateTime<Utc>
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::insert()
+        .into_table(Alarm::Table)
+        .columns([
+            Alarm::DeviceId,
+            Alarm::ModelId,
+            Alarm::Index,
+            Alarm::Kind,
+            Alarm::Value,
+            Alarm::Timestamp
+        ])
+        .values([
+            device_id.into(),
+            model_id.into(),
+            (index as i16).into(),
+            i16::from(kind).into(),
+            value.into(),
+            timestamp.into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        // alarm of the opposite threshold which was active on the field is replaced
+        .on_conflict(OnConflict::columns([Alarm::DeviceId, Alarm::ModelId, Alarm::Index])
+            .update_columns([Alarm::Kind, Alarm::Value, Alarm::Timestamp])
+            .to_owned()
+        )
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn delete_alarm(conn: &mut PgConnection,
+    device_id: Uuid,
+    model_id: Uuid,
+    index: usize
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::delete()
+        .from_table(Alarm::Table)
+        .and_where(Expr::col(Alarm::DeviceId).eq(device_id))
+        .and_where(Expr::col(Alarm::ModelId).eq(model_id))
+        .and_where(Expr::col(Alarm::Index).eq(index as i16))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
@@ -9,8 +9,11 @@ use crate::schema::value::{DataType, DataValue, ArrayDataValue};
 use crate::schema::model::Model;
 use crate::schema::buffer::{DataBuffer, BufferSchema, BufferSetSchema};
 use crate::schema::set::SetMap;
-use crate::operation::data::select_data_types;
-use crate::operation::model::{select_tag_members, select_tag_members_set};
+use crate::operation::data::{select_data_types, IngestCheck, write_ingest};
+use crate::operation::status::StatusKind;
+use crate::operation::model::{select_tag_members, select_tag_members_set, select_tag_members_group};
+use crate::operation::group::group_member_condition;
+use crate::schema::group::GroupKind;
 use crate::utility::tag as Tag;
 use super::{EMPTY_LENGTH_UNMATCH, DATA_TYPE_UNMATCH, MODEL_NOT_EXISTS};
 
@@ -144,6 +147,103 @@ pub(crate) async fn select_buffer(pool: &Pool<Postgres>,
     Ok(rows)
 }
 
+pub(crate) async fn select_buffer_group(pool: &Pool<Postgres>,
+    selector: BufferSelector,
+    group_device_id: Uuid,
+    group_model_id: Uuid,
+    tag: Option<i16>
+) -> Result<Vec<BufferSchema>, Error>
+{
+    let mut stmt = Query::select()
+        .columns([
+            (DataBuffer::Table, DataBuffer::Id),
+            (DataBuffer::Table, DataBuffer::DeviceId),
+            (DataBuffer::Table, DataBuffer::ModelId),
+            (DataBuffer::Table, DataBuffer::Timestamp),
+            (DataBuffer::Table, DataBuffer::Tag),
+            (DataBuffer::Table, DataBuffer::Data)
+        ])
+        .column((Model::Table, Model::DataType))
+        .from(DataBuffer::Table)
+        .inner_join(Model::Table, 
+            Expr::col((DataBuffer::Table, DataBuffer::ModelId))
+            .equals((Model::Table, Model::ModelId)))
+        .and_where(group_member_condition(pool, GroupKind::Device, group_device_id, (DataBuffer::Table, DataBuffer::DeviceId), (DataBuffer::Table, DataBuffer::Timestamp)).await?)
+        .and_where(group_member_condition(pool, GroupKind::Model, group_model_id, (DataBuffer::Table, DataBuffer::ModelId), (DataBuffer::Table, DataBuffer::Timestamp)).await?)
+        .to_owned();
+
+    if let Some(t) = tag {
+        let tags = select_tag_members_group(pool, group_model_id, t).await?;
+        stmt = stmt.and_where(Expr::col((DataBuffer::Table, DataBuffer::Tag)).is_in(tags)).to_owned();
+    }
+
+    match selector {
+        BufferSelector::Time(timestamp) => {
+            stmt = stmt.and_where(Expr::col((DataBuffer::Table, DataBuffer::Timestamp)).eq(timestamp)).to_owned();
+        },
+        BufferSelector::Latest(last) => {
+            stmt = stmt.and_where(Expr::col((DataBuffer::Table, DataBuffer::Timestamp)).gt(last))
+                .order_by((DataBuffer::Table, DataBuffer::Timestamp), Order::Asc)
+                .to_owned();
+        },
+        BufferSelector::Range(begin, end) => {
+            stmt = stmt
+                .and_where(Expr::col((DataBuffer::Table, DataBuffer::Timestamp)).gte(begin))
+                .and_where(Expr::col((DataBuffer::Table, DataBuffer::Timestamp)).lte(end))
+                .order_by((DataBuffer::Table, DataBuffer::Timestamp), Order::Asc)
+                .to_owned();
+        },
+        BufferSelector::NumberBefore(timestamp, number) => {
+            stmt = stmt
+                .and_where(Expr::col((DataBuffer::Table, DataBuffer::Timestamp)).lte(timestamp))
+                .order_by((DataBuffer::Table, DataBuffer::Timestamp), Order::Desc)
+                .limit(number as u64)
+                .to_owned();
+        },
+        BufferSelector::NumberAfter(timestamp, number) => {
+            stmt = stmt
+                .and_where(Expr::col((DataBuffer::Table, DataBuffer::Timestamp)).gte(timestamp))
+                .order_by((DataBuffer::Table, DataBuffer::Timestamp), Order::Asc)
+                .limit(number as u64)
+                .to_owned();
+        },
+        BufferSelector::First(number, offset) => {
+            stmt = stmt
+                .order_by((DataBuffer::Table, DataBuffer::Id), Order::Asc)
+                .limit(number as u64)
+                .offset(offset as u64)
+                .to_owned();
+        },
+        BufferSelector::Last(number, offset) => {
+            stmt = stmt
+                .order_by((DataBuffer::Table, DataBuffer::Id), Order::Desc)
+                .limit(number as u64)
+                .offset(offset as u64)
+                .to_owned();
+        },
+        BufferSelector::None => {}
+    }
+    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes: Vec<u8> = row.get(5);
+            let types: Vec<DataType> = row.get::<Vec<u8>,_>(6).into_iter().map(|ty| ty.into()).collect();
+            BufferSchema {
+                id: row.get(0),
+                device_id: row.get(1),
+                model_id: row.get(2),
+                timestamp: row.get(3),
+                data: ArrayDataValue::from_bytes(&bytes, &types).to_vec(),
+                tag: row.get(4)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
 pub(crate) async fn select_timestamp(pool: &Pool<Postgres>,
     selector: BufferSelector,
     device_ids: Option<&[Uuid]>,
@@ -250,7 +350,8 @@ pub(crate) async fn insert_buffer(pool: &Pool<Postgres>,
     model_id: Uuid,
     timestamp: DateTime<Utc>,
     data: &[DataValue],
-    tag: Option<i16>
+    tag: Option<i16>,
+    check: &IngestCheck
 ) -> Result<i32, Error>
 {
     let types_vec = select_data_types(pool, &[model_id]).await?;
@@ -280,8 +381,9 @@ pub(crate) async fn insert_buffer(pool: &Pool<Postgres>,
         .unwrap_or(&mut sea_query::InsertStatement::default())
         .build_sqlx(PostgresQueryBuilder);
 
+    let mut tx = pool.begin().await?;
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
     let sql = Query::select()
@@ -290,9 +392,12 @@ pub(crate) async fn insert_buffer(pool: &Pool<Postgres>,
         .to_string(PostgresQueryBuilder);
     let id: i32 = sqlx::query(&sql)
         .map(|row: PgRow| row.get(0))
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+    write_ingest(&mut tx, StatusKind::Buffer, check, &[device_id], &[model_id], &[timestamp], &[data]).await?;
+    tx.commit().await?;
+
     Ok(id)
 }
 
@@ -301,7 +406,8 @@ pub(crate) async fn insert_buffer_multiple(pool: &Pool<Postgres>,
     model_ids: &[Uuid],
     timestamps: &[DateTime<Utc>],
     data: &[&[DataValue]],
-    tags: Option<&[i16]>
+    tags: Option<&[i16]>,
+    check: &IngestCheck
 ) -> Result<Vec<i32>, Error>
 {
     let number = device_ids.len();
@@ -353,8 +459,9 @@ pub(crate) async fn insert_buffer_multiple(pool: &Pool<Postgres>,
     }
     let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
 
+    let mut tx = pool.begin().await?;
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
     let sql = Query::select()
@@ -363,10 +470,13 @@ pub(crate) async fn insert_buffer_multiple(pool: &Pool<Postgres>,
         .to_string(PostgresQueryBuilder);
     let id: i32 = sqlx::query(&sql)
         .map(|row: PgRow| row.get(0))
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
     let ids = (id-number as i32+1..id+1).collect();
 
+    write_ingest(&mut tx, StatusKind::Buffer, check, device_ids, model_ids, timestamps, data).await?;
+    tx.commit().await?;
+
     Ok(ids)
 }
 
@@ -0,0 +1,477 @@
+use sqlx::{Pool, Row, Error};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
+use sqlx::types::chrono::Utc;
+use sea_query::{PostgresQueryBuilder, Query, Expr, OnConflict};
+use sea_query_binder::SqlxBinder;
+use uuid::Uuid;
+
+use crate::schema::model::{Model, ModelConfig, ModelTag, ModelSchema, ModelConfigSchema};
+use crate::schema::device::{DeviceType, DeviceTypeModel, TypeSchema};
+use crate::schema::set::{SetTemplate, SetTemplateMap, SetTemplateSchema};
+use crate::schema::group::{GroupModel, GroupKind, GroupModelSchema};
+use crate::schema::bundle::{BundleSchema, BundleDiffSchema, BundleKind, BUNDLE_VERSION};
+use crate::schema::data::{Data, DataArchive};
+use crate::schema::buffer::DataBuffer;
+use crate::operation::{model, types, set, group};
+use crate::operation::model::sync_model_config_history;
+use crate::operation::{BUNDLE_VERSION_UNSUPPORTED, BUNDLE_DATA_TYPE_CHANGE};
+
+pub(crate) async fn select_bundle(pool: &Pool<Postgres>,
+    model_ids: &[Uuid],
+    type_ids: &[Uuid],
+    template_ids: &[Uuid],
+    group_model_ids: &[Uuid]
+) -> Result<BundleSchema, Error>
+{
+    let types = match type_ids.is_empty() {
+        true => Vec::new(),
+        false => types::select_device_type(pool, None, Some(type_ids), None).await?
+    };
+    let set_templates = match template_ids.is_empty() {
+        true => Vec::new(),
+        false => set::select_set_template(pool, None, Some(template_ids), None).await?
+    };
+    let group_models: Vec<GroupModelSchema> = match group_model_ids.is_empty() {
+        true => Vec::new(),
+        false => group::select_group(pool, GroupKind::Model, None, Some(group_model_ids), None, None).await?
+            .into_iter().map(|el| el.into_group_model()).collect()
+    };
+
+    // include models referenced by exported types, templates and groups so the bundle is self contained
+    let mut bundle_model_ids = model_ids.to_vec();
+    for type_ in &types {
+        bundle_model_ids.extend_from_slice(&type_.model_ids);
+    }
+    for template in &set_templates {
+        bundle_model_ids.extend(template.members.iter().map(|member| member.model_id));
+    }
+    for group in &group_models {
+        bundle_model_ids.extend_from_slice(&group.model_ids);
+    }
+    bundle_model_ids.sort();
+    bundle_model_ids.dedup();
+    let models = match bundle_model_ids.is_empty() {
+        true => Vec::new(),
+        false => model::select_model(pool, None, Some(&bundle_model_ids), None, None, None).await?
+    };
+
+    Ok(BundleSchema {
+        version: BUNDLE_VERSION,
+        models,
+        types,
+        set_templates,
+        group_models
+    })
+}
+
+pub(crate) async fn import_bundle(pool: &Pool<Postgres>,
+    bundle: &BundleSchema
+) -> Result<Vec<BundleDiffSchema>, Error>
+{
+    if bundle.version > BUNDLE_VERSION {
+        return Err(Error::InvalidArgument(BUNDLE_VERSION_UNSUPPORTED.to_string()));
+    }
+
+    let mut diffs: Vec<BundleDiffSchema> = Vec::new();
+    let mut tx = pool.begin().await?;
+
+    // read current state of every bundle item in the import transaction to compute the diff
+    let ids: Vec<Uuid> = bundle.models.iter().map(|el| el.id).collect();
+    let models = model::read_model(&mut tx, None, Some(&ids), None, None, None).await?;
+    let ids: Vec<Uuid> = bundle.types.iter().map(|el| el.id).collect();
+    let types = types::read_device_type(&mut tx, None, Some(&ids), None).await?;
+    let ids: Vec<Uuid> = bundle.set_templates.iter().map(|el| el.id).collect();
+    let set_templates = set::read_set_template(&mut tx, None, Some(&ids), None).await?;
+    let ids: Vec<Uuid> = bundle.group_models.iter().map(|el| el.id).collect();
+    let group_models: Vec<GroupModelSchema> = group::read_group(&mut tx, GroupKind::Model, None, Some(&ids), None, None).await?
+        .into_iter().map(|el| el.into_group_model()).collect();
+
+    for model in &bundle.models {
+        let existing = models.iter().find(|el| el.id == model.id);
+        let equal = existing.map(|el| model_equal(el, model)).unwrap_or(false);
+        if !equal {
+            // stored data bytes are decoded with the model data type so it can't change once data exists
+            let data_type_change = existing.is_some_and(|el| el.data_type != model.data_type);
+            if data_type_change && model_data_exists(&mut tx, model.id).await? {
+                return Err(Error::InvalidArgument(BUNDLE_DATA_TYPE_CHANGE.to_string()));
+            }
+            upsert_model(&mut tx, model, existing).await?;
+        }
+        diffs.push(BundleDiffSchema::new(BundleKind::Model, model.id, &model.name, existing.is_some(), equal));
+    }
+    for type_ in &bundle.types {
+        let existing = types.iter().find(|el| el.id == type_.id);
+        let equal = existing.map(|el| type_equal(el, type_)).unwrap_or(false);
+        if !equal {
+            upsert_type(&mut tx, type_).await?;
+        }
+        diffs.push(BundleDiffSchema::new(BundleKind::Type, type_.id, &type_.name, existing.is_some(), equal));
+    }
+    for template in &bundle.set_templates {
+        let existing = set_templates.iter().find(|el| el.id == template.id);
+        let equal = existing.map(|el| el.name == template.name && el.description == template.description && el.members == template.members).unwrap_or(false);
+        if !equal {
+            upsert_set_template(&mut tx, template).await?;
+        }
+        diffs.push(BundleDiffSchema::new(BundleKind::SetTemplate, template.id, &template.name, existing.is_some(), equal));
+    }
+    for group in &bundle.group_models {
+        let existing = group_models.iter().find(|el| el.id == group.id);
+        let equal = existing.map(|el| group_model_equal(el, group)).unwrap_or(false);
+        if !equal {
+            upsert_group_model(&mut tx, group).await?;
+        }
+        diffs.push(BundleDiffSchema::new(BundleKind::GroupModel, group.id, &group.name, existing.is_some(), equal));
+    }
+
+    tx.commit().await?;
+
+    Ok(diffs)
+}
+
+fn model_equal(a: &ModelSchema, b: &ModelSchema) -> bool
+{
+    // config ids are local to a database so configs are compared by their content only
+    let configs = |model: &ModelSchema| {
+        let mut configs: Vec<(i16, String, Vec<u8>, i16, String)> = model.configs.iter().flatten()
+            .map(|c| (c.index, c.name.clone(), c.value.to_bytes(), i16::from(c.value.get_type()), c.category.clone()))
+            .collect();
+        configs.sort();
+        configs
+    };
+    let tags = |model: &ModelSchema| {
+        let mut tags: Vec<(i16, String, Vec<i16>)> = model.tags.iter()
+            .map(|t| (t.tag, t.name.clone(), t.members.clone()))
+            .collect();
+        tags.sort();
+        tags
+    };
+    a.category == b.category && a.name == b.name && a.description == b.description && a.data_type == b.data_type
+        && configs(a) == configs(b) && tags(a) == tags(b)
+}
+
+fn type_equal(a: &TypeSchema, b: &TypeSchema) -> bool
+{
+    let mut a_ids = a.model_ids.clone();
+    let mut b_ids = b.model_ids.clone();
+    a_ids.sort();
+    b_ids.sort();
+    a.name == b.name && a.description == b.description && a_ids == b_ids
+}
+
+fn group_model_equal(a: &GroupModelSchema, b: &GroupModelSchema) -> bool
+{
+    let mut a_ids = a.model_ids.clone();
+    let mut b_ids = b.model_ids.clone();
+    a_ids.sort();
+    b_ids.sort();
+    a.name == b.name && a.category == b.category && a.description == b.description && a_ids == b_ids
+}
+
+async fn model_data_exists(conn: &mut PgConnection,
+    model_id: Uuid
+) -> Result<bool, Error>
+{
+    let (sql, values) = Query::select()
+        .expr(Expr::exists(Query::select()
+                .expr(Expr::val(1))
+                .from(Data::Table)
+                .and_where(Expr::col(Data::ModelId).eq(model_id))
+                .to_owned()
+            )
+            .or(Expr::exists(Query::select()
+                .expr(Expr::val(1))
+                .from(DataBuffer::Table)
+                .and_where(Expr::col(DataBuffer::ModelId).eq(model_id))
+                .to_owned()
+            ))
+            .or(Expr::exists(Query::select()
+                .expr(Expr::val(1))
+                .from(DataArchive::Table)
+                .and_where(Expr::col(DataArchive::ModelId).eq(model_id))
+                .to_owned()
+            ))
+        )
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_one(conn)
+        .await
+}
+
+async fn upsert_model(conn: &mut PgConnection,
+    model: &ModelSchema,
+    existing: Option<&ModelSchema>
+) -> Result<(), Error>
+{
+    let data_type: Vec<u8> = model.data_type.iter().map(|ty| ty.to_owned().into()).collect();
+    let (sql, values) = Query::insert()
+        .into_table(Model::Table)
+        .columns([
+            Model::ModelId,
+            Model::Category,
+            Model::Name,
+            Model::Description,
+            Model::DataType
+        ])
+        .values([
+            model.id.into(),
+            model.category.as_str().into(),
+            model.name.as_str().into(),
+            model.description.as_str().into(),
+            data_type.into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .on_conflict(OnConflict::column(Model::ModelId)
+            .update_columns([Model::Category, Model::Name, Model::Description, Model::DataType])
+            .to_owned()
+        )
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    // configs are matched by index and name so existing config ids are kept
+    let bundle_configs: Vec<&ModelConfigSchema> = model.configs.iter().flatten().collect();
+    let existing_configs: Vec<&ModelConfigSchema> = existing.map(|el| el.configs.iter().flatten().collect()).unwrap_or_default();
+    for config in &existing_configs {
+        let (sql, values) = match bundle_configs.iter().find(|el| el.index == config.index && el.name == config.name) {
+            Some(bundle_config) => Query::update()
+                .table(ModelConfig::Table)
+                .value(ModelConfig::Value, bundle_config.value.to_bytes())
+                .value(ModelConfig::Type, i16::from(bundle_config.value.get_type()))
+                .value(ModelConfig::Category, bundle_config.category.as_str())
+                .and_where(Expr::col(ModelConfig::Id).eq(config.id))
+                .build_sqlx(PostgresQueryBuilder),
+            None => Query::delete()
+                .from_table(ModelConfig::Table)
+                .and_where(Expr::col(ModelConfig::Id).eq(config.id))
+                .build_sqlx(PostgresQueryBuilder)
+        };
+        sqlx::query_with(&sql, values)
+            .execute(&mut *conn)
+            .await?;
+    }
+    for config in bundle_configs {
+        if existing_configs.iter().any(|el| el.index == config.index && el.name == config.name) {
+            continue;
+        }
+        let (sql, values) = Query::insert()
+            .into_table(ModelConfig::Table)
+            .columns([
+                ModelConfig::ModelId,
+                ModelConfig::Index,
+                ModelConfig::Name,
+                ModelConfig::Value,
+                ModelConfig::Type,
+                ModelConfig::Category
+            ])
+            // index is bound with the same type as insert_model_config which prepares the same statement
+            .values([
+                model.id.into(),
+                i32::from(config.index).into(),
+                config.name.as_str().into(),
+                config.value.to_bytes().into(),
+                i16::from(config.value.get_type()).into(),
+                config.category.as_str().into()
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    sync_model_config_history(&mut *conn, None, Some(model.id), Utc::now()).await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(ModelTag::Table)
+        .and_where(Expr::col(ModelTag::ModelId).eq(model.id))
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+    for tag in &model.tags {
+        // first element of members is the tag itself
+        let mut bytes: Vec<u8> = Vec::new();
+        for member in tag.members.iter().skip(1) {
+            bytes.append(member.to_be_bytes().to_vec().as_mut());
+        }
+        let (sql, values) = Query::insert()
+            .into_table(ModelTag::Table)
+            .columns([
+                ModelTag::ModelId,
+                ModelTag::Tag,
+                ModelTag::Name,
+                ModelTag::Members
+            ])
+            .values([
+                model.id.into(),
+                tag.tag.into(),
+                tag.name.as_str().into(),
+                bytes.into()
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn upsert_type(conn: &mut PgConnection,
+    type_: &TypeSchema
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::insert()
+        .into_table(DeviceType::Table)
+        .columns([
+            DeviceType::TypeId,
+            DeviceType::Name,
+            DeviceType::Description
+        ])
+        .values([
+            type_.id.into(),
+            type_.name.as_str().into(),
+            type_.description.as_str().into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .on_conflict(OnConflict::column(DeviceType::TypeId)
+            .update_columns([DeviceType::Name, DeviceType::Description])
+            .to_owned()
+        )
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(DeviceTypeModel::Table)
+        .and_where(Expr::col(DeviceTypeModel::TypeId).eq(type_.id))
+        .and_where(Expr::col(DeviceTypeModel::ModelId).is_not_in(type_.model_ids.clone()))
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+    for model_id in &type_.model_ids {
+        let (sql, values) = Query::insert()
+            .into_table(DeviceTypeModel::Table)
+            .columns([
+                DeviceTypeModel::TypeId,
+                DeviceTypeModel::ModelId
+            ])
+            .values([
+                type_.id.into(),
+                (*model_id).into()
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .on_conflict(OnConflict::columns([DeviceTypeModel::TypeId, DeviceTypeModel::ModelId])
+                .do_nothing()
+                .to_owned()
+            )
+            .build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn upsert_set_template(conn: &mut PgConnection,
+    template: &SetTemplateSchema
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::insert()
+        .into_table(SetTemplate::Table)
+        .columns([
+            SetTemplate::TemplateId,
+            SetTemplate::Name,
+            SetTemplate::Description
+        ])
+        .values([
+            template.id.into(),
+            template.name.as_str().into(),
+            template.description.as_str().into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .on_conflict(OnConflict::column(SetTemplate::TemplateId)
+            .update_columns([SetTemplate::Name, SetTemplate::Description])
+            .to_owned()
+        )
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    // template members are ordered so they are rewritten with their bundle index
+    let (sql, values) = Query::delete()
+        .from_table(SetTemplateMap::Table)
+        .and_where(Expr::col(SetTemplateMap::TemplateId).eq(template.id))
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+    for (index, member) in template.members.iter().enumerate() {
+        let (sql, values) = Query::insert()
+            .into_table(SetTemplateMap::Table)
+            .columns([
+                SetTemplateMap::TemplateId,
+                SetTemplateMap::TypeId,
+                SetTemplateMap::ModelId,
+                SetTemplateMap::DataIndex,
+                SetTemplateMap::TemplateIndex
+            ])
+            .values([
+                template.id.into(),
+                member.type_id.into(),
+                member.model_id.into(),
+                member.data_index.clone().into(),
+                (index as i16).into()
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn upsert_group_model(conn: &mut PgConnection,
+    group: &GroupModelSchema
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::insert()
+        .into_table(GroupModel::Table)
+        .columns([
+            GroupModel::GroupId,
+            GroupModel::Name,
+            GroupModel::Category,
+            GroupModel::Description
+        ])
+        .values([
+            group.id.into(),
+            group.name.as_str().into(),
+            group.category.as_str().into(),
+            group.description.as_str().into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .on_conflict(OnConflict::column(GroupModel::GroupId)
+            .update_columns([GroupModel::Name, GroupModel::Category, GroupModel::Description])
+            .to_owned()
+        )
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    group::replace_group_map(&mut *conn, GroupKind::Model, group.id, &group.model_ids).await?;
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use sqlx::{Pool, Error};
+use sqlx::postgres::Postgres;
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::schema::value::DataValue;
+use crate::schema::device::{DeviceConfigSchema, EffectiveConfigHistory};
+use crate::schema::data::DataSchema;
+use crate::schema::calibration::{Calibration, DataCalibratedSchema};
+use crate::operation::data::{DataSelector, select_data};
+use crate::operation::device::select_device_effective_history;
+use crate::operation::model::select_model_config_history;
+
+pub(crate) struct CalibrationSelect {
+    model_id: Uuid,
+    index: usize,
+    kind: String,
+    category: String,
+    valid_from: DateTime<Utc>,
+    valid_to: Option<DateTime<Utc>>
+}
+
+pub(crate) async fn select_data_calibrated(pool: &Pool<Postgres>,
+    selector: DataSelector,
+    device_ids: &[Uuid],
+    model_ids: &[Uuid],
+    tag: Option<i16>
+) -> Result<Vec<DataCalibratedSchema>, Error>
+{
+    let data_vec = select_data(pool, selector, device_ids, model_ids, tag).await?;
+    if data_vec.is_empty() {
+        return Ok(Vec::new());
+    }
+    let selects = select_calibration(pool, model_ids).await?;
+    let categories: Vec<String> = selects.iter().map(|select| select.category.clone()).collect();
+    let mut data_device_ids: Vec<Uuid> = data_vec.iter().map(|data| data.device_id).collect();
+    data_device_ids.sort();
+    data_device_ids.dedup();
+    // calibration parameters may be defined on device type or gateway and overridden by device configs
+    let mut histories: Vec<EffectiveConfigHistory> = Vec::new();
+    for device_id in data_device_ids {
+        histories.push(select_device_effective_history(pool, device_id).await?);
+    }
+
+    // use calibration parameters which were in effect at the timestamp of each data
+    let rows = data_vec.into_iter()
+        .map(|data| {
+            let device_configs: Vec<DeviceConfigSchema> = histories.iter()
+                .find(|history| history.device_id == data.device_id)
+                .map(|history| history.at(data.timestamp))
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|cfg| categories.contains(&cfg.category))
+                .map(|cfg| cfg.into_device_config())
+                .collect();
+            calibrate_data(data, &selects, &device_configs)
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+pub(crate) async fn select_calibration(pool: &Pool<Postgres>,
+    model_ids: &[Uuid]
+) -> Result<Vec<CalibrationSelect>, Error>
+{
+    // every version of calibration model configs so data use the calibration in effect at their timestamp
+    let rows = select_model_config_history(pool, model_ids, None).await?
+        .into_iter()
+        .filter(|cfg| cfg.category == Calibration::CATEGORY)
+        .map(|cfg| {
+            let category = match cfg.value {
+                DataValue::String(value) => value,
+                _ => String::new()
+            };
+            CalibrationSelect {
+                model_id: cfg.model_id,
+                index: cfg.index as usize,
+                kind: cfg.name,
+                category,
+                valid_from: cfg.valid_from,
+                valid_to: cfg.valid_to
+            }
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+pub(crate) fn calibrate_data(data: DataSchema, selects: &[CalibrationSelect], configs: &[DeviceConfigSchema]) -> DataCalibratedSchema
+{
+    let values = data.data.iter().enumerate()
+        .map(|(index, value)| {
+            let select = selects.iter().find(|s| s.model_id == data.model_id && s.index == index
+                && s.valid_from <= data.timestamp && s.valid_to.is_none_or(|valid_to| valid_to > data.timestamp)
+            );
+            let calibration = select.and_then(|s| {
+                let params: Vec<DeviceConfigSchema> = configs.iter()
+                    .filter(|cfg| cfg.category == s.category)
+                    .cloned()
+                    .collect();
+                Calibration::from_configs(&s.kind, &params)
+            });
+            // fields without calibration or with non numeric value keep the raw value
+            match (calibration, value.to_f64()) {
+                (Some(calibration), Some(raw)) => DataValue::F64(calibration.apply(raw)),
+                _ => value.clone()
+            }
+        })
+        .collect();
+    DataCalibratedSchema {
+        device_id: data.device_id,
+        model_id: data.model_id,
+        timestamp: data.timestamp,
+        raw: data.data,
+        data: values,
+        tag: data.tag
+    }
+}
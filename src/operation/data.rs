@@ -1,5 +1,6 @@
+use std::collections::HashSet;
 use sqlx::{Pool, Row, Error};
-use sqlx::postgres::{Postgres, PgRow};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
 use sqlx::types::chrono::{DateTime, Utc};
 use sea_query::{PostgresQueryBuilder, Query, Expr, Order, Condition};
 use sea_query_binder::SqlxBinder;
@@ -9,10 +10,18 @@ use crate::schema::value::{DataType, DataValue, ArrayDataValue};
 use crate::schema::model::Model;
 use crate::schema::data::{Data, DataSchema, DataSetSchema};
 use crate::schema::set::SetMap;
-use crate::operation::model::{select_tag_members, select_tag_members_set};
+use crate::operation::model::{select_tag_members, select_tag_members_set, select_tag_members_group};
+use crate::operation::group::group_member_condition;
+use crate::schema::group::GroupKind;
+use crate::operation::derived::{select_derived, select_data_derived};
+use crate::operation::types::write_device_model_log;
+use crate::operation::status::{StatusKind, write_device_status};
+use crate::operation::alarm::evaluate_alarm;
+use crate::schema::alarm::ThresholdHistory;
 use crate::utility::tag as Tag;
-use super::{EMPTY_LENGTH_UNMATCH, DATA_TYPE_UNMATCH, MODEL_NOT_EXISTS};
+use super::{EMPTY_LENGTH_UNMATCH, DATA_TYPE_UNMATCH, MODEL_NOT_EXISTS, DATA_MODEL_DERIVED};
 
+#[derive(Clone)]
 pub(crate) enum DataSelector {
     Time(DateTime<Utc>),
     Latest(DateTime<Utc>),
@@ -27,6 +36,43 @@ pub(crate) async fn select_data(pool: &Pool<Postgres>,
     model_ids: &[Uuid],
     tag: Option<i16>
 ) -> Result<Vec<DataSchema>, Error>
+{
+    let mut rows = select_data_stored(pool, selector.clone(), device_ids, model_ids, tag).await?;
+    let derived_vec = select_derived(pool, model_ids).await?;
+    if derived_vec.is_empty() {
+        return Ok(rows);
+    }
+
+    // a materialized row of a derived model takes precedence over the row computed with the same device, timestamp and tag
+    let stored: HashSet<(Uuid, Uuid, DateTime<Utc>, i16)> = rows.iter()
+        .map(|el| (el.device_id, el.model_id, el.timestamp, el.tag))
+        .collect();
+    for derived in derived_vec {
+        let derived_rows = select_data_derived(pool, selector.clone(), device_ids, &derived, tag).await?;
+        rows.extend(derived_rows.into_iter().filter(|el| !stored.contains(&(el.device_id, el.model_id, el.timestamp, el.tag))));
+    }
+    match selector {
+        DataSelector::Time(_) => {},
+        DataSelector::NumberBefore(_, limit) => {
+            rows.sort_by_key(|el| std::cmp::Reverse(el.timestamp));
+            rows.truncate(limit);
+        },
+        DataSelector::NumberAfter(_, limit) => {
+            rows.sort_by_key(|el| el.timestamp);
+            rows.truncate(limit);
+        },
+        _ => rows.sort_by_key(|el| el.timestamp)
+    }
+
+    Ok(rows)
+}
+
+pub(crate) async fn select_data_stored(pool: &Pool<Postgres>, 
+    selector: DataSelector,
+    device_ids: &[Uuid],
+    model_ids: &[Uuid],
+    tag: Option<i16>
+) -> Result<Vec<DataSchema>, Error>
 {
     let mut stmt = Query::select()
         .columns([
@@ -198,16 +244,44 @@ pub(crate) async fn select_data_types(pool: &Pool<Postgres>,
         .await
 }
 
+// checks of a data or buffer write which are read before and written inside the insert transaction
+pub(crate) struct IngestCheck {
+    pub(crate) incompatible: Vec<(Uuid, Uuid)>,
+    pub(crate) thresholds: Vec<ThresholdHistory>
+}
+
+pub(crate) async fn write_ingest(conn: &mut PgConnection,
+    kind: StatusKind,
+    check: &IngestCheck,
+    device_ids: &[Uuid],
+    model_ids: &[Uuid],
+    timestamps: &[DateTime<Utc>],
+    data: &[&[DataValue]]
+) -> Result<(), Error>
+{
+    write_device_model_log(conn, &check.incompatible).await?;
+    write_device_status(conn, kind, device_ids, timestamps).await?;
+    for (((device_id, model_id), timestamp), data) in device_ids.iter().zip(model_ids).zip(timestamps).zip(data) {
+        evaluate_alarm(conn, &check.thresholds, *device_id, *model_id, *timestamp, data).await?;
+    }
+    Ok(())
+}
+
 pub(crate) async fn insert_data(pool: &Pool<Postgres>,
     device_id: Uuid,
     model_id: Uuid,
     timestamp: DateTime<Utc>,
     data: &[DataValue],
-    tag: Option<i16>
+    tag: Option<i16>,
+    check: &IngestCheck
 ) -> Result<(), Error>
 {
     let types_vec = select_data_types(pool, &[model_id]).await?;
     let types = types_vec.into_iter().next().ok_or(Error::InvalidArgument(MODEL_NOT_EXISTS.to_string()))?;
+    // data of a derived model are computed from sources or written by materialization only
+    if !select_derived(pool, &[model_id]).await?.is_empty() {
+        return Err(Error::InvalidArgument(DATA_MODEL_DERIVED.to_string()));
+    }
     let bytes = match ArrayDataValue::from_vec(data).convert(&types) {
         Some(value) => value.to_bytes(),
         None => return Err(Error::InvalidArgument(DATA_TYPE_UNMATCH.to_string()))
@@ -234,10 +308,14 @@ pub(crate) async fn insert_data(pool: &Pool<Postgres>,
         .to_owned();
     let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
 
+    let mut tx = pool.begin().await?;
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
+    write_ingest(&mut tx, StatusKind::Data, check, &[device_id], &[model_id], &[timestamp], &[data]).await?;
+    tx.commit().await?;
+
     Ok(())
 }
 
@@ -246,7 +324,8 @@ pub(crate) async fn insert_data_multiple(pool: &Pool<Postgres>,
     model_ids: &[Uuid],
     timestamps: &[DateTime<Utc>],
     data: &[&[DataValue]],
-    tags: Option<&[i16]>
+    tags: Option<&[i16]>,
+    check: &IngestCheck
 ) -> Result<(), Error>
 {
     let number = device_ids.len();
@@ -266,6 +345,9 @@ pub(crate) async fn insert_data_multiple(pool: &Pool<Postgres>,
     if model_ids_unique.len() != types_vec.len() {
         return Err(Error::InvalidArgument(MODEL_NOT_EXISTS.to_string()));
     }
+    if !select_derived(pool, &model_ids_unique).await?.is_empty() {
+        return Err(Error::InvalidArgument(DATA_MODEL_DERIVED.to_string()));
+    }
     let types: Vec<Vec<DataType>> = model_ids.into_iter().map(|id| {
         let index = model_ids_unique.iter().position(|el| el == id).unwrap_or_default();
         types_vec[index].clone()
@@ -298,10 +380,14 @@ pub(crate) async fn insert_data_multiple(pool: &Pool<Postgres>,
     }
     let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
 
+    let mut tx = pool.begin().await?;
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
+    write_ingest(&mut tx, StatusKind::Data, check, device_ids, model_ids, timestamps, data).await?;
+    tx.commit().await?;
+
     Ok(())
 }
 
@@ -496,3 +582,171 @@ pub(crate) async fn count_data(pool: &Pool<Postgres>,
 
     Ok(count as usize)
 }
+
+pub(crate) async fn select_data_group(pool: &Pool<Postgres>,
+    selector: DataSelector,
+    group_device_id: Uuid,
+    group_model_id: Uuid,
+    tag: Option<i16>
+) -> Result<Vec<DataSchema>, Error>
+{
+    let mut stmt = Query::select()
+        .columns([
+            (Data::Table, Data::DeviceId),
+            (Data::Table, Data::ModelId),
+            (Data::Table, Data::Timestamp),
+            (Data::Table, Data::Tag),
+            (Data::Table, Data::Data)
+        ])
+        .column((Model::Table, Model::DataType))
+        .from(Data::Table)
+        .inner_join(Model::Table, 
+            Expr::col((Data::Table, Data::ModelId))
+            .equals((Model::Table, Model::ModelId)))
+        .and_where(group_member_condition(pool, GroupKind::Device, group_device_id, (Data::Table, Data::DeviceId), (Data::Table, Data::Timestamp)).await?)
+        .and_where(group_member_condition(pool, GroupKind::Model, group_model_id, (Data::Table, Data::ModelId), (Data::Table, Data::Timestamp)).await?)
+        .to_owned();
+
+    match selector {
+        DataSelector::Time(time) => {
+            stmt = stmt.and_where(Expr::col((Data::Table, Data::Timestamp)).eq(time)).to_owned();
+        },
+        DataSelector::Latest(last) => {
+            stmt = stmt.and_where(Expr::col((Data::Table, Data::Timestamp)).gt(last))
+                .order_by((Data::Table, Data::Timestamp), Order::Asc)
+                .to_owned();
+        },
+        DataSelector::Range(begin, end) => {
+            stmt = stmt
+                .and_where(Expr::col((Data::Table, Data::Timestamp)).gte(begin))
+                .and_where(Expr::col((Data::Table, Data::Timestamp)).lte(end))
+                .order_by((Data::Table, Data::Timestamp), Order::Asc)
+                .to_owned();
+        },
+        DataSelector::NumberBefore(time, limit) => {
+            stmt = stmt
+                .and_where(Expr::col((Data::Table, Data::Timestamp)).lte(time))
+                .order_by((Data::Table, Data::Timestamp), Order::Desc)
+                .limit(limit as u64)
+                .to_owned();
+        },
+        DataSelector::NumberAfter(time, limit) => {
+            stmt = stmt
+                .and_where(Expr::col((Data::Table, Data::Timestamp)).gte(time))
+                .order_by((Data::Table, Data::Timestamp), Order::Asc)
+                .limit(limit as u64)
+                .to_owned();
+        }
+    }
+
+    if let Some(t) = tag {
+        let tags = select_tag_members_group(pool, group_model_id, t).await?;
+        stmt = stmt.and_where(Expr::col((Data::Table, Data::Tag)).is_in(tags)).to_owned();
+    }
+    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes: Vec<u8> = row.get(4);
+            let types: Vec<DataType> = row.get::<Vec<u8>,_>(5).into_iter().map(|ty| ty.into()).collect();
+            DataSchema {
+                device_id: row.get(0),
+                model_id: row.get(1),
+                timestamp: row.get(2),
+                data: ArrayDataValue::from_bytes(&bytes, &types).to_vec(),
+                tag: row.get(3)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+pub(crate) async fn select_timestamp_group(pool: &Pool<Postgres>,
+    selector: DataSelector,
+    group_device_id: Uuid,
+    group_model_id: Uuid,
+    tag: Option<i16>
+) -> Result<Vec<DateTime<Utc>>, Error>
+{
+    let mut stmt = Query::select()
+        .distinct()
+        .column((Data::Table, Data::Timestamp))
+        .from(Data::Table)
+        .and_where(group_member_condition(pool, GroupKind::Device, group_device_id, (Data::Table, Data::DeviceId), (Data::Table, Data::Timestamp)).await?)
+        .and_where(group_member_condition(pool, GroupKind::Model, group_model_id, (Data::Table, Data::ModelId), (Data::Table, Data::Timestamp)).await?)
+        .to_owned();
+
+    match selector {
+        DataSelector::Time(time) => {
+            stmt = stmt.and_where(Expr::col((Data::Table, Data::Timestamp)).eq(time)).to_owned();
+        },
+        DataSelector::Latest(last) => {
+            stmt = stmt.and_where(Expr::col((Data::Table, Data::Timestamp)).gt(last)).to_owned();
+        },
+        DataSelector::Range(begin, end) => {
+            stmt = stmt
+                .and_where(Expr::col((Data::Table, Data::Timestamp)).gte(begin))
+                .and_where(Expr::col((Data::Table, Data::Timestamp)).lte(end))
+                .to_owned();
+        }
+        _ => {}
+    }
+
+    if let Some(t) = tag {
+        let tags = select_tag_members_group(pool, group_model_id, t).await?;
+        stmt = stmt.and_where(Expr::col((Data::Table, Data::Tag)).is_in(tags)).to_owned();
+    }
+    let (sql, values) = stmt
+        .order_by((Data::Table, Data::Timestamp), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(pool)
+        .await
+}
+
+pub(crate) async fn count_data_group(pool: &Pool<Postgres>,
+    selector: DataSelector,
+    group_device_id: Uuid,
+    group_model_id: Uuid,
+    tag: Option<i16>
+) -> Result<usize, Error>
+{
+    let mut stmt = Query::select()
+        .expr(Expr::col((Data::Table, Data::Timestamp)).count())
+        .from(Data::Table)
+        .and_where(group_member_condition(pool, GroupKind::Device, group_device_id, (Data::Table, Data::DeviceId), (Data::Table, Data::Timestamp)).await?)
+        .and_where(group_member_condition(pool, GroupKind::Model, group_model_id, (Data::Table, Data::ModelId), (Data::Table, Data::Timestamp)).await?)
+        .to_owned();
+
+    match selector {
+        DataSelector::Latest(last) => {
+            stmt = stmt.and_where(Expr::col((Data::Table, Data::Timestamp)).gt(last)).to_owned();
+        },
+        DataSelector::Range(begin, end) => {
+            stmt = stmt
+                .and_where(Expr::col((Data::Table, Data::Timestamp)).gte(begin))
+                .and_where(Expr::col((Data::Table, Data::Timestamp)).lte(end))
+                .to_owned();
+        },
+        _ => {}
+    }
+
+    if let Some(t) = tag {
+        let tags = select_tag_members_group(pool, group_model_id, t).await?;
+        stmt = stmt.and_where(Expr::col((Data::Table, Data::Tag)).is_in(tags)).to_owned();
+    }
+    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+
+    let count: i64 = sqlx::query_with(&sql, values)
+        .map(|row| {
+            row.get(0)
+        })
+        .fetch_one(pool)
+        .await?;
+
+    Ok(count as usize)
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use sqlx::{Pool, Row, Error};
+use sqlx::postgres::{Postgres, PgRow};
+use sqlx::types::chrono::{DateTime, Utc};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, OnConflict};
+use sea_query_binder::SqlxBinder;
+use uuid::Uuid;
+
+use crate::schema::value::{DataValue, ArrayDataValue};
+use crate::schema::data::{Data, DataSchema};
+use crate::schema::derived::{ModelDerived, ModelDerivedSource, DerivedModelSchema, DerivedSource, Expression};
+use crate::operation::data::{DataSelector, select_data_stored, select_data_types};
+use crate::utility::tag as Tag;
+use super::{MODEL_NOT_EXISTS, DERIVED_NOT_EXISTS, DERIVED_EXPRESSION_INVALID, DERIVED_TAG_INVALID, DATA_INSERT_BATCH};
+
+pub(crate) async fn select_derived(pool: &Pool<Postgres>,
+    model_ids: &[Uuid]
+) -> Result<Vec<DerivedModelSchema>, Error>
+{
+    let (sql, values) = Query::select()
+        .columns([
+            ModelDerived::ModelId,
+            ModelDerived::Index,
+            ModelDerived::Expression
+        ])
+        .from(ModelDerived::Table)
+        .and_where(Expr::col(ModelDerived::ModelId).is_in(model_ids.to_vec()))
+        .order_by(ModelDerived::ModelId, Order::Asc)
+        .order_by(ModelDerived::Index, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let mut derived_vec: Vec<DerivedModelSchema> = Vec::new();
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let model_id: Uuid = row.get(0);
+            // get last derived schema with the same model_id or push a new one
+            if derived_vec.last().map(|el| el.model_id) != Some(model_id) {
+                derived_vec.push(DerivedModelSchema { model_id, ..Default::default() });
+            }
+            if let Some(derived) = derived_vec.last_mut() {
+                derived.expressions.push(row.get(2));
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    if derived_vec.is_empty() {
+        return Ok(derived_vec);
+    }
+
+    let (sql, values) = Query::select()
+        .columns([
+            ModelDerivedSource::ModelId,
+            ModelDerivedSource::SourceModelId,
+            ModelDerivedSource::SourceField
+        ])
+        .from(ModelDerivedSource::Table)
+        .and_where(Expr::col(ModelDerivedSource::ModelId).is_in(model_ids.to_vec()))
+        .order_by(ModelDerivedSource::ModelId, Order::Asc)
+        .order_by(ModelDerivedSource::SourceIndex, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let model_id: Uuid = row.get(0);
+            if let Some(derived) = derived_vec.iter_mut().find(|el| el.model_id == model_id) {
+                derived.sources.push(DerivedSource {
+                    model_id: row.get(1),
+                    index: row.get::<i16,_>(2) as u8
+                });
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(derived_vec)
+}
+
+pub(crate) async fn insert_derived(pool: &Pool<Postgres>,
+    model_id: Uuid,
+    sources: &[DerivedSource],
+    expressions: &[&str]
+) -> Result<(), Error>
+{
+    // every field of the derived model must have a valid expression which only refer to defined sources
+    let types = select_data_types(pool, &[model_id]).await?.into_iter().next()
+        .ok_or(Error::InvalidArgument(MODEL_NOT_EXISTS.to_string()))?;
+    if expressions.len() != types.len() {
+        return Err(Error::InvalidArgument(DERIVED_EXPRESSION_INVALID.to_string()));
+    }
+    for expression in expressions {
+        let parsed = Expression::parse(expression)
+            .ok_or(Error::InvalidArgument(DERIVED_EXPRESSION_INVALID.to_string()))?;
+        if parsed.max_variable().map(|index| index >= sources.len()).unwrap_or(false) {
+            return Err(Error::InvalidArgument(DERIVED_EXPRESSION_INVALID.to_string()));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let mut stmt = Query::insert()
+        .into_table(ModelDerived::Table)
+        .columns([
+            ModelDerived::ModelId,
+            ModelDerived::Index,
+            ModelDerived::Expression
+        ])
+        .to_owned();
+    for (index, expression) in expressions.iter().enumerate() {
+        stmt = stmt.values([
+            model_id.into(),
+            (index as i16).into(),
+            (*expression).into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .to_owned();
+    }
+    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    if !sources.is_empty() {
+        let mut stmt = Query::insert()
+            .into_table(ModelDerivedSource::Table)
+            .columns([
+                ModelDerivedSource::ModelId,
+                ModelDerivedSource::SourceIndex,
+                ModelDerivedSource::SourceModelId,
+                ModelDerivedSource::SourceField
+            ])
+            .to_owned();
+        for (index, source) in sources.iter().enumerate() {
+            stmt = stmt.values([
+                model_id.into(),
+                (index as i16).into(),
+                source.model_id.into(),
+                (source.index as i16).into()
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .to_owned();
+        }
+        let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub(crate) async fn delete_derived(pool: &Pool<Postgres>,
+    model_id: Uuid
+) -> Result<(), Error>
+{
+    let mut tx = pool.begin().await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(ModelDerivedSource::Table)
+        .and_where(Expr::col(ModelDerivedSource::ModelId).eq(model_id))
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    let (sql, values) = Query::delete()
+        .from_table(ModelDerived::Table)
+        .and_where(Expr::col(ModelDerived::ModelId).eq(model_id))
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub(crate) async fn select_data_derived(pool: &Pool<Postgres>,
+    selector: DataSelector,
+    device_ids: &[Uuid],
+    derived: &DerivedModelSchema,
+    tag: Option<i16>
+) -> Result<Vec<DataSchema>, Error>
+{
+    let types = select_data_types(pool, &[derived.model_id]).await?.into_iter().next().unwrap_or_default();
+    let expressions: Vec<Expression> = derived.expressions.iter().filter_map(|el| Expression::parse(el)).collect();
+    if derived.sources.is_empty() || expressions.len() != types.len() {
+        return Ok(Vec::new());
+    }
+
+    // read every source model separately so a row number limit applies to each source
+    let mut source_model_ids: Vec<Uuid> = derived.sources.iter().map(|el| el.model_id).collect();
+    source_model_ids.sort();
+    source_model_ids.dedup();
+    let mut source_data: Vec<DataSchema> = Vec::new();
+    for model_id in source_model_ids {
+        let data_vec = select_data_stored(pool, selector.clone(), device_ids, &[model_id], tag).await?;
+        source_data.extend(data_vec);
+    }
+
+    // align source rows by device, timestamp and tag of the first source
+    let first_model_id = derived.sources[0].model_id;
+    let source_map: HashMap<(Uuid, Uuid, DateTime<Utc>, i16), &DataSchema> = source_data.iter()
+        .map(|el| ((el.model_id, el.device_id, el.timestamp, el.tag), el))
+        .collect();
+    let mut rows: Vec<DataSchema> = Vec::new();
+    for first in source_data.iter().filter(|el| el.model_id == first_model_id) {
+        let values: Option<Vec<f64>> = derived.sources.iter()
+            .map(|source| {
+                source_map.get(&(source.model_id, first.device_id, first.timestamp, first.tag))
+                    .and_then(|el| el.data.get(source.index as usize))
+                    .and_then(|value| value.to_f64())
+            })
+            .collect();
+        let values = match values {
+            Some(value) => value,
+            None => continue
+        };
+        let data: Option<Vec<DataValue>> = expressions.iter().zip(types.iter())
+            .map(|(expression, type_)| {
+                expression.evaluate(&values).map(|value| DataValue::from_f64(value, type_.clone()))
+            })
+            .collect();
+        if let Some(data) = data {
+            rows.push(DataSchema {
+                device_id: first.device_id,
+                model_id: derived.model_id,
+                timestamp: first.timestamp,
+                data,
+                tag: first.tag
+            });
+        }
+    }
+
+    Ok(rows)
+}
+
+pub(crate) async fn materialize_derived(pool: &Pool<Postgres>,
+    device_id: Uuid,
+    model_id: Uuid,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+    tag: i16
+) -> Result<usize, Error>
+{
+    if !(Tag::ANALYSIS_10..=Tag::ANALYSIS_1).contains(&tag) {
+        return Err(Error::InvalidArgument(DERIVED_TAG_INVALID.to_string()));
+    }
+    let derived = select_derived(pool, &[model_id]).await?.into_iter().next()
+        .ok_or(Error::InvalidArgument(DERIVED_NOT_EXISTS.to_string()))?;
+    let selector = DataSelector::Range(begin, end);
+    let data_vec = select_data_derived(pool, selector, &[device_id], &derived, None).await?;
+    if data_vec.is_empty() {
+        return Ok(0);
+    }
+
+    let mut tx = pool.begin().await?;
+
+    for batch in data_vec.chunks(DATA_INSERT_BATCH) {
+        let mut stmt = Query::insert()
+            .into_table(Data::Table)
+            .columns([
+                Data::DeviceId,
+                Data::ModelId,
+                Data::Timestamp,
+                Data::Tag,
+                Data::Data
+            ])
+            .to_owned();
+        for data in batch {
+            stmt = stmt.values([
+                data.device_id.into(),
+                data.model_id.into(),
+                data.timestamp.into(),
+                tag.into(),
+                ArrayDataValue::from_vec(&data.data).to_bytes().into()
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .to_owned();
+        }
+        // materialize again overwrite previous values
+        let (sql, values) = stmt
+            .on_conflict(OnConflict::columns([Data::Timestamp, Data::ModelId, Data::DeviceId, Data::Tag])
+                .update_column(Data::Data)
+                .to_owned()
+            )
+            .build_sqlx(PostgresQueryBuilder);
+
+        sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(data_vec.len())
+}
@@ -1,20 +1,31 @@
 use sqlx::{Pool, Row, Error};
-use sqlx::postgres::{Postgres, PgRow};
-use sea_query::{PostgresQueryBuilder, Query, Expr, Order, Func};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
+use sqlx::types::chrono::{DateTime, Utc};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, Func, Condition, CommonTableExpression, UnionType, OnConflict, InsertStatement, SimpleExpr};
 use sea_query_binder::SqlxBinder;
 use uuid::Uuid;
 
 use crate::schema::value::{DataValue, DataType};
-use crate::schema::device::{Device, DeviceType, DeviceTypeModel, DeviceConfig, DeviceKind, DeviceSchema, DeviceConfigSchema};
+use crate::schema::device::{Device, DeviceType, DeviceTypeModel, DeviceConfig, DeviceConfigHistory, DeviceTypeConfig, DeviceTypeConfigKey, DeviceKind, DeviceLifecycle, SerialScope, DeviceTree, DeviceSchema, DeviceTreeSchema, DeviceConfigSchema, DeviceConfigHistorySchema, EffectiveConfigSchema, EffectiveConfigHistory, TypeConfigKeySchema, ConfigViolation, ConfigViolationSchema};
+use crate::operation::types::{select_device_type_config, select_device_type_config_key, select_type_config_key};
+use crate::operation::{DEVICE_PARENT_CYCLE, SERIAL_NUMBER_DUPLICATE, SERIAL_NUMBER_AMBIGUOUS, CONFIG_INVALID};
+
+const DEVICE_TREE_DEPTH_LIMIT: u32 = 64;
+
+#[derive(Default)]
+pub(crate) struct DeviceFilter<'a> {
+    pub(crate) gateway_id: Option<Uuid>,
+    pub(crate) type_id: Option<Uuid>,
+    pub(crate) name: Option<&'a str>,
+    pub(crate) lifecycles: Option<&'a [DeviceLifecycle]>
+}
 
 pub(crate) async fn select_device(pool: &Pool<Postgres>, 
     kind: DeviceKind,
     id: Option<Uuid>,
     serial_number: Option<&str>,
     ids: Option<&[Uuid]>,
-    gateway_id: Option<Uuid>,
-    type_id: Option<Uuid>,
-    name: Option<&str>
+    filter: &DeviceFilter<'_>
 ) -> Result<Vec<DeviceSchema>, Error>
 {
     let mut stmt = Query::select()
@@ -40,6 +51,20 @@ pub(crate) async fn select_device(pool: &Pool<Postgres>,
             (DeviceConfig::Table, DeviceConfig::Type),
             (DeviceConfig::Table, DeviceConfig::Category)
         ])
+        .columns([
+            (Device::Table, Device::Lifecycle),
+            (Device::Table, Device::LifecycleTimestamp),
+            (Device::Table, Device::ParentId),
+            (Device::Table, Device::Latitude),
+            (Device::Table, Device::Longitude),
+            (Device::Table, Device::Altitude)
+        ])
+        .columns([
+            (DeviceConfig::Table, DeviceConfig::DesiredTimestamp),
+            (DeviceConfig::Table, DeviceConfig::ReportedValue),
+            (DeviceConfig::Table, DeviceConfig::ReportedType),
+            (DeviceConfig::Table, DeviceConfig::ReportedTimestamp)
+        ])
         .from(Device::Table)
         .inner_join(DeviceType::Table, 
             Expr::col((Device::Table, Device::TypeId))
@@ -60,23 +85,32 @@ pub(crate) async fn select_device(pool: &Pool<Postgres>,
     }
     else if let Some(sn) = serial_number {
         stmt = stmt.and_where(Expr::col((Device::Table, Device::SerialNumber)).eq(sn.to_owned())).to_owned();
+        // serial number is unique within a type
+        if let Some(type_id) = filter.type_id {
+            stmt = stmt.and_where(Expr::col((Device::Table, Device::TypeId)).eq(type_id)).to_owned();
+        }
     }
     else if let Some(ids) = ids {
         stmt = stmt.and_where(Expr::col((Device::Table, Device::DeviceId)).is_in(ids.to_vec())).to_owned();
     }
     else {
-        if let Some(gateway_id) = gateway_id {
+        if let Some(gateway_id) = filter.gateway_id {
             stmt = stmt.and_where(Expr::col((Device::Table, Device::GatewayId)).eq(gateway_id)).to_owned();
         }
-        if let Some(type_id) = type_id {
+        if let Some(type_id) = filter.type_id {
             stmt = stmt.and_where(Expr::col((Device::Table, Device::TypeId)).eq(type_id)).to_owned();
         }
-        if let Some(name) = name {
+        if let Some(name) = filter.name {
             let name_like = String::from("%") + name + "%";
             stmt = stmt.and_where(Expr::col((Device::Table, Device::Name)).like(name_like)).to_owned();
         }
     }
 
+    if let Some(lifecycles) = filter.lifecycles {
+        let lifecycles: Vec<i16> = lifecycles.iter().map(|&lifecycle| lifecycle.into()).collect();
+        stmt = stmt.and_where(Expr::col((Device::Table, Device::Lifecycle)).is_in(lifecycles)).to_owned();
+    }
+
     if let DeviceKind::Gateway = kind {
         stmt = stmt.and_where(
             Expr::col((Device::Table, Device::DeviceId)).equals((Device::Table, Device::GatewayId))
@@ -115,6 +149,12 @@ pub(crate) async fn select_device(pool: &Pool<Postgres>,
             device_schema.type_.id = row.get(2);
             device_schema.type_.name = row.get(6);
             device_schema.type_.description = row.get(7);
+            device_schema.lifecycle = DeviceLifecycle::from(row.get::<i16,_>(14));
+            device_schema.lifecycle_timestamp = row.get(15);
+            device_schema.parent_id = row.get(16);
+            device_schema.latitude = row.get(17);
+            device_schema.longitude = row.get(18);
+            device_schema.altitude = row.get(19);
             // on every new model id found, add model id to type model and initialize a new config
             let model_id = row.try_get(8).ok();
             if last_model == None || last_model != Some(model_id.unwrap_or_default()) {
@@ -134,7 +174,15 @@ pub(crate) async fn select_device(pool: &Pool<Postgres>,
                 (config_id, config_name, config_bytes, config_type, config_category) 
             {
                 let value = DataValue::from_bytes(&bytes, DataType::from(type_));
-                device_schema.configs.push(DeviceConfigSchema { id, device_id, name, value, category });
+                let reported_bytes: Option<Vec<u8>> = row.get(21);
+                let reported_type: Option<i16> = row.get(22);
+                let reported = reported_bytes.zip(reported_type).map(|(bytes, type_)| DataValue::from_bytes(&bytes, DataType::from(type_)));
+                device_schema.configs.push(DeviceConfigSchema {
+                    id, device_id, name, value, category,
+                    desired_timestamp: row.get(20),
+                    reported,
+                    reported_timestamp: row.get(23)
+                });
             }
             // update device_schema_vec with updated device_schema
             device_schema_vec.push(device_schema.clone());
@@ -145,49 +193,166 @@ pub(crate) async fn select_device(pool: &Pool<Postgres>,
     Ok(device_schema_vec)
 }
 
+pub(crate) struct DeviceInsert<'a> {
+    pub(crate) id: Uuid,
+    pub(crate) gateway_id: Uuid,
+    pub(crate) type_id: Uuid,
+    pub(crate) serial_number: &'a str,
+    pub(crate) name: &'a str,
+    pub(crate) description: Option<&'a str>
+}
+
+impl DeviceInsert<'_> {
+    fn is_gateway(&self) -> bool {
+        self.gateway_id == self.id
+    }
+
+    fn statement(&self) -> InsertStatement {
+        Query::insert()
+            .into_table(Device::Table)
+            .columns([
+                Device::DeviceId,
+                Device::GatewayId,
+                Device::TypeId,
+                Device::SerialNumber,
+                Device::Name,
+                Device::Description,
+                Device::ParentId
+            ])
+            .values([
+                self.id.into(),
+                self.gateway_id.into(),
+                self.type_id.into(),
+                self.serial_number.into(),
+                self.name.into(),
+                self.description.unwrap_or_default().into(),
+                // parent of a device is its gateway by default, a gateway is a root device
+                if self.is_gateway() { Option::<Uuid>::None.into() } else { device_exists_expr(self.gateway_id) }
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .to_owned()
+    }
+}
+
+// device and default values of the config keys of its type are created in one transaction
 pub(crate) async fn insert_device(pool: &Pool<Postgres>,
-    id: Uuid,
-    gateway_id: Uuid,
-    type_id: Uuid,
-    serial_number: &str,
-    name: &str,
-    description: Option<&str>
+    scope: SerialScope,
+    device: &DeviceInsert<'_>
 ) -> Result<Uuid, Error>
 {
-    let (sql, values) = Query::insert()
-        .into_table(Device::Table)
-        .columns([
-            Device::DeviceId,
-            Device::GatewayId,
-            Device::TypeId,
-            Device::SerialNumber,
-            Device::Name,
-            Device::Description
-        ])
-        .values([
-            id.into(),
-            gateway_id.into(),
-            type_id.into(),
-            serial_number.into(),
-            name.into(),
-            description.unwrap_or_default().into()
-        ])
-        .unwrap_or(&mut sea_query::InsertStatement::default())
-        .build_sqlx(PostgresQueryBuilder);
+    let mut tx = pool.begin().await?;
 
+    lock_serial_number(&mut tx, device.serial_number).await?;
+    check_serial_number(&mut tx, scope, device.id, device.serial_number, Some(device.type_id)).await?;
+
+    let (sql, values) = device.statement().build_sqlx(PostgresQueryBuilder);
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
+    write_device_config_default(&mut tx, device.id, device.type_id, &[]).await?;
+
+    tx.commit().await?;
+
+    Ok(device.id)
+}
+
+// device of the same kind with the serial number in the scope is updated otherwise a new device is created,
+// a device of the other kind is a duplicate and the type of a device matched across types is changed
+pub(crate) async fn upsert_device_by_sn(pool: &Pool<Postgres>,
+    scope: SerialScope,
+    device: &DeviceInsert<'_>
+) -> Result<Uuid, Error>
+{
+    let type_id = match scope {
+        SerialScope::Global => None,
+        SerialScope::Type => Some(device.type_id)
+    };
+
+    let mut tx = pool.begin().await?;
+
+    lock_serial_number(&mut tx, device.serial_number).await?;
+    let ids = select_device_id_by_sn(&mut tx, device.serial_number, type_id).await?;
+    if ids.len() > 1 {
+        return Err(Error::InvalidArgument(SERIAL_NUMBER_AMBIGUOUS.to_string()));
+    }
+    let existing = match ids.first() {
+        Some(&id) => {
+            let (gateway_id, type_id) = select_device_owner(&mut tx, id).await?;
+            Some((id, gateway_id, type_id))
+        },
+        None => None
+    };
+    if let Some((id, gateway_id, type_id)) = existing {
+        if (gateway_id == id) != device.is_gateway() {
+            return Err(Error::InvalidArgument(SERIAL_NUMBER_DUPLICATE.to_string()));
+        }
+        if type_id != device.type_id {
+            write_device_type(&mut tx, id, device.type_id).await?;
+        }
+        if !device.is_gateway() {
+            check_device_gateway(&mut tx, id, device.gateway_id).await?;
+        }
+    }
+
+    let mut on_conflict = OnConflict::columns([Device::TypeId, Device::SerialNumber])
+        .update_column(Device::Name)
+        .to_owned();
+    if device.description.is_some() {
+        on_conflict.update_column(Device::Description);
+    }
+    // only a device of the same kind is updated, gateway of a non gateway device may change
+    if device.is_gateway() {
+        on_conflict.action_and_where(Expr::col((Device::Table, Device::GatewayId)).equals((Device::Table, Device::DeviceId)));
+    } else {
+        on_conflict.value(Device::GatewayId, device.gateway_id);
+        on_conflict.value(Device::ParentId, device_parent_expr(device.gateway_id));
+        on_conflict.action_and_where(Expr::col((Device::Table, Device::GatewayId)).not_equals((Device::Table, Device::DeviceId)));
+    }
+    let (sql, values) = device.statement()
+        .on_conflict(on_conflict)
+        .returning_col(Device::DeviceId)
+        .build_sqlx(PostgresQueryBuilder);
+    let id: Uuid = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(Error::InvalidArgument(SERIAL_NUMBER_DUPLICATE.to_string()))?;
+    if existing.is_none() {
+        write_device_config_default(&mut tx, id, device.type_id, &[]).await?;
+    }
+
+    tx.commit().await?;
 
     Ok(id)
 }
 
+// parent of a non gateway device follows its gateway unless the parent was set to another device
+fn device_parent_expr(gateway_id: Uuid) -> SimpleExpr
+{
+    Expr::case(Expr::col((Device::Table, Device::DeviceId)).eq(gateway_id), Expr::col((Device::Table, Device::ParentId)))
+        .case(Expr::col((Device::Table, Device::ParentId)).equals((Device::Table, Device::GatewayId)), device_exists_expr(gateway_id))
+        .finally(Expr::col((Device::Table, Device::ParentId)))
+        .into()
+}
+
+// a gateway which is not a device row can't be a parent, the parent is empty instead
+fn device_exists_expr(id: Uuid) -> SimpleExpr
+{
+    SimpleExpr::SubQuery(None, Box::new(Query::select()
+        .column(Device::DeviceId)
+        .from(Device::Table)
+        .and_where(Expr::col(Device::DeviceId).eq(id))
+        .to_owned()
+        .into_sub_query_statement()
+    ))
+}
+
 pub(crate) async fn update_device(pool: &Pool<Postgres>,
     kind: DeviceKind,
     id: Uuid,
     gateway_id: Option<Uuid>,
     type_id: Option<Uuid>,
-    serial_number: Option<&str>,
+    serial_number: Option<(&str, SerialScope)>,
     name: Option<&str>,
     description: Option<&str>
 ) -> Result<(), Error>
@@ -196,13 +361,19 @@ pub(crate) async fn update_device(pool: &Pool<Postgres>,
         .table(Device::Table)
         .to_owned();
 
+    let mut tx = pool.begin().await?;
+
     if let Some(value) = gateway_id {
+        check_device_gateway(&mut tx, id, value).await?;
         stmt = stmt.value(Device::GatewayId, value).to_owned();
+        stmt = stmt.value(Device::ParentId, device_parent_expr(value)).to_owned();
     }
     if let Some(value) = type_id {
         stmt = stmt.value(Device::TypeId, value).to_owned();
     }
-    if let Some(value) = serial_number {
+    if let Some((value, scope)) = serial_number {
+        lock_serial_number(&mut tx, value).await?;
+        check_serial_number(&mut tx, scope, id, value, type_id).await?;
         stmt = stmt.value(Device::SerialNumber, value).to_owned();
     }
     if let Some(value) = name {
@@ -221,12 +392,318 @@ pub(crate) async fn update_device(pool: &Pool<Postgres>,
         .build_sqlx(PostgresQueryBuilder);
 
     sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn select_device_id_by_sn(conn: &mut PgConnection,
+    serial_number: &str,
+    type_id: Option<Uuid>
+) -> Result<Vec<Uuid>, Error>
+{
+    let mut stmt = Query::select()
+        .column(Device::DeviceId)
+        .from(Device::Table)
+        .and_where(Expr::col(Device::SerialNumber).eq(serial_number))
+        .to_owned();
+
+    if let Some(type_id) = type_id {
+        stmt = stmt.and_where(Expr::col(Device::TypeId).eq(type_id)).to_owned();
+    }
+    let (sql, values) = stmt
+        .order_by(Device::DeviceId, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(conn)
+        .await
+}
+
+// device with the serial number, serial number shared by devices of different types must be read with a type
+pub(crate) async fn select_device_by_sn(pool: &Pool<Postgres>,
+    kind: DeviceKind,
+    serial_number: &str,
+    type_id: Option<Uuid>
+) -> Result<DeviceSchema, Error>
+{
+    let mut devices = select_device(pool, kind, None, Some(serial_number), None, &DeviceFilter { type_id, ..Default::default() }).await?;
+    if devices.len() > 1 {
+        return Err(Error::InvalidArgument(SERIAL_NUMBER_AMBIGUOUS.to_string()));
+    }
+    devices.pop().ok_or(Error::RowNotFound)
+}
+
+// writes of a serial number are serialized so the check in the scope and the write don't interleave,
+// a serial number unique across types can't be enforced by the unique index of the type and serial number
+pub(crate) async fn lock_serial_number(conn: &mut PgConnection,
+    serial_number: &str
+) -> Result<(), Error>
+{
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(serial_number)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn check_serial_number(conn: &mut PgConnection,
+    scope: SerialScope,
+    id: Uuid,
+    serial_number: &str,
+    type_id: Option<Uuid>
+) -> Result<(), Error>
+{
+    // serial number scoped per type is checked against the new type or the current type of the device
+    let type_id = match (scope, type_id) {
+        (SerialScope::Global, _) => None,
+        (SerialScope::Type, Some(type_id)) => Some(type_id),
+        (SerialScope::Type, None) => Some(select_device_owner(conn, id).await?.1)
+    };
+    let ids = select_device_id_by_sn(conn, serial_number, type_id).await?;
+    if ids.iter().any(|&device_id| device_id != id) {
+        return Err(Error::InvalidArgument(SERIAL_NUMBER_DUPLICATE.to_string()));
+    }
+    Ok(())
+}
+
+// change type of a device, existing configs must conform to config keys of the new type
+// and default values of the new type are created for keys without config
+async fn write_device_type(conn: &mut PgConnection,
+    id: Uuid,
+    type_id: Uuid
+) -> Result<(), Error>
+{
+    let keys = select_type_config_key(conn, &[type_id]).await?;
+    let (sql, values) = Query::select()
+        .columns([
+            DeviceConfig::Name,
+            DeviceConfig::Category,
+            DeviceConfig::Value,
+            DeviceConfig::Type
+        ])
+        .from(DeviceConfig::Table)
+        .and_where(Expr::col(DeviceConfig::DeviceId).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+    let configs: Vec<(String, String, DataValue)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes: Vec<u8> = row.get(2);
+            (row.get(0), row.get(1), DataValue::from_bytes(&bytes, DataType::from(row.get::<i16,_>(3))))
+        })
+        .fetch_all(&mut *conn)
+        .await?;
+    if !keys.is_empty() {
+        for (name, category, value) in &configs {
+            let violation = match keys.iter().find(|key| &key.name == name && &key.category == category) {
+                Some(key) => key.check(value),
+                None => Some(ConfigViolation::Undeclared)
+            };
+            if let Some(violation) = violation {
+                return Err(Error::InvalidArgument(format!("{} ({}:{} {:?})", CONFIG_INVALID, category, name, violation)));
+            }
+        }
+    }
+
+    let (sql, values) = Query::update()
+        .table(Device::Table)
+        .value(Device::TypeId, type_id)
+        .and_where(Expr::col(Device::DeviceId).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    let configured: Vec<(String, String)> = configs.into_iter().map(|(name, category, _)| (name, category)).collect();
+    write_device_config_default(conn, id, type_id, &configured).await
+}
+
+pub(crate) async fn update_device_lifecycle(pool: &Pool<Postgres>,
+    kind: DeviceKind,
+    id: Uuid,
+    lifecycle: DeviceLifecycle,
+    timestamp: DateTime<Utc>
+) -> Result<(), Error>
+{
+    let mut stmt = Query::update()
+        .table(Device::Table)
+        .value(Device::Lifecycle, i16::from(lifecycle))
+        .value(Device::LifecycleTimestamp, timestamp)
+        .and_where(Expr::col(Device::DeviceId).eq(id))
+        .to_owned();
+
+    if let DeviceKind::Gateway = kind {
+        stmt = stmt.and_where(Expr::col(Device::GatewayId).eq(id)).to_owned();
+    }
+    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+
+    let result = sqlx::query_with(&sql, values)
         .execute(pool)
         .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::RowNotFound);
+    }
 
     Ok(())
 }
 
+pub(crate) async fn update_device_parent(pool: &Pool<Postgres>,
+    id: Uuid,
+    parent_id: Option<Uuid>
+) -> Result<(), Error>
+{
+    let mut tx = pool.begin().await?;
+
+    lock_device_tree(&mut tx).await?;
+    if let Some(parent_id) = parent_id {
+        check_device_parent(&mut tx, id, parent_id).await?;
+    }
+
+    let (sql, values) = Query::update()
+        .table(Device::Table)
+        .value(Device::ParentId, parent_id)
+        .and_where(Expr::col(Device::DeviceId).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let result = sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::RowNotFound);
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+// parent changes are serialized so the cycle check and the write don't interleave
+async fn lock_device_tree(conn: &mut PgConnection) -> Result<(), Error>
+{
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('device_parent'))")
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+// parent can not be the device itself or one of its descendants
+async fn check_device_parent(conn: &mut PgConnection,
+    id: Uuid,
+    parent_id: Uuid
+) -> Result<(), Error>
+{
+    if select_device_tree_node(conn, parent_id, Some(0)).await?.is_empty() {
+        return Err(Error::RowNotFound);
+    }
+    let nodes = select_device_tree_node(conn, id, None).await?;
+    if parent_id == id || nodes.iter().any(|(node_id, _, _)| *node_id == parent_id) {
+        return Err(Error::InvalidArgument(DEVICE_PARENT_CYCLE.to_string()));
+    }
+    Ok(())
+}
+
+// parent which follows the gateway of a device is checked against the new gateway
+async fn check_device_gateway(conn: &mut PgConnection,
+    id: Uuid,
+    gateway_id: Uuid
+) -> Result<(), Error>
+{
+    lock_device_tree(conn).await?;
+    let (sql, values) = Query::select()
+        .columns([Device::GatewayId, Device::ParentId])
+        .from(Device::Table)
+        .and_where(Expr::col(Device::DeviceId).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+    let owner: Option<(Uuid, Option<Uuid>)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get(0), row.get(1)))
+        .fetch_optional(&mut *conn)
+        .await?;
+    match owner {
+        Some((current_id, Some(parent_id))) if parent_id == current_id && gateway_id != current_id && gateway_id != id => {
+            let nodes = select_device_tree_node(conn, id, None).await?;
+            if nodes.iter().any(|(node_id, _, _)| *node_id == gateway_id) {
+                return Err(Error::InvalidArgument(DEVICE_PARENT_CYCLE.to_string()));
+            }
+            Ok(())
+        },
+        _ => Ok(())
+    }
+}
+
+pub(crate) async fn select_device_tree(pool: &Pool<Postgres>,
+    root_id: Uuid,
+    max_depth: Option<u32>
+) -> Result<DeviceTreeSchema, Error>
+{
+    let nodes = select_device_tree_node(&mut *pool.acquire().await?, root_id, max_depth).await?;
+    let ids: Vec<Uuid> = nodes.iter().map(|(id, _, _)| *id).collect();
+    let devices = select_device(pool, DeviceKind::Device, None, None, Some(&ids), &DeviceFilter::default()).await?;
+
+    DeviceTreeSchema::build(root_id, &nodes, &devices).ok_or(Error::RowNotFound)
+}
+
+pub(crate) async fn select_device_descendant(pool: &Pool<Postgres>,
+    root_id: Uuid,
+    max_depth: Option<u32>,
+    lifecycles: Option<&[DeviceLifecycle]>
+) -> Result<Vec<DeviceSchema>, Error>
+{
+    let nodes = select_device_tree_node(&mut *pool.acquire().await?, root_id, max_depth).await?;
+    let ids: Vec<Uuid> = nodes.iter().map(|(id, _, _)| *id).collect();
+
+    select_device(pool, DeviceKind::Device, None, None, Some(&ids), &DeviceFilter { lifecycles, ..Default::default() }).await
+}
+
+async fn select_device_tree_node(conn: &mut PgConnection,
+    root_id: Uuid,
+    max_depth: Option<u32>
+) -> Result<Vec<(Uuid, Option<Uuid>, i32)>, Error>
+{
+    // depth is always limited so a cyclic parent chain can not recurse forever
+    let max_depth = max_depth.map(|depth| depth.min(DEVICE_TREE_DEPTH_LIMIT)).unwrap_or(DEVICE_TREE_DEPTH_LIMIT) as i32;
+    let mut base = Query::select()
+        .columns([Device::DeviceId, Device::ParentId])
+        .expr(Expr::val(0))
+        .from(Device::Table)
+        .and_where(Expr::col(Device::DeviceId).eq(root_id))
+        .to_owned();
+    let recursive = Query::select()
+        .columns([
+            (Device::Table, Device::DeviceId),
+            (Device::Table, Device::ParentId)
+        ])
+        .expr(Expr::col((DeviceTree::Table, DeviceTree::Depth)).add(1))
+        .from(Device::Table)
+        .inner_join(DeviceTree::Table,
+            Expr::col((Device::Table, Device::ParentId))
+            .equals((DeviceTree::Table, DeviceTree::DeviceId))
+        )
+        .and_where(Expr::col((Device::Table, Device::DeviceId)).ne(root_id))
+        .and_where(Expr::col((DeviceTree::Table, DeviceTree::Depth)).lt(max_depth))
+        .to_owned();
+    let cte = CommonTableExpression::new()
+        .query(base.union(UnionType::All, recursive).to_owned())
+        .columns([DeviceTree::DeviceId, DeviceTree::ParentId, DeviceTree::Depth])
+        .table_name(DeviceTree::Table)
+        .to_owned();
+    let (sql, values) = Query::select()
+        .columns([DeviceTree::DeviceId, DeviceTree::ParentId, DeviceTree::Depth])
+        .from(DeviceTree::Table)
+        .order_by(DeviceTree::Depth, Order::Asc)
+        .order_by(DeviceTree::DeviceId, Order::Asc)
+        .to_owned()
+        .with(Query::with().cte(cte).recursive(true).to_owned())
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get(0), row.get(1), row.get(2)))
+        .fetch_all(conn)
+        .await
+}
+
 pub(crate) async fn delete_device(pool: &Pool<Postgres>, 
     kind: DeviceKind,
     id: Uuid
@@ -262,7 +739,11 @@ pub(crate) async fn select_device_config(pool: &Pool<Postgres>,
             (DeviceConfig::Table, DeviceConfig::Name),
             (DeviceConfig::Table, DeviceConfig::Value),
             (DeviceConfig::Table, DeviceConfig::Type),
-            (DeviceConfig::Table, DeviceConfig::Category)
+            (DeviceConfig::Table, DeviceConfig::Category),
+            (DeviceConfig::Table, DeviceConfig::DesiredTimestamp),
+            (DeviceConfig::Table, DeviceConfig::ReportedValue),
+            (DeviceConfig::Table, DeviceConfig::ReportedType),
+            (DeviceConfig::Table, DeviceConfig::ReportedTimestamp)
         ])
         .columns([
             (Device::Table, Device::GatewayId)
@@ -295,12 +776,17 @@ pub(crate) async fn select_device_config(pool: &Pool<Postgres>,
         .map(|row: PgRow| {
             let bytes = row.get(3);
             let type_ = DataType::from(row.get::<i16,_>(4));
+            let reported_bytes: Option<Vec<u8>> = row.get(7);
+            let reported_type: Option<i16> = row.get(8);
             DeviceConfigSchema {
                 id: row.get(0),
                 device_id: row.get(1),
                 name: row.get(2),
                 value: DataValue::from_bytes(bytes, type_),
-                category: row.get(5)
+                category: row.get(5),
+                desired_timestamp: row.get(6),
+                reported: reported_bytes.zip(reported_type).map(|(bytes, type_)| DataValue::from_bytes(&bytes, DataType::from(type_))),
+                reported_timestamp: row.get(9)
             }
         })
         .fetch_all(pool)
@@ -315,6 +801,22 @@ pub(crate) async fn insert_device_config(pool: &Pool<Postgres>,
     value: DataValue,
     category: &str
 ) -> Result<i32, Error>
+{
+    let mut tx = pool.begin().await?;
+
+    let id = write_device_config(&mut tx, device_id, name, value, category).await?;
+
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+async fn write_device_config(conn: &mut PgConnection,
+    device_id: Uuid,
+    name: &str,
+    value: DataValue,
+    category: &str
+) -> Result<i32, Error>
 {
     let config_value = value.to_bytes();
     let config_type = i16::from(value.get_type());
@@ -338,7 +840,7 @@ pub(crate) async fn insert_device_config(pool: &Pool<Postgres>,
         .build_sqlx(PostgresQueryBuilder);
 
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
 
     let sql = Query::select()
@@ -347,9 +849,11 @@ pub(crate) async fn insert_device_config(pool: &Pool<Postgres>,
         .to_string(PostgresQueryBuilder);
     let id: i32 = sqlx::query(&sql)
         .map(|row: PgRow| row.get(0))
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await?;
 
+    sync_device_config_history(conn, Some(id), None, Utc::now()).await?;
+
     Ok(id)
 }
 
@@ -372,7 +876,8 @@ pub(crate) async fn update_device_config(pool: &Pool<Postgres>,
         let type_ = i16::from(value.get_type());
         stmt = stmt
             .value(DeviceConfig::Value, bytes)
-            .value(DeviceConfig::Type, type_).to_owned();
+            .value(DeviceConfig::Type, type_)
+            .value(DeviceConfig::DesiredTimestamp, Utc::now()).to_owned();
     }
     if let Some(value) = category {
         stmt = stmt.value(DeviceConfig::Category, value).to_owned();
@@ -382,13 +887,292 @@ pub(crate) async fn update_device_config(pool: &Pool<Postgres>,
         .and_where(Expr::col(DeviceConfig::Id).eq(id))
         .build_sqlx(PostgresQueryBuilder);
 
+    let mut tx = pool.begin().await?;
+
     sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    sync_device_config_history(&mut tx, Some(id), None, Utc::now()).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub(crate) async fn select_device_config_drift(pool: &Pool<Postgres>,
+    kind: DeviceKind,
+    device_id: Option<Uuid>
+) -> Result<Vec<DeviceConfigSchema>, Error>
+{
+    let mut stmt = Query::select()
+        .columns([
+            (DeviceConfig::Table, DeviceConfig::Id),
+            (DeviceConfig::Table, DeviceConfig::DeviceId),
+            (DeviceConfig::Table, DeviceConfig::Name),
+            (DeviceConfig::Table, DeviceConfig::Value),
+            (DeviceConfig::Table, DeviceConfig::Type),
+            (DeviceConfig::Table, DeviceConfig::Category),
+            (DeviceConfig::Table, DeviceConfig::DesiredTimestamp),
+            (DeviceConfig::Table, DeviceConfig::ReportedValue),
+            (DeviceConfig::Table, DeviceConfig::ReportedType),
+            (DeviceConfig::Table, DeviceConfig::ReportedTimestamp)
+        ])
+        .from(DeviceConfig::Table)
+        .inner_join(Device::Table,
+            Expr::col((DeviceConfig::Table, DeviceConfig::DeviceId))
+            .equals((Device::Table, Device::DeviceId))
+        )
+        // not reported yet or reported value differs from desired value
+        .cond_where(Condition::any()
+            .add(Expr::col((DeviceConfig::Table, DeviceConfig::ReportedValue)).is_null())
+            .add(Expr::col((DeviceConfig::Table, DeviceConfig::ReportedType)).is_null())
+            .add(Expr::col((DeviceConfig::Table, DeviceConfig::ReportedValue)).ne(Expr::col((DeviceConfig::Table, DeviceConfig::Value))))
+            .add(Expr::col((DeviceConfig::Table, DeviceConfig::ReportedType)).ne(Expr::col((DeviceConfig::Table, DeviceConfig::Type))))
+        )
+        .to_owned();
+
+    if let Some(device_id) = device_id {
+        stmt = stmt.and_where(Expr::col((DeviceConfig::Table, DeviceConfig::DeviceId)).eq(device_id)).to_owned();
+    }
+    if let DeviceKind::Gateway = kind {
+        stmt = stmt.and_where(
+            Expr::col((DeviceConfig::Table, DeviceConfig::DeviceId)).equals((Device::Table, Device::GatewayId))
+        ).to_owned()
+    }
+    let (sql, values) = stmt
+        .order_by((DeviceConfig::Table, DeviceConfig::DeviceId), Order::Asc)
+        .order_by((DeviceConfig::Table, DeviceConfig::Id), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes = row.get(3);
+            let type_ = DataType::from(row.get::<i16,_>(4));
+            let reported_bytes: Option<Vec<u8>> = row.get(7);
+            let reported_type: Option<i16> = row.get(8);
+            DeviceConfigSchema {
+                id: row.get(0),
+                device_id: row.get(1),
+                name: row.get(2),
+                value: DataValue::from_bytes(bytes, type_),
+                category: row.get(5),
+                desired_timestamp: row.get(6),
+                reported: reported_bytes.zip(reported_type).map(|(bytes, type_)| DataValue::from_bytes(&bytes, DataType::from(type_))),
+                reported_timestamp: row.get(9)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+// update value reported by the device of a config identified by its name and category
+pub(crate) async fn update_device_config_reported(pool: &Pool<Postgres>,
+    device_id: Uuid,
+    name: &str,
+    value: DataValue,
+    category: &str,
+    timestamp: DateTime<Utc>
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::update()
+        .table(DeviceConfig::Table)
+        .values([
+            (DeviceConfig::ReportedValue, value.to_bytes().into()),
+            (DeviceConfig::ReportedType, i16::from(value.get_type()).into()),
+            (DeviceConfig::ReportedTimestamp, timestamp.into())
+        ])
+        .and_where(Expr::col(DeviceConfig::DeviceId).eq(device_id))
+        .and_where(Expr::col(DeviceConfig::Name).eq(name))
+        .and_where(Expr::col(DeviceConfig::Category).eq(category))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let result = sqlx::query_with(&sql, values)
         .execute(pool)
         .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::RowNotFound);
+    }
+
+    Ok(())
+}
+
+// check a config to be written against config keys declared by the type of the device
+pub(crate) async fn check_device_config(pool: &Pool<Postgres>,
+    device_id: Uuid,
+    name: &str,
+    value: &DataValue,
+    category: &str
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::select()
+        .column(Device::TypeId)
+        .from(Device::Table)
+        .and_where(Expr::col(Device::DeviceId).eq(device_id))
+        .build_sqlx(PostgresQueryBuilder);
+    let type_id: Option<Uuid> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_optional(pool)
+        .await?;
+    let keys = match type_id {
+        Some(type_id) => select_device_type_config_key(pool, type_id).await?,
+        None => return Ok(())
+    };
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let violation = match keys.iter().find(|key| key.name == name && key.category == category) {
+        Some(key) => key.check(value),
+        None => Some(ConfigViolation::Undeclared)
+    };
+    match violation {
+        Some(violation) => Err(Error::InvalidArgument(format!("{} ({}:{} {:?})", CONFIG_INVALID, category, name, violation))),
+        None => Ok(())
+    }
+}
+
+pub(crate) async fn check_device_config_update(pool: &Pool<Postgres>,
+    id: i32,
+    name: Option<&str>,
+    value: Option<&DataValue>,
+    category: Option<&str>
+) -> Result<(), Error>
+{
+    match select_device_config(pool, DeviceKind::Device, Some(id), None).await?.into_iter().next() {
+        Some(config) => check_device_config(pool,
+            config.device_id,
+            name.unwrap_or(&config.name),
+            value.unwrap_or(&config.value),
+            category.unwrap_or(&config.category)
+        ).await,
+        None => Ok(())
+    }
+}
 
+// a config of a required key can't be deleted unless the type of the device also has that config
+async fn check_device_config_delete(conn: &mut PgConnection,
+    id: i32
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::select()
+        .columns([
+            (DeviceConfig::Table, DeviceConfig::Name),
+            (DeviceConfig::Table, DeviceConfig::Category)
+        ])
+        .from(DeviceConfig::Table)
+        .inner_join(Device::Table,
+            Expr::col((DeviceConfig::Table, DeviceConfig::DeviceId))
+            .equals((Device::Table, Device::DeviceId))
+        )
+        .inner_join(DeviceTypeConfigKey::Table,
+            Condition::all()
+            .add(Expr::col((DeviceTypeConfigKey::Table, DeviceTypeConfigKey::TypeId)).equals((Device::Table, Device::TypeId)))
+            .add(Expr::col((DeviceTypeConfigKey::Table, DeviceTypeConfigKey::Name)).equals((DeviceConfig::Table, DeviceConfig::Name)))
+            .add(Expr::col((DeviceTypeConfigKey::Table, DeviceTypeConfigKey::Category)).equals((DeviceConfig::Table, DeviceConfig::Category)))
+        )
+        .and_where(Expr::col((DeviceConfig::Table, DeviceConfig::Id)).eq(id))
+        .and_where(Expr::col((DeviceTypeConfigKey::Table, DeviceTypeConfigKey::Required)).eq(true))
+        .and_where(Expr::exists(Query::select()
+            .expr(Expr::val(1))
+            .from(DeviceTypeConfig::Table)
+            .and_where(Expr::col((DeviceTypeConfig::Table, DeviceTypeConfig::TypeId)).equals((Device::Table, Device::TypeId)))
+            .and_where(Expr::col((DeviceTypeConfig::Table, DeviceTypeConfig::Name)).equals((DeviceConfig::Table, DeviceConfig::Name)))
+            .and_where(Expr::col((DeviceTypeConfig::Table, DeviceTypeConfig::Category)).equals((DeviceConfig::Table, DeviceConfig::Category)))
+            .to_owned()
+        ).not())
+        .build_sqlx(PostgresQueryBuilder);
+
+    let required: Option<(String, String)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get(0), row.get(1)))
+        .fetch_optional(conn)
+        .await?;
+    match required {
+        Some((name, category)) => Err(Error::InvalidArgument(format!("{} ({}:{} {:?})", CONFIG_INVALID, category, name, ConfigViolation::Missing))),
+        None => Ok(())
+    }
+}
+
+// create configs of a new device from default values of config keys declared by its type
+// default values are created for config keys of the type which are not configured yet
+async fn write_device_config_default(conn: &mut PgConnection,
+    device_id: Uuid,
+    type_id: Uuid,
+    configured: &[(String, String)]
+) -> Result<(), Error>
+{
+    let keys = select_type_config_key(conn, &[type_id]).await?;
+    for key in keys {
+        if configured.iter().any(|(name, category)| name == &key.name && category == &key.category) {
+            continue;
+        }
+        if let Some(value) = key.default {
+            write_device_config(conn, device_id, &key.name, value, &key.category).await?;
+        }
+    }
     Ok(())
 }
 
+pub(crate) async fn select_device_config_violation(pool: &Pool<Postgres>,
+    type_id: Uuid
+) -> Result<Vec<ConfigViolationSchema>, Error>
+{
+    let keys = select_device_type_config_key(pool, type_id).await?;
+    let type_configs = select_device_type_config(pool, None, Some(type_id)).await?;
+
+    let (sql, values) = Query::select()
+        .column(Device::DeviceId)
+        .from(Device::Table)
+        .and_where(Expr::col(Device::TypeId).eq(type_id))
+        .order_by(Device::DeviceId, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+    let device_ids: Vec<Uuid> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(pool)
+        .await?;
+
+    let (sql, values) = Query::select()
+        .columns([
+            (DeviceConfig::Table, DeviceConfig::Id),
+            (DeviceConfig::Table, DeviceConfig::DeviceId),
+            (DeviceConfig::Table, DeviceConfig::Name),
+            (DeviceConfig::Table, DeviceConfig::Value),
+            (DeviceConfig::Table, DeviceConfig::Type),
+            (DeviceConfig::Table, DeviceConfig::Category)
+        ])
+        .from(DeviceConfig::Table)
+        .inner_join(Device::Table,
+            Expr::col((DeviceConfig::Table, DeviceConfig::DeviceId))
+            .equals((Device::Table, Device::DeviceId))
+        )
+        .and_where(Expr::col((Device::Table, Device::TypeId)).eq(type_id))
+        .order_by((DeviceConfig::Table, DeviceConfig::Id), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+    let configs: Vec<DeviceConfigSchema> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes = row.get(3);
+            let type_ = DataType::from(row.get::<i16,_>(4));
+            DeviceConfigSchema {
+                id: row.get(0),
+                device_id: row.get(1),
+                name: row.get(2),
+                value: DataValue::from_bytes(bytes, type_),
+                category: row.get(5),
+                ..Default::default()
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    let violations = device_ids.into_iter().flat_map(|device_id| {
+        let device_configs: Vec<DeviceConfigSchema> = configs.iter().filter(|cfg| cfg.device_id == device_id).cloned().collect();
+        TypeConfigKeySchema::validate(device_id, &keys, &type_configs, &device_configs)
+    }).collect();
+
+    Ok(violations)
+}
+
 pub(crate) async fn delete_device_config(pool: &Pool<Postgres>, 
     id: i32
 ) -> Result<(), Error> 
@@ -398,9 +1182,209 @@ pub(crate) async fn delete_device_config(pool: &Pool<Postgres>,
         .and_where(Expr::col(DeviceConfig::Id).eq(id))
         .build_sqlx(PostgresQueryBuilder);
 
+    let mut tx = pool.begin().await?;
+
+    check_device_config_delete(&mut tx, id).await?;
+
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
+    sync_device_config_history(&mut tx, Some(id), None, Utc::now()).await?;
+
+    tx.commit().await?;
+
     Ok(())
 }
+
+pub(crate) async fn select_device_config_history(pool: &Pool<Postgres>,
+    device_ids: &[Uuid],
+    timestamp: Option<DateTime<Utc>>
+) -> Result<Vec<DeviceConfigHistorySchema>, Error>
+{
+    let mut stmt = Query::select()
+        .columns([
+            DeviceConfigHistory::ConfigId,
+            DeviceConfigHistory::DeviceId,
+            DeviceConfigHistory::Name,
+            DeviceConfigHistory::Value,
+            DeviceConfigHistory::Type,
+            DeviceConfigHistory::Category,
+            DeviceConfigHistory::ValidFrom,
+            DeviceConfigHistory::ValidTo
+        ])
+        .from(DeviceConfigHistory::Table)
+        .and_where(Expr::col(DeviceConfigHistory::DeviceId).is_in(device_ids.to_vec()))
+        .to_owned();
+
+    if let Some(timestamp) = timestamp {
+        stmt = stmt
+            .and_where(Expr::col(DeviceConfigHistory::ValidFrom).lte(timestamp))
+            .and_where(Expr::col(DeviceConfigHistory::ValidTo).is_null()
+                .or(Expr::col(DeviceConfigHistory::ValidTo).gt(timestamp))
+            )
+            .to_owned();
+    }
+    let (sql, values) = stmt
+        .order_by(DeviceConfigHistory::DeviceId, Order::Asc)
+        .order_by(DeviceConfigHistory::ConfigId, Order::Asc)
+        .order_by(DeviceConfigHistory::ValidFrom, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes = row.get(3);
+            let type_ = DataType::from(row.get::<i16,_>(4));
+            DeviceConfigHistorySchema {
+                id: row.get(0),
+                device_id: row.get(1),
+                name: row.get(2),
+                value: DataValue::from_bytes(bytes, type_),
+                category: row.get(5),
+                valid_from: row.get(6),
+                valid_to: row.get(7)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+// close history of configs which are changed or deleted and open history of new or changed configs
+// a new config is valid since the timestamp it is created, only configs existing before history was recorded are valid since epoch
+pub(crate) async fn sync_device_config_history(conn: &mut PgConnection,
+    id: Option<i32>,
+    device_id: Option<Uuid>,
+    timestamp: DateTime<Utc>
+) -> Result<(), Error>
+{
+    let mut stmt = Query::update()
+        .table(DeviceConfigHistory::Table)
+        .value(DeviceConfigHistory::ValidTo, timestamp)
+        .and_where(Expr::col(DeviceConfigHistory::ValidTo).is_null())
+        .and_where(Expr::exists(Query::select()
+            .expr(Expr::val(1))
+            .from(DeviceConfig::Table)
+            .and_where(Expr::col((DeviceConfig::Table, DeviceConfig::Id)).equals((DeviceConfigHistory::Table, DeviceConfigHistory::ConfigId)))
+            .and_where(Expr::col((DeviceConfig::Table, DeviceConfig::Name)).equals((DeviceConfigHistory::Table, DeviceConfigHistory::Name)))
+            .and_where(Expr::col((DeviceConfig::Table, DeviceConfig::Value)).equals((DeviceConfigHistory::Table, DeviceConfigHistory::Value)))
+            .and_where(Expr::col((DeviceConfig::Table, DeviceConfig::Type)).equals((DeviceConfigHistory::Table, DeviceConfigHistory::Type)))
+            .and_where(Expr::col((DeviceConfig::Table, DeviceConfig::Category)).equals((DeviceConfigHistory::Table, DeviceConfigHistory::Category)))
+            .to_owned()
+        ).not())
+        .to_owned();
+    if let Some(id) = id {
+        stmt = stmt.and_where(Expr::col(DeviceConfigHistory::ConfigId).eq(id)).to_owned();
+    }
+    if let Some(device_id) = device_id {
+        stmt = stmt.and_where(Expr::col(DeviceConfigHistory::DeviceId).eq(device_id)).to_owned();
+    }
+    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    let mut select = Query::select()
+        .columns([
+            (DeviceConfig::Table, DeviceConfig::Id),
+            (DeviceConfig::Table, DeviceConfig::DeviceId),
+            (DeviceConfig::Table, DeviceConfig::Name),
+            (DeviceConfig::Table, DeviceConfig::Value),
+            (DeviceConfig::Table, DeviceConfig::Type),
+            (DeviceConfig::Table, DeviceConfig::Category)
+        ])
+        .expr(Expr::val(timestamp))
+        .from(DeviceConfig::Table)
+        .and_where(Expr::exists(Query::select()
+            .expr(Expr::val(1))
+            .from(DeviceConfigHistory::Table)
+            .and_where(Expr::col((DeviceConfigHistory::Table, DeviceConfigHistory::ConfigId)).equals((DeviceConfig::Table, DeviceConfig::Id)))
+            .and_where(Expr::col((DeviceConfigHistory::Table, DeviceConfigHistory::ValidTo)).is_null())
+            .to_owned()
+        ).not())
+        .to_owned();
+    if let Some(id) = id {
+        select = select.and_where(Expr::col((DeviceConfig::Table, DeviceConfig::Id)).eq(id)).to_owned();
+    }
+    if let Some(device_id) = device_id {
+        select = select.and_where(Expr::col((DeviceConfig::Table, DeviceConfig::DeviceId)).eq(device_id)).to_owned();
+    }
+    let (sql, values) = Query::insert()
+        .into_table(DeviceConfigHistory::Table)
+        .columns([
+            DeviceConfigHistory::ConfigId,
+            DeviceConfigHistory::DeviceId,
+            DeviceConfigHistory::Name,
+            DeviceConfigHistory::Value,
+            DeviceConfigHistory::Type,
+            DeviceConfigHistory::Category,
+            DeviceConfigHistory::ValidFrom
+        ])
+        .select_from(select)
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+
+async fn select_device_owner(conn: &mut PgConnection,
+    device_id: Uuid
+) -> Result<(Uuid, Uuid), Error>
+{
+    let (sql, values) = Query::select()
+        .columns([
+            Device::GatewayId,
+            Device::TypeId
+        ])
+        .from(Device::Table)
+        .and_where(Expr::col(Device::DeviceId).eq(device_id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get(0), row.get(1)))
+        .fetch_one(conn)
+        .await
+}
+
+pub(crate) async fn select_device_effective_config(pool: &Pool<Postgres>,
+    device_id: Uuid,
+    timestamp: Option<DateTime<Utc>>
+) -> Result<Vec<EffectiveConfigSchema>, Error>
+{
+    if let Some(timestamp) = timestamp {
+        let history = select_device_effective_history(pool, device_id).await?;
+        return Ok(history.at(timestamp));
+    }
+    let (gateway_id, type_id) = select_device_owner(&mut *pool.acquire().await?, device_id).await?;
+
+    let type_configs = select_device_type_config(pool, None, Some(type_id)).await?;
+    // a gateway is its own gateway so its configs are only applied once as device configs
+    let gateway_configs = match gateway_id == device_id {
+        true => Vec::new(),
+        false => select_device_config(pool, DeviceKind::Device, None, Some(gateway_id)).await?
+    };
+    let device_configs = select_device_config(pool, DeviceKind::Device, None, Some(device_id)).await?;
+
+    Ok(EffectiveConfigSchema::merge(device_id, &type_configs, &gateway_configs, &device_configs))
+}
+
+pub(crate) async fn select_device_effective_history(pool: &Pool<Postgres>,
+    device_id: Uuid
+) -> Result<EffectiveConfigHistory, Error>
+{
+    let (gateway_id, type_id) = select_device_owner(&mut *pool.acquire().await?, device_id).await?;
+
+    let type_configs = select_device_type_config(pool, None, Some(type_id)).await?;
+    let gateway_configs = match gateway_id == device_id {
+        true => Vec::new(),
+        false => select_device_config_history(pool, &[gateway_id], None).await?
+    };
+    let device_configs = select_device_config_history(pool, &[device_id], None).await?;
+
+    Ok(EffectiveConfigHistory { device_id, type_configs, gateway_configs, device_configs })
+}
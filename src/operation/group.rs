@@ -1,10 +1,16 @@
 use sqlx::{Pool, Row, Error};
-use sqlx::postgres::{Postgres, PgRow};
-use sea_query::{PostgresQueryBuilder, Query, Expr, Order};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
+use sqlx::types::chrono::{DateTime, Utc};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, OnConflict, Condition, SimpleExpr, DynIden, IntoIden, IntoColumnRef, ColumnRef, CommonTableExpression, UnionType, SelectStatement, WithQuery, BinOper, QueryStatementBuilder};
 use sea_query_binder::SqlxBinder;
 use uuid::Uuid;
 
-use crate::schema::group::{GroupModel, GroupModelMap, GroupDevice, GroupDeviceMap, GroupKind, GroupSchema};
+use crate::schema::group::{GroupModel, GroupModelMap, GroupDevice, GroupDeviceMap, GroupModelChild, GroupDeviceChild, GroupTree, GroupModelRule, GroupDeviceRule, GroupKind, GroupSchema, GroupRuleSchema, GroupMemberDiff};
+use crate::schema::model::Model;
+use crate::schema::device::{Device, DeviceTypeModel};
+use crate::schema::label::{LabelKind, LabelSelector};
+use crate::operation::label::label_condition;
+use crate::operation::{GROUP_CHILD_CYCLE, GROUP_MEMBER_PERIOD_INVALID, GROUP_RULE_LABEL_INVALID};
 
 pub(crate) async fn select_group(pool: &Pool<Postgres>, 
     kind: GroupKind,
@@ -13,6 +19,18 @@ pub(crate) async fn select_group(pool: &Pool<Postgres>,
     name: Option<&str>,
     category: Option<&str>
 ) -> Result<Vec<GroupSchema>, Error>
+{
+    let mut conn = pool.acquire().await?;
+    read_group(&mut conn, kind, id, ids, name, category).await
+}
+
+pub(crate) async fn read_group(conn: &mut PgConnection,
+    kind: GroupKind,
+    id: Option<Uuid>,
+    ids: Option<&[Uuid]>,
+    name: Option<&str>,
+    category: Option<&str>
+) -> Result<Vec<GroupSchema>, Error>
 {
     let mut stmt = Query::select().to_owned();
     match &kind {
@@ -29,8 +47,9 @@ pub(crate) async fn select_group(pool: &Pool<Postgres>,
                 ])
                 .from(GroupModel::Table)
                 .left_join(GroupModelMap::Table, 
-                    Expr::col((GroupModel::Table, GroupModel::GroupId))
-                    .equals((GroupModelMap::Table, GroupModelMap::GroupId))
+                    Condition::all()
+                    .add(Expr::col((GroupModel::Table, GroupModel::GroupId)).equals((GroupModelMap::Table, GroupModelMap::GroupId)))
+                    .add(Expr::col((GroupModelMap::Table, GroupModelMap::ValidTo)).is_null())
                 )
                 .to_owned();
             if let Some(id) = id {
@@ -67,8 +86,9 @@ pub(crate) async fn select_group(pool: &Pool<Postgres>,
                 ])
                 .from(GroupDevice::Table)
                 .left_join(GroupDeviceMap::Table, 
-                    Expr::col((GroupDevice::Table, GroupDevice::GroupId))
-                    .equals((GroupDeviceMap::Table, GroupDeviceMap::GroupId))
+                    Condition::all()
+                    .add(Expr::col((GroupDevice::Table, GroupDevice::GroupId)).equals((GroupDeviceMap::Table, GroupDeviceMap::GroupId)))
+                    .add(Expr::col((GroupDeviceMap::Table, GroupDeviceMap::ValidTo)).is_null())
                 )
                 .and_where(Expr::col((GroupDevice::Table, GroupDevice::Kind)).eq(kind == GroupKind::Gateway)).to_owned()
                 .to_owned();
@@ -122,13 +142,39 @@ pub(crate) async fn select_group(pool: &Pool<Postgres>,
             if let Ok(value) = member_id {
                 group_schema.members.push(value);
             }
-            // update group_schema_vec with updated group_schema
-            group_schema_vec.pop();
+            // update group_schema_vec with updated group_schema, groups read before it are kept
             group_schema_vec.push(group_schema.clone());
         })
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await?;
 
+    // members of dynamic groups are computed from their rule, members of every rule are read in one statement
+    let ids: Vec<Uuid> = group_schema_vec.iter().map(|group| group.id).collect();
+    let rules = read_group_rule(conn, kind.clone(), &ids).await?;
+    let mut queries = rules.iter().map(|(group_id, rule)| {
+        group_rule_member_query(&kind, rule)
+            .expr(Expr::val(*group_id))
+            .to_owned()
+    });
+    let mut stmt = match queries.next() {
+        Some(value) => value,
+        None => return Ok(group_schema_vec)
+    };
+    for query in queries {
+        stmt = stmt.union(UnionType::All, query).to_owned();
+    }
+    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+    let rows: Vec<(Uuid, Uuid)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get(0), row.get(1)))
+        .fetch_all(&mut *conn)
+        .await?;
+    for (group_id, _) in &rules {
+        if let Some(group) = group_schema_vec.iter_mut().find(|group| group.id == *group_id) {
+            group.members = rows.iter().filter(|(_, id)| id == group_id).map(|(member_id, _)| *member_id).collect();
+            group.members.sort();
+        }
+    }
+
     Ok(group_schema_vec)
 }
 
@@ -268,40 +314,707 @@ pub(crate) async fn delete_group(pool: &Pool<Postgres>,
 pub(crate) async fn insert_group_map(pool: &Pool<Postgres>,
     kind: GroupKind,
     id: Uuid,
-    member_id: Uuid
+    member_id: Uuid,
+    valid_from: Option<DateTime<Utc>>
 ) -> Result<(), Error>
 {
-    let mut stmt = Query::insert().to_owned();
-    match &kind {
+    let mut conn = pool.acquire().await?;
+    insert_group_period(&mut conn, &kind, id, member_id, valid_from).await
+}
+
+pub(crate) async fn delete_group_map(pool: &Pool<Postgres>, 
+    kind: GroupKind,
+    id: Uuid,
+    member_id: Uuid,
+    valid_to: Option<DateTime<Utc>>
+) -> Result<(), Error> 
+{
+    let mut conn = pool.acquire().await?;
+    delete_group_period(&mut conn, &kind, id, member_id, valid_to).await
+}
+
+// a member added without timestamp starts a new period now, data written before it was added
+// are only addressed by the group when the period is backfilled with an explicit timestamp
+pub(crate) async fn insert_group_period(conn: &mut PgConnection,
+    kind: &GroupKind,
+    id: Uuid,
+    member_id: Uuid,
+    valid_from: Option<DateTime<Utc>>
+) -> Result<(), Error>
+{
+    let (table, group_col, member_col) = map_table(kind);
+    let (valid_from_col, valid_to_col) = map_validity(kind);
+    let (sql, values) = Query::select()
+        .column(valid_to_col)
+        .from(table.clone())
+        .and_where(Expr::col(group_col.clone()).eq(id))
+        .and_where(Expr::col(member_col.clone()).eq(member_id))
+        .build_sqlx(PostgresQueryBuilder);
+    let periods: Vec<Option<DateTime<Utc>>> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let valid_from = valid_from.unwrap_or_else(Utc::now);
+    // new period is open so it must start after every previous period ended
+    if periods.iter().any(|valid_to| valid_to.is_none_or(|valid_to| valid_to > valid_from)) {
+        return Err(Error::InvalidArgument(GROUP_MEMBER_PERIOD_INVALID.to_string()));
+    }
+
+    let (sql, values) = Query::insert()
+        .into_table(table)
+        .columns([group_col, member_col, valid_from_col])
+        .values([
+            id.into(),
+            member_id.into(),
+            valid_from.into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+// a member removed has its open period closed at the timestamp or now and the period is never deleted,
+// so group addressed data keep the history of the membership
+async fn delete_group_period(conn: &mut PgConnection,
+    kind: &GroupKind,
+    id: Uuid,
+    member_id: Uuid,
+    valid_to: Option<DateTime<Utc>>
+) -> Result<(), Error>
+{
+    let (table, group_col, member_col) = map_table(kind);
+    let (valid_from_col, valid_to_col) = map_validity(kind);
+    let open = Condition::all()
+        .add(Expr::col(group_col).eq(id))
+        .add(Expr::col(member_col).eq(member_id))
+        .add(Expr::col(valid_to_col.clone()).is_null());
+
+    let (sql, values) = Query::select()
+        .column(valid_from_col)
+        .from(table.clone())
+        .cond_where(open.clone())
+        .build_sqlx(PostgresQueryBuilder);
+    let valid_from: DateTime<Utc> = match sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_optional(&mut *conn)
+        .await? {
+        Some(value) => value,
+        None => return Ok(())
+    };
+
+    let valid_to = valid_to.unwrap_or_else(Utc::now);
+    if valid_to < valid_from {
+        return Err(Error::InvalidArgument(GROUP_MEMBER_PERIOD_INVALID.to_string()));
+    }
+    let (sql, values) = Query::update()
+        .table(table)
+        .value(valid_to_col, valid_to)
+        .cond_where(open)
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+// close periods of members which are not in members and start periods of new members, both now
+pub(crate) async fn replace_group_map(conn: &mut PgConnection,
+    kind: GroupKind,
+    id: Uuid,
+    members: &[Uuid]
+) -> Result<(), Error>
+{
+    let current = select_group_map_open(&mut *conn, &kind, id).await?;
+    for member_id in current.iter().filter(|member_id| !members.contains(member_id)) {
+        delete_group_period(&mut *conn, &kind, id, *member_id, None).await?;
+    }
+    let mut added: Vec<Uuid> = members.iter().filter(|member_id| !current.contains(member_id)).copied().collect();
+    added.sort();
+    added.dedup();
+    for member_id in added {
+        insert_group_period(&mut *conn, &kind, id, member_id, None).await?;
+    }
+    Ok(())
+}
+
+async fn select_group_map_open(conn: &mut PgConnection,
+    kind: &GroupKind,
+    id: Uuid
+) -> Result<Vec<Uuid>, Error>
+{
+    let (table, group_col, member_col) = map_table(kind);
+    let (_, valid_to_col) = map_validity(kind);
+    let (sql, values) = Query::select()
+        .column(member_col.clone())
+        .from(table)
+        .and_where(Expr::col(group_col).eq(id))
+        .and_where(Expr::col(valid_to_col).is_null())
+        .order_by(member_col, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(&mut *conn)
+        .await
+}
+
+// replace static members of a group and return the applied difference
+pub(crate) async fn update_group_map(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid,
+    members: &[Uuid]
+) -> Result<GroupMemberDiff, Error>
+{
+    if select_group(pool, kind.clone(), Some(id), None, None, None).await?.is_empty() {
+        return Err(Error::RowNotFound);
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let current = select_group_map_open(&mut tx, &kind, id).await?;
+    let diff = GroupMemberDiff::new(&current, members);
+    if diff != GroupMemberDiff::default() {
+        replace_group_map(&mut tx, kind, id, members).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(diff)
+}
+
+// (table, group column, member column) of group child table or group map table
+fn child_table(kind: &GroupKind) -> (DynIden, DynIden, DynIden)
+{
+    match kind {
+        GroupKind::Model => (GroupModelChild::Table.into_iden(), GroupModelChild::GroupId.into_iden(), GroupModelChild::ChildId.into_iden()),
+        GroupKind::Device | GroupKind::Gateway => (GroupDeviceChild::Table.into_iden(), GroupDeviceChild::GroupId.into_iden(), GroupDeviceChild::ChildId.into_iden())
+    }
+}
+
+fn map_table(kind: &GroupKind) -> (DynIden, DynIden, DynIden)
+{
+    match kind {
+        GroupKind::Model => (GroupModelMap::Table.into_iden(), GroupModelMap::GroupId.into_iden(), GroupModelMap::ModelId.into_iden()),
+        GroupKind::Device | GroupKind::Gateway => (GroupDeviceMap::Table.into_iden(), GroupDeviceMap::GroupId.into_iden(), GroupDeviceMap::DeviceId.into_iden())
+    }
+}
+
+fn map_validity(kind: &GroupKind) -> (DynIden, DynIden)
+{
+    match kind {
+        GroupKind::Model => (GroupModelMap::ValidFrom.into_iden(), GroupModelMap::ValidTo.into_iden()),
+        GroupKind::Device | GroupKind::Gateway => (GroupDeviceMap::ValidFrom.into_iden(), GroupDeviceMap::ValidTo.into_iden())
+    }
+}
+
+fn rule_table(kind: &GroupKind) -> (DynIden, DynIden)
+{
+    match kind {
+        GroupKind::Model => (GroupModelRule::Table.into_iden(), GroupModelRule::GroupId.into_iden()),
+        GroupKind::Device | GroupKind::Gateway => (GroupDeviceRule::Table.into_iden(), GroupDeviceRule::GroupId.into_iden())
+    }
+}
+
+// recursive query of descendants of a group which start from base query of group ids
+fn group_tree_query(kind: &GroupKind, base: SelectStatement) -> WithQuery
+{
+    let (table, group_id, child_id) = child_table(kind);
+    let mut base = base;
+    let recursive = Query::select()
+        .column((table.clone(), child_id))
+        .from(table.clone())
+        .inner_join(GroupTree::Table,
+            Expr::col((table, group_id))
+            .equals((GroupTree::Table, GroupTree::GroupId))
+        )
+        .to_owned();
+    // union without all discards visited groups so the recursion ends even on a cycle
+    let cte = CommonTableExpression::new()
+        .query(base.union(UnionType::Distinct, recursive).to_owned())
+        .column(GroupTree::GroupId)
+        .table_name(GroupTree::Table)
+        .to_owned();
+    Query::select()
+        .column(GroupTree::GroupId)
+        .from(GroupTree::Table)
+        .to_owned()
+        .with(Query::with().cte(cte).recursive(true).to_owned())
+}
+
+// the group and its descendants as a sub query so a group subtree is expanded inside the statement using it
+fn group_subtree(kind: &GroupKind, id: Uuid) -> SimpleExpr
+{
+    let base = Query::select()
+        .expr(Expr::val(id))
+        .to_owned();
+    SimpleExpr::SubQuery(None, Box::new(group_tree_query(kind, base).into_sub_query_statement()))
+}
+
+// member column of a row was a static member of the group subtree at the row timestamp
+// or is a current member of a rule in the subtree, rules have no membership history
+pub(crate) async fn group_member_condition(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid,
+    member: impl IntoColumnRef,
+    timestamp: impl IntoColumnRef
+) -> Result<SimpleExpr, Error>
+{
+    group_member_expr(pool, kind, id, member.into_column_ref(), Some(timestamp.into_column_ref())).await
+}
+
+// member column was a static member of the group subtree in any period or is a current member of a rule in the subtree
+pub(crate) async fn group_member_any_condition(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid,
+    member: impl IntoColumnRef
+) -> Result<SimpleExpr, Error>
+{
+    group_member_expr(pool, kind, id, member.into_column_ref(), None).await
+}
+
+async fn group_member_expr(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid,
+    member: ColumnRef,
+    timestamp: Option<ColumnRef>
+) -> Result<SimpleExpr, Error>
+{
+    // label selectors of rules are parsed here, so only rules of the subtree are read before the statement
+    let (rule_table, rule_group_col) = rule_table(&kind);
+    let rules = select_group_rule_where(&mut *pool.acquire().await?, kind.clone(),
+        Expr::col((rule_table.clone(), rule_group_col.clone())).binary(BinOper::In, group_subtree(&kind, id))
+    ).await?;
+
+    // a group with a rule only has its rule members
+    let (table, group_col, member_col) = map_table(&kind);
+    let mut stmt = Query::select()
+        .expr(Expr::val(1))
+        .from(table.clone())
+        .and_where(Expr::col((table.clone(), group_col.clone())).binary(BinOper::In, group_subtree(&kind, id)))
+        .and_where(Expr::col((table.clone(), group_col)).not_in_subquery(Query::select()
+            .column(rule_group_col)
+            .from(rule_table)
+            .to_owned()
+        ))
+        .and_where(Expr::col((table.clone(), member_col)).equals(member.clone()))
+        .to_owned();
+    if let Some(timestamp) = timestamp {
+        let (valid_from_col, valid_to_col) = map_validity(&kind);
+        stmt = stmt
+            .and_where(Expr::col((table.clone(), valid_from_col)).lte(Expr::col(timestamp.clone())))
+            .and_where(Expr::col((table.clone(), valid_to_col.clone())).is_null()
+                .or(Expr::col((table, valid_to_col)).gt(Expr::col(timestamp)))
+            )
+            .to_owned();
+    }
+    let mut condition = Expr::exists(stmt);
+    for (_, rule) in &rules {
+        condition = condition.or(Expr::col(member.clone()).in_subquery(group_rule_member_query(&kind, rule)));
+    }
+    Ok(condition)
+}
+
+pub(crate) async fn select_group_member_at(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid,
+    timestamp: DateTime<Utc>
+) -> Result<Vec<Uuid>, Error>
+{
+    let (table, group_col, member_col) = map_table(&kind);
+    let (valid_from_col, valid_to_col) = map_validity(&kind);
+    let (sql, values) = Query::select()
+        .distinct()
+        .column(member_col.clone())
+        .from(table)
+        .and_where(Expr::col(group_col).eq(id))
+        .and_where(Expr::col(valid_from_col).lte(timestamp))
+        .and_where(Expr::col(valid_to_col.clone()).is_null().or(Expr::col(valid_to_col).gt(timestamp)))
+        .order_by(member_col, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(pool)
+        .await
+}
+
+pub(crate) async fn select_group_at(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid,
+    timestamp: DateTime<Utc>
+) -> Result<Option<GroupSchema>, Error>
+{
+    let mut group = match select_group(pool, kind.clone(), Some(id), None, None, None).await?.into_iter().next() {
+        Some(value) => value,
+        None => return Ok(None)
+    };
+    group.members = select_group_member_at(pool, kind, id, timestamp).await?;
+    Ok(Some(group))
+}
+
+pub(crate) async fn select_group_child(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid
+) -> Result<Vec<Uuid>, Error>
+{
+    let (table, group_id, child_id) = child_table(&kind);
+    let (sql, values) = Query::select()
+        .column(child_id.clone())
+        .from(table)
+        .and_where(Expr::col(group_id).eq(id))
+        .order_by(child_id, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(pool)
+        .await
+}
+
+pub(crate) async fn select_group_descendant(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid
+) -> Result<Vec<Uuid>, Error>
+{
+    let mut conn = pool.acquire().await?;
+    select_group_tree(&mut conn, &kind, id).await
+}
+
+async fn select_group_tree(conn: &mut PgConnection,
+    kind: &GroupKind,
+    id: Uuid
+) -> Result<Vec<Uuid>, Error>
+{
+    let (table, group_id, child_id) = child_table(kind);
+    let base = Query::select()
+        .column(child_id)
+        .from(table)
+        .and_where(Expr::col(group_id).eq(id))
+        .to_owned();
+    let (sql, values) = group_tree_query(kind, base)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(&mut *conn)
+        .await
+}
+
+pub(crate) async fn select_group_expanded(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid
+) -> Result<Option<GroupSchema>, Error>
+{
+    let mut group = match select_group(pool, kind.clone(), Some(id), None, None, None).await?.into_iter().next() {
+        Some(group) => group,
+        None => return Ok(None)
+    };
+    let mut group_ids = select_group_descendant(pool, kind.clone(), id).await?;
+    group_ids.push(id);
+
+    let mut members = Vec::new();
+    let rules = select_group_rule(pool, kind.clone(), &group_ids).await?;
+    for (_, rule) in &rules {
+        members.extend(select_group_rule_member(pool, kind.clone(), rule).await?);
+    }
+    group_ids.retain(|group_id| !rules.iter().any(|(rule_id, _)| rule_id == group_id));
+
+    let (table, group_id, member_id) = map_table(&kind);
+    let (_, valid_to) = map_validity(&kind);
+    let (sql, values) = Query::select()
+        .column(member_id)
+        .from(table)
+        .and_where(Expr::col(group_id).is_in(group_ids))
+        .and_where(Expr::col(valid_to).is_null())
+        .build_sqlx(PostgresQueryBuilder);
+
+    members.extend(sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get::<Uuid, _>(0))
+        .fetch_all(pool)
+        .await?);
+    members.sort();
+    members.dedup();
+    group.members = members;
+
+    Ok(Some(group))
+}
+
+pub(crate) async fn insert_group_child(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid,
+    child_id: Uuid
+) -> Result<(), Error>
+{
+    // both groups must exist with the same kind
+    let groups = select_group(pool, kind.clone(), None, Some(&[id, child_id]), None, None).await?;
+    if !groups.iter().any(|group| group.id == id) || !groups.iter().any(|group| group.id == child_id) {
+        return Err(Error::RowNotFound);
+    }
+    let (table, group_col, child_col) = child_table(&kind);
+    let mut tx = pool.begin().await?;
+
+    // child edges are locked against concurrent inserts until the checked edge is written
+    let sql = format!("LOCK TABLE \"{}\" IN SHARE ROW EXCLUSIVE MODE", table.to_string());
+    sqlx::query(&sql)
+        .execute(&mut *tx)
+        .await?;
+    // child can not be the group itself or one of its ancestors
+    if child_id == id || select_group_tree(&mut tx, &kind, child_id).await?.contains(&id) {
+        return Err(Error::InvalidArgument(GROUP_CHILD_CYCLE.to_string()));
+    }
+
+    let (sql, values) = Query::insert()
+        .into_table(table)
+        .columns([group_col, child_col])
+        .values([
+            id.into(),
+            child_id.into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub(crate) async fn delete_group_child(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid,
+    child_id: Uuid
+) -> Result<(), Error>
+{
+    let (table, group_col, child_col) = child_table(&kind);
+    let (sql, values) = Query::delete()
+        .from_table(table)
+        .and_where(Expr::col(group_col).eq(id))
+        .and_where(Expr::col(child_col).eq(child_id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn select_group_rule(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    ids: &[Uuid]
+) -> Result<Vec<(Uuid, GroupRuleSchema)>, Error>
+{
+    let mut conn = pool.acquire().await?;
+    read_group_rule(&mut conn, kind, ids).await
+}
+
+async fn read_group_rule(conn: &mut PgConnection,
+    kind: GroupKind,
+    ids: &[Uuid]
+) -> Result<Vec<(Uuid, GroupRuleSchema)>, Error>
+{
+    let (table, group_col) = rule_table(&kind);
+    select_group_rule_where(conn, kind, Expr::col((table, group_col)).is_in(ids.to_vec())).await
+}
+
+async fn select_group_rule_where(conn: &mut PgConnection,
+    kind: GroupKind,
+    condition: SimpleExpr
+) -> Result<Vec<(Uuid, GroupRuleSchema)>, Error>
+{
+    let stmt = match &kind {
         GroupKind::Model => {
-            stmt = stmt
-                .into_table(GroupModelMap::Table)
+            Query::select()
                 .columns([
-                    GroupModelMap::GroupId,
-                    GroupModelMap::ModelId
+                    (GroupModelRule::Table, GroupModelRule::GroupId),
+                    (GroupModelRule::Table, GroupModelRule::TypeId)
+                ])
+                .expr(Expr::val(Option::<Uuid>::None))
+                .columns([
+                    (GroupModelRule::Table, GroupModelRule::Name),
+                    (GroupModelRule::Table, GroupModelRule::Category),
+                    (GroupModelRule::Table, GroupModelRule::Label)
+                ])
+                .from(GroupModelRule::Table)
+                .and_where(condition)
+                .order_by((GroupModelRule::Table, GroupModelRule::GroupId), Order::Asc)
+                .to_owned()
+        },
+        GroupKind::Device | GroupKind::Gateway => {
+            Query::select()
+                .columns([
+                    (GroupDeviceRule::Table, GroupDeviceRule::GroupId),
+                    (GroupDeviceRule::Table, GroupDeviceRule::TypeId),
+                    (GroupDeviceRule::Table, GroupDeviceRule::GatewayId),
+                    (GroupDeviceRule::Table, GroupDeviceRule::Name)
+                ])
+                .expr(Expr::val(Option::<String>::None))
+                .column((GroupDeviceRule::Table, GroupDeviceRule::Label))
+                .from(GroupDeviceRule::Table)
+                .inner_join(GroupDevice::Table,
+                    Expr::col((GroupDeviceRule::Table, GroupDeviceRule::GroupId))
+                    .equals((GroupDevice::Table, GroupDevice::GroupId))
+                )
+                .and_where(condition)
+                .and_where(Expr::col((GroupDevice::Table, GroupDevice::Kind)).eq(kind == GroupKind::Gateway))
+                .order_by((GroupDeviceRule::Table, GroupDeviceRule::GroupId), Order::Asc)
+                .to_owned()
+        }
+    };
+    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let label: String = row.get(5);
+            (row.get(0), GroupRuleSchema {
+                type_id: row.get(1),
+                gateway_id: row.get(2),
+                name: row.get(3),
+                category: row.get(4),
+                labels: LabelSelector::parse(&label).unwrap_or_default()
+            })
+        })
+        .fetch_all(conn)
+        .await
+}
+
+pub(crate) async fn select_group_rule_member(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    rule: &GroupRuleSchema
+) -> Result<Vec<Uuid>, Error>
+{
+    let (sql, values) = group_rule_member_query(&kind, rule)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let mut members: Vec<Uuid> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(pool)
+        .await?;
+    members.sort();
+
+    Ok(members)
+}
+
+// query of model or device ids matching a group rule
+fn group_rule_member_query(kind: &GroupKind, rule: &GroupRuleSchema) -> SelectStatement
+{
+    let mut condition = Condition::all();
+    match kind {
+        GroupKind::Model => {
+            if let Some(type_id) = rule.type_id {
+                condition = condition.add(Expr::col((Model::Table, Model::ModelId)).in_subquery(Query::select()
+                    .column(DeviceTypeModel::ModelId)
+                    .from(DeviceTypeModel::Table)
+                    .and_where(Expr::col(DeviceTypeModel::TypeId).eq(type_id))
+                    .to_owned()
+                ));
+            }
+            if let Some(name) = &rule.name {
+                condition = condition.add(Expr::col((Model::Table, Model::Name)).like(name));
+            }
+            if let Some(category) = &rule.category {
+                condition = condition.add(Expr::col((Model::Table, Model::Category)).eq(category));
+            }
+            Query::select()
+                .column((Model::Table, Model::ModelId))
+                .from(Model::Table)
+                .cond_where(condition.add(label_condition(&LabelKind::Model, &rule.labels)))
+                .to_owned()
+        },
+        GroupKind::Device | GroupKind::Gateway => {
+            if *kind == GroupKind::Gateway {
+                condition = condition.add(Expr::col((Device::Table, Device::DeviceId)).equals((Device::Table, Device::GatewayId)));
+            }
+            if let Some(type_id) = rule.type_id {
+                condition = condition.add(Expr::col((Device::Table, Device::TypeId)).eq(type_id));
+            }
+            if let Some(gateway_id) = rule.gateway_id {
+                condition = condition.add(Expr::col((Device::Table, Device::GatewayId)).eq(gateway_id));
+            }
+            if let Some(name) = &rule.name {
+                condition = condition.add(Expr::col((Device::Table, Device::Name)).like(name));
+            }
+            Query::select()
+                .column((Device::Table, Device::DeviceId))
+                .from(Device::Table)
+                .cond_where(condition.add(label_condition(&LabelKind::Device, &rule.labels)))
+                .to_owned()
+        }
+    }
+}
+
+pub(crate) async fn insert_group_rule(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid,
+    rule: &GroupRuleSchema
+) -> Result<(), Error>
+{
+    if select_group(pool, kind.clone(), Some(id), None, None, None).await?.is_empty() {
+        return Err(Error::RowNotFound);
+    }
+    // selector is stored as text so it must be read back as the same selector
+    let label = rule.labels.to_string();
+    if LabelSelector::parse(&label).as_ref() != Ok(&rule.labels) {
+        return Err(Error::InvalidArgument(GROUP_RULE_LABEL_INVALID.to_string()));
+    }
+    let stmt = match &kind {
+        GroupKind::Model => {
+            Query::insert()
+                .into_table(GroupModelRule::Table)
+                .columns([
+                    GroupModelRule::GroupId,
+                    GroupModelRule::TypeId,
+                    GroupModelRule::Name,
+                    GroupModelRule::Category,
+                    GroupModelRule::Label
                 ])
                 .values([
                     id.into(),
-                    member_id.into()
+                    rule.type_id.into(),
+                    rule.name.clone().into(),
+                    rule.category.clone().into(),
+                    label.into()
                 ])
                 .unwrap_or(&mut sea_query::InsertStatement::default())
-                .to_owned();
+                .on_conflict(OnConflict::column(GroupModelRule::GroupId)
+                    .update_columns([GroupModelRule::TypeId, GroupModelRule::Name, GroupModelRule::Category, GroupModelRule::Label])
+                    .to_owned()
+                )
+                .to_owned()
         },
         GroupKind::Device | GroupKind::Gateway => {
-            stmt = stmt
-                .into_table(GroupDeviceMap::Table)
+            Query::insert()
+                .into_table(GroupDeviceRule::Table)
                 .columns([
-                    GroupDeviceMap::GroupId,
-                    GroupDeviceMap::DeviceId
+                    GroupDeviceRule::GroupId,
+                    GroupDeviceRule::TypeId,
+                    GroupDeviceRule::GatewayId,
+                    GroupDeviceRule::Name,
+                    GroupDeviceRule::Label
                 ])
                 .values([
                     id.into(),
-                    member_id.into()
+                    rule.type_id.into(),
+                    rule.gateway_id.into(),
+                    rule.name.clone().into(),
+                    label.into()
                 ])
                 .unwrap_or(&mut sea_query::InsertStatement::default())
-                .to_owned();
+                .on_conflict(OnConflict::column(GroupDeviceRule::GroupId)
+                    .update_columns([GroupDeviceRule::TypeId, GroupDeviceRule::GatewayId, GroupDeviceRule::Name, GroupDeviceRule::Label])
+                    .to_owned()
+                )
+                .to_owned()
         }
-    }
+    };
     let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
 
     sqlx::query_with(&sql, values)
@@ -311,30 +1024,25 @@ pub(crate) async fn insert_group_map(pool: &Pool<Postgres>,
     Ok(())
 }
 
-pub(crate) async fn delete_group_map(pool: &Pool<Postgres>, 
+pub(crate) async fn delete_group_rule(pool: &Pool<Postgres>,
     kind: GroupKind,
-    id: Uuid,
-    member_id: Uuid
-) -> Result<(), Error> 
+    id: Uuid
+) -> Result<(), Error>
 {
-    let mut stmt = Query::delete().to_owned();
-    match &kind {
+    let (sql, values) = match &kind {
         GroupKind::Model => {
-            stmt = stmt
-                .from_table(GroupModelMap::Table)
-                .and_where(Expr::col(GroupModelMap::GroupId).eq(id))
-                .and_where(Expr::col(GroupModelMap::ModelId).eq(member_id))
-                .to_owned();
+            Query::delete()
+                .from_table(GroupModelRule::Table)
+                .and_where(Expr::col(GroupModelRule::GroupId).eq(id))
+                .build_sqlx(PostgresQueryBuilder)
         },
         GroupKind::Device | GroupKind::Gateway => {
-            stmt = stmt
-                .from_table(GroupDeviceMap::Table)
-                .and_where(Expr::col(GroupDeviceMap::GroupId).eq(id))
-                .and_where(Expr::col(GroupDeviceMap::DeviceId).eq(member_id))
-                .to_owned();
+            Query::delete()
+                .from_table(GroupDeviceRule::Table)
+                .and_where(Expr::col(GroupDeviceRule::GroupId).eq(id))
+                .build_sqlx(PostgresQueryBuilder)
         }
-    }
-    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+    };
 
     sqlx::query_with(&sql, values)
         .execute(pool)
@@ -342,3 +1050,38 @@ pub(crate) async fn delete_group_map(pool: &Pool<Postgres>,
 
     Ok(())
 }
+
+// snapshot current members of a dynamic group into its static members and remove the rule
+pub(crate) async fn materialize_group_rule(pool: &Pool<Postgres>,
+    kind: GroupKind,
+    id: Uuid
+) -> Result<Vec<Uuid>, Error>
+{
+    let rule = match select_group_rule(pool, kind.clone(), &[id]).await?.into_iter().next() {
+        Some((_, rule)) => rule,
+        None => return Err(Error::RowNotFound)
+    };
+    let members = select_group_rule_member(pool, kind.clone(), &rule).await?;
+
+    let mut tx = pool.begin().await?;
+
+    replace_group_map(&mut tx, kind.clone(), id, &members).await?;
+
+    let (sql, values) = match &kind {
+        GroupKind::Model => Query::delete()
+            .from_table(GroupModelRule::Table)
+            .and_where(Expr::col(GroupModelRule::GroupId).eq(id))
+            .build_sqlx(PostgresQueryBuilder),
+        GroupKind::Device | GroupKind::Gateway => Query::delete()
+            .from_table(GroupDeviceRule::Table)
+            .and_where(Expr::col(GroupDeviceRule::GroupId).eq(id))
+            .build_sqlx(PostgresQueryBuilder)
+    };
+    sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(members)
+}
@@ -0,0 +1,178 @@
+use sqlx::{Pool, Row, Error};
+use sqlx::postgres::{Postgres, PgRow};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, OnConflict, Condition, SimpleExpr, DynIden, IntoIden};
+use sea_query_binder::SqlxBinder;
+use uuid::Uuid;
+use std::slice::from_ref;
+
+use crate::schema::model::Model;
+use crate::schema::device::Device;
+use crate::schema::set::Set;
+use crate::schema::label::{DeviceLabel, ModelLabel, SetLabel, LabelKind, LabelSchema, LabelSelector, LabelRequirement};
+
+struct LabelTable {
+    table: DynIden,
+    id: DynIden,
+    key: DynIden,
+    value: DynIden,
+    parent: DynIden,
+    parent_id: DynIden
+}
+
+impl From<&LabelKind> for LabelTable {
+    fn from(kind: &LabelKind) -> Self {
+        match kind {
+            LabelKind::Device => LabelTable {
+                table: DeviceLabel::Table.into_iden(),
+                id: DeviceLabel::DeviceId.into_iden(),
+                key: DeviceLabel::Key.into_iden(),
+                value: DeviceLabel::Value.into_iden(),
+                parent: Device::Table.into_iden(),
+                parent_id: Device::DeviceId.into_iden()
+            },
+            LabelKind::Model => LabelTable {
+                table: ModelLabel::Table.into_iden(),
+                id: ModelLabel::ModelId.into_iden(),
+                key: ModelLabel::Key.into_iden(),
+                value: ModelLabel::Value.into_iden(),
+                parent: Model::Table.into_iden(),
+                parent_id: Model::ModelId.into_iden()
+            },
+            LabelKind::Set => LabelTable {
+                table: SetLabel::Table.into_iden(),
+                id: SetLabel::SetId.into_iden(),
+                key: SetLabel::Key.into_iden(),
+                value: SetLabel::Value.into_iden(),
+                parent: Set::Table.into_iden(),
+                parent_id: Set::SetId.into_iden()
+            }
+        }
+    }
+}
+
+pub(crate) async fn select_label(pool: &Pool<Postgres>,
+    kind: LabelKind,
+    id: Uuid
+) -> Result<Vec<LabelSchema>, Error>
+{
+    let t = LabelTable::from(&kind);
+    let (sql, values) = Query::select()
+        .columns([t.id.clone(), t.key.clone(), t.value.clone()])
+        .from(t.table)
+        .and_where(Expr::col(t.id).eq(id))
+        .order_by(t.key, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            LabelSchema {
+                id: row.get(0),
+                key: row.get(1),
+                value: row.get(2)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+// condition on parent table rows (device, model or set) matching the selector
+pub(crate) fn label_condition(kind: &LabelKind, selector: &LabelSelector) -> Condition
+{
+    let t = LabelTable::from(kind);
+    // label of the parent row with the key exists, optionally with its value in given values
+    let exists = |key: &str, values: Option<&[String]>| -> SimpleExpr {
+        let mut stmt = Query::select()
+            .expr(Expr::val(1))
+            .from(t.table.clone())
+            .and_where(Expr::col((t.table.clone(), t.id.clone())).equals((t.parent.clone(), t.parent_id.clone())))
+            .and_where(Expr::col((t.table.clone(), t.key.clone())).eq(key))
+            .to_owned();
+        if let Some(values) = values {
+            stmt = stmt.and_where(Expr::col((t.table.clone(), t.value.clone())).is_in(values.to_vec())).to_owned();
+        }
+        Expr::exists(stmt)
+    };
+    let mut condition = Condition::all();
+    for requirement in &selector.requirements {
+        condition = condition.add(match requirement {
+            LabelRequirement::Equal(key, value) => exists(key, Some(from_ref(value))),
+            LabelRequirement::NotEqual(key, value) => exists(key, Some(from_ref(value))).not(),
+            LabelRequirement::In(key, values) => exists(key, Some(values)),
+            LabelRequirement::NotIn(key, values) => exists(key, Some(values)).not(),
+            LabelRequirement::Exists(key) => exists(key, None),
+            LabelRequirement::NotExists(key) => exists(key, None).not()
+        });
+    }
+    condition
+}
+
+pub(crate) async fn select_label_ids(pool: &Pool<Postgres>,
+    kind: LabelKind,
+    selector: &LabelSelector
+) -> Result<Vec<Uuid>, Error>
+{
+    let t = LabelTable::from(&kind);
+    let (sql, values) = Query::select()
+        .column((t.parent.clone(), t.parent_id.clone()))
+        .from(t.parent.clone())
+        .cond_where(label_condition(&kind, selector))
+        .order_by((t.parent, t.parent_id), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(pool)
+        .await
+}
+
+pub(crate) async fn insert_label(pool: &Pool<Postgres>,
+    kind: LabelKind,
+    id: Uuid,
+    key: &str,
+    value: &str
+) -> Result<(), Error>
+{
+    let t = LabelTable::from(&kind);
+    let (sql, values) = Query::insert()
+        .into_table(t.table)
+        .columns([t.id.clone(), t.key.clone(), t.value.clone()])
+        .values([
+            id.into(),
+            key.into(),
+            value.into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .on_conflict(OnConflict::columns([t.id, t.key])
+            .update_column(t.value)
+            .to_owned()
+        )
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn delete_label(pool: &Pool<Postgres>,
+    kind: LabelKind,
+    id: Uuid,
+    key: &str
+) -> Result<(), Error>
+{
+    let t = LabelTable::from(&kind);
+    let (sql, values) = Query::delete()
+        .from_table(t.table)
+        .and_where(Expr::col(t.id).eq(id))
+        .and_where(Expr::col(t.key).eq(key))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use sqlx::{Pool, Row, Error};
+use sqlx::postgres::{Postgres, PgRow};
+use sqlx::types::chrono::{DateTime, Utc};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, Func, OnConflict, Condition, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use uuid::Uuid;
+
+use crate::schema::device::Device;
+use crate::schema::location::{DeviceLocation, DeviceLocationSchema, EARTH_RADIUS, is_valid, radius_bounds};
+use crate::operation::LOCATION_INVALID;
+
+pub(crate) async fn select_device_location(pool: &Pool<Postgres>,
+    id: Uuid,
+    begin: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>
+) -> Result<Vec<DeviceLocationSchema>, Error>
+{
+    let mut stmt = Query::select()
+        .columns([
+            DeviceLocation::DeviceId,
+            DeviceLocation::Timestamp,
+            DeviceLocation::Latitude,
+            DeviceLocation::Longitude,
+            DeviceLocation::Altitude
+        ])
+        .from(DeviceLocation::Table)
+        .and_where(Expr::col(DeviceLocation::DeviceId).eq(id))
+        .to_owned();
+    if let Some(begin) = begin {
+        stmt = stmt.and_where(Expr::col(DeviceLocation::Timestamp).gte(begin)).to_owned();
+    }
+    if let Some(end) = end {
+        stmt = stmt.and_where(Expr::col(DeviceLocation::Timestamp).lte(end)).to_owned();
+    }
+    let (sql, values) = stmt
+        .order_by(DeviceLocation::Timestamp, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            DeviceLocationSchema {
+                device_id: row.get(0),
+                timestamp: row.get(1),
+                latitude: row.get(2),
+                longitude: row.get(3),
+                altitude: row.get(4)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+pub(crate) async fn update_device_location(pool: &Pool<Postgres>,
+    id: Uuid,
+    timestamp: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    altitude: Option<f64>
+) -> Result<(), Error>
+{
+    if !is_valid(latitude, longitude) {
+        return Err(Error::InvalidArgument(LOCATION_INVALID.to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let (sql, values) = Query::select()
+        .expr(Func::max(Expr::col(DeviceLocation::Timestamp)))
+        .from(DeviceLocation::Table)
+        .and_where(Expr::col(DeviceLocation::DeviceId).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+    let latest: Option<DateTime<Utc>> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_one(&mut *tx)
+        .await?;
+
+    // current device location only follow the latest location in history
+    if latest.map(|latest| timestamp >= latest).unwrap_or(true) {
+        let (sql, values) = Query::update()
+            .table(Device::Table)
+            .values([
+                (Device::Latitude, latitude.into()),
+                (Device::Longitude, longitude.into()),
+                (Device::Altitude, altitude.into())
+            ])
+            .and_where(Expr::col(Device::DeviceId).eq(id))
+            .build_sqlx(PostgresQueryBuilder);
+        let result = sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
+    }
+
+    let (sql, values) = Query::insert()
+        .into_table(DeviceLocation::Table)
+        .columns([
+            DeviceLocation::DeviceId,
+            DeviceLocation::Timestamp,
+            DeviceLocation::Latitude,
+            DeviceLocation::Longitude,
+            DeviceLocation::Altitude
+        ])
+        .values([
+            id.into(),
+            timestamp.into(),
+            latitude.into(),
+            longitude.into(),
+            altitude.into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .on_conflict(OnConflict::columns([DeviceLocation::DeviceId, DeviceLocation::Timestamp])
+            .update_columns([DeviceLocation::Latitude, DeviceLocation::Longitude, DeviceLocation::Altitude])
+            .to_owned()
+        )
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub(crate) async fn delete_device_location(pool: &Pool<Postgres>,
+    id: Uuid
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::update()
+        .table(Device::Table)
+        .values([
+            (Device::Latitude, Option::<f64>::None.into()),
+            (Device::Longitude, Option::<f64>::None.into()),
+            (Device::Altitude, Option::<f64>::None.into())
+        ])
+        .and_where(Expr::col(Device::DeviceId).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let result = sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::RowNotFound);
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn select_device_id_within_radius(pool: &Pool<Postgres>,
+    latitude: f64,
+    longitude: f64,
+    meters: f64
+) -> Result<Vec<Uuid>, Error>
+{
+    if !is_valid(latitude, longitude) || meters.is_nan() || meters < 0.0 {
+        return Err(Error::InvalidArgument(LOCATION_INVALID.to_string()));
+    }
+    // haversine distance in plain sql, the bounding box condition narrows rows using location index
+    let distance = Expr::cust_with_values(
+        "2 * $1 * asin(least(1, sqrt(power(sin(radians(\"device\".\"latitude\" - $2) / 2), 2) + cos(radians($2)) * cos(radians(\"device\".\"latitude\")) * power(sin(radians(\"device\".\"longitude\" - $3) / 2), 2))))",
+        [EARTH_RADIUS, latitude, longitude]
+    );
+    let (min_lat, min_lon, max_lat, max_lon) = radius_bounds(latitude, longitude, meters);
+    let (sql, values) = Query::select()
+        .column((Device::Table, Device::DeviceId))
+        .from(Device::Table)
+        .cond_where(bbox_condition(min_lat, min_lon, max_lat, max_lon))
+        .and_where(Expr::expr(distance.clone()).lte(meters))
+        .order_by_expr(distance, Order::Asc)
+        .order_by((Device::Table, Device::DeviceId), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(pool)
+        .await
+}
+
+pub(crate) async fn select_device_id_in_bbox(pool: &Pool<Postgres>,
+    min_latitude: f64,
+    min_longitude: f64,
+    max_latitude: f64,
+    max_longitude: f64
+) -> Result<Vec<Uuid>, Error>
+{
+    if !is_valid(min_latitude, min_longitude) || !is_valid(max_latitude, max_longitude) || min_latitude > max_latitude {
+        return Err(Error::InvalidArgument(LOCATION_INVALID.to_string()));
+    }
+    let (sql, values) = Query::select()
+        .column((Device::Table, Device::DeviceId))
+        .from(Device::Table)
+        .cond_where(bbox_condition(min_latitude, min_longitude, max_latitude, max_longitude))
+        .order_by((Device::Table, Device::DeviceId), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(pool)
+        .await
+}
+
+// box with min longitude greater than max longitude crosses the antimeridian
+fn bbox_condition(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Condition
+{
+    let longitude: SimpleExpr = if min_lon <= max_lon {
+        Expr::col((Device::Table, Device::Longitude)).between(min_lon, max_lon)
+    } else {
+        Expr::col((Device::Table, Device::Longitude)).gte(min_lon)
+            .or(Expr::col((Device::Table, Device::Longitude)).lte(max_lon))
+    };
+    Condition::all()
+        .add(Expr::col((Device::Table, Device::Latitude)).between(min_lat, max_lat))
+        .add(longitude)
+}
@@ -1,5 +1,5 @@
 use sqlx::{Pool, Row, Error};
-use sqlx::postgres::{Postgres, PgRow};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
 use sqlx::types::chrono::{DateTime, Utc};
 use sea_query::{PostgresQueryBuilder, Query, Expr, Order, Func};
 use sea_query_binder::SqlxBinder;
@@ -126,6 +126,19 @@ pub(crate) async fn insert_log(pool: &Pool<Postgres>,
     value: DataValue,
     tag: Option<i16>
 ) -> Result<i32, Error>
+{
+    let mut conn = pool.acquire().await?;
+    write_log(&mut conn, timestamp, device_id, model_id, value, tag).await
+}
+
+// insert a log with a connection so it is written inside transaction of the caller
+pub(crate) async fn write_log(conn: &mut PgConnection,
+    timestamp: DateTime<Utc>,
+    device_id: Option<Uuid>,
+    model_id: Option<Uuid>,
+    value: DataValue,
+    tag: Option<i16>
+) -> Result<i32, Error>
 {
     let bytes = value.to_bytes();
     let type_ = i16::from(value.get_type());
@@ -153,7 +166,7 @@ pub(crate) async fn insert_log(pool: &Pool<Postgres>,
         .build_sqlx(PostgresQueryBuilder);
 
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
 
     let sql = Query::select()
@@ -162,7 +175,7 @@ pub(crate) async fn insert_log(pool: &Pool<Postgres>,
         .to_string(PostgresQueryBuilder);
     let id: i32 = sqlx::query(&sql)
         .map(|row: PgRow| row.get(0))
-        .fetch_one(pool)
+        .fetch_one(&mut *conn)
         .await?;
 
     Ok(id)
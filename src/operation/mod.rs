@@ -7,7 +7,40 @@ pub(crate) mod data;
 pub(crate) mod buffer;
 pub(crate) mod slice;
 pub(crate) mod log;
+pub(crate) mod bundle;
+pub(crate) mod calibration;
+pub(crate) mod derived;
+pub(crate) mod alarm;
+pub(crate) mod purge;
+pub(crate) mod status;
+pub(crate) mod provision;
+pub(crate) mod label;
+pub(crate) mod location;
+pub(crate) mod aggregate;
 
 const EMPTY_LENGTH_UNMATCH: &str = "One or more input array arguments are empty or doesn't have the same length";
 const MODEL_NOT_EXISTS: &str = "Input model argument doesn't exist";
 const DATA_TYPE_UNMATCH: &str = "The type of input data argument doesn't match with the model";
+const BUNDLE_VERSION_UNSUPPORTED: &str = "The version of input bundle argument is not supported";
+const BUNDLE_DATA_TYPE_CHANGE: &str = "Input bundle argument changes the data type of a model which already has data";
+const DERIVED_NOT_EXISTS: &str = "Input model argument is not a derived model";
+const DERIVED_EXPRESSION_INVALID: &str = "One or more input expression arguments are invalid or doesn't match with the model";
+const SERIAL_NUMBER_DUPLICATE: &str = "Input serial number argument is already used by another device";
+const SERIAL_NUMBER_AMBIGUOUS: &str = "Input serial number argument is used by devices of more than one type";
+const DEVICE_PARENT_CYCLE: &str = "Input parent argument is the device itself or one of its descendants";
+const DERIVED_TAG_INVALID: &str = "Input tag argument is not an analysis tag";
+const DEVICE_MODEL_INCOMPATIBLE: &str = "Input model argument is not linked to the type of input device argument";
+const CONFIG_KEY_DEFAULT_INVALID: &str = "Input default argument doesn't match with the type or range of the config key";
+const CONFIG_INVALID: &str = "Input config argument doesn't conform to the config keys declared by the device type";
+const GROUP_CHILD_CYCLE: &str = "Input child group argument is the group itself or one of its ancestors";
+const GROUP_MEMBER_PERIOD_INVALID: &str = "Input validity argument overlaps or precedes another membership period of the member";
+const AGGREGATE_FUNCTION_UNMATCH: &str = "The number of input function arguments doesn't match with the model data type";
+const SET_TEMPLATE_INDEX_INVALID: &str = "One or more input binding arguments refer to a slot that doesn't exist in the set template";
+const SET_TEMPLATE_TYPE_UNMATCH: &str = "The type of input device binding argument doesn't match with the set template slot";
+const SET_TEMPLATE_UNBOUND: &str = "One or more set template slots have no device binding and no single matching device in the gateway";
+const LOCATION_INVALID: &str = "Input latitude, longitude or radius argument is out of range";
+const GROUP_RULE_LABEL_INVALID: &str = "Input label selector argument contains a key or value which can't be stored in a group rule";
+const DATA_MODEL_DERIVED: &str = "Input model argument is a derived model which data can only be materialized";
+
+// rows of a multi row data insert, five parameters of a row keep a statement under the limit of 65535 parameters
+const DATA_INSERT_BATCH: usize = 10000;
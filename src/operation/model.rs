@@ -1,13 +1,16 @@
 use sqlx::{Pool, Row, Error};
-use sqlx::postgres::{Postgres, PgRow};
-use sea_query::{PostgresQueryBuilder, Query, Expr, Order, Func};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
+use sqlx::types::chrono::{DateTime, Utc};
+use sea_query::{PostgresQueryBuilder, Query, Expr, SimpleExpr, Order, Func};
 use sea_query_binder::SqlxBinder;
 use uuid::Uuid;
 
 use crate::schema::value::{DataValue, DataType};
-use crate::schema::model::{Model, ModelTag, ModelConfig, ModelSchema, ModelConfigSchema, TagSchema, ModelSchemaFlat};
+use crate::schema::model::{Model, ModelTag, ModelConfig, ModelConfigHistory, ModelSchema, ModelConfigSchema, ModelConfigHistorySchema, TagSchema, ModelSchemaFlat};
 use crate::schema::device::DeviceTypeModel;
 use crate::schema::set::SetMap;
+use crate::schema::group::GroupKind;
+use crate::operation::group::group_member_any_condition;
 
 pub(crate) async fn select_model(pool: &Pool<Postgres>, 
     id: Option<Uuid>,
@@ -16,6 +19,18 @@ pub(crate) async fn select_model(pool: &Pool<Postgres>,
     name: Option<&str>,
     category: Option<&str>
 ) -> Result<Vec<ModelSchema>, Error>
+{
+    let mut conn = pool.acquire().await?;
+    read_model(&mut conn, id, ids, type_id, name, category).await
+}
+
+pub(crate) async fn read_model(conn: &mut PgConnection,
+    id: Option<Uuid>,
+    ids: Option<&[Uuid]>,
+    type_id: Option<Uuid>,
+    name: Option<&str>,
+    category: Option<&str>
+) -> Result<Vec<ModelSchema>, Error>
 {
     let mut stmt = Query::select()
         .columns([
@@ -135,7 +150,7 @@ pub(crate) async fn select_model(pool: &Pool<Postgres>,
             // update model_schema_vec with updated model_schema
             model_schema_vec.push(model_schema.clone());
         })
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await?;
 
     Ok(model_schema_vec.into_iter().map(|schema| schema.into()).collect())
@@ -231,6 +246,145 @@ pub(crate) async fn delete_model(pool: &Pool<Postgres>,
     Ok(())
 }
 
+pub(crate) async fn clone_model(pool: &Pool<Postgres>,
+    source_id: Uuid,
+    id: Uuid,
+    category: Option<&str>,
+    name: Option<&str>,
+    description: Option<&str>,
+    with_type: bool
+) -> Result<Uuid, Error>
+{
+    let mut tx = pool.begin().await?;
+
+    let category: SimpleExpr = match category {
+        Some(value) => Expr::val(value).into(),
+        None => Expr::col(Model::Category).into()
+    };
+    let name: SimpleExpr = match name {
+        Some(value) => Expr::val(value).into(),
+        None => Expr::col(Model::Name).into()
+    };
+    let description: SimpleExpr = match description {
+        Some(value) => Expr::val(value).into(),
+        None => Expr::col(Model::Description).into()
+    };
+    let (sql, values) = Query::insert()
+        .into_table(Model::Table)
+        .columns([
+            Model::ModelId,
+            Model::Category,
+            Model::Name,
+            Model::Description,
+            Model::DataType
+        ])
+        .select_from(Query::select()
+            .expr(Expr::val(id))
+            .expr(category)
+            .expr(name)
+            .expr(description)
+            .column(Model::DataType)
+            .from(Model::Table)
+            .and_where(Expr::col(Model::ModelId).eq(source_id))
+            .to_owned()
+        )
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+
+    let result = sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::RowNotFound);
+    }
+
+    // copy configs ordered by id so the new serial ids keep the source order
+    let (sql, values) = Query::insert()
+        .into_table(ModelConfig::Table)
+        .columns([
+            ModelConfig::ModelId,
+            ModelConfig::Index,
+            ModelConfig::Name,
+            ModelConfig::Value,
+            ModelConfig::Type,
+            ModelConfig::Category
+        ])
+        .select_from(Query::select()
+            .expr(Expr::val(id))
+            .columns([
+                ModelConfig::Index,
+                ModelConfig::Name,
+                ModelConfig::Value,
+                ModelConfig::Type,
+                ModelConfig::Category
+            ])
+            .from(ModelConfig::Table)
+            .and_where(Expr::col(ModelConfig::ModelId).eq(source_id))
+            .order_by(ModelConfig::Id, Order::Asc)
+            .to_owned()
+        )
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    let (sql, values) = Query::insert()
+        .into_table(ModelTag::Table)
+        .columns([
+            ModelTag::ModelId,
+            ModelTag::Tag,
+            ModelTag::Name,
+            ModelTag::Members
+        ])
+        .select_from(Query::select()
+            .expr(Expr::val(id))
+            .columns([
+                ModelTag::Tag,
+                ModelTag::Name,
+                ModelTag::Members
+            ])
+            .from(ModelTag::Table)
+            .and_where(Expr::col(ModelTag::ModelId).eq(source_id))
+            .to_owned()
+        )
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    if with_type {
+        let (sql, values) = Query::insert()
+            .into_table(DeviceTypeModel::Table)
+            .columns([
+                DeviceTypeModel::TypeId,
+                DeviceTypeModel::ModelId
+            ])
+            .select_from(Query::select()
+                .column(DeviceTypeModel::TypeId)
+                .expr(Expr::val(id))
+                .from(DeviceTypeModel::Table)
+                .and_where(Expr::col(DeviceTypeModel::ModelId).eq(source_id))
+                .to_owned()
+            )
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .build_sqlx(PostgresQueryBuilder);
+
+        sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sync_model_config_history(&mut tx, None, Some(id), Utc::now()).await?;
+
+    tx.commit().await?;
+
+    Ok(id)
+}
+
 pub(crate) async fn select_model_config(pool: &Pool<Postgres>,
     id: Option<i32>,
     model_id: Option<Uuid>
@@ -312,8 +466,10 @@ pub(crate) async fn insert_model_config(pool: &Pool<Postgres>,
         .unwrap_or(&mut sea_query::InsertStatement::default())
         .build_sqlx(PostgresQueryBuilder);
 
+    let mut tx = pool.begin().await?;
+
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
     let sql = Query::select()
@@ -322,9 +478,13 @@ pub(crate) async fn insert_model_config(pool: &Pool<Postgres>,
         .to_string(PostgresQueryBuilder);
     let id: i32 = sqlx::query(&sql)
         .map(|row: PgRow| row.get(0))
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+    sync_model_config_history(&mut tx, Some(id), None, Utc::now()).await?;
+
+    tx.commit().await?;
+
     Ok(id)
 }
 
@@ -357,10 +517,16 @@ pub(crate) async fn update_model_config(pool: &Pool<Postgres>,
         .and_where(Expr::col(ModelConfig::Id).eq(id))
         .build_sqlx(PostgresQueryBuilder);
 
+    let mut tx = pool.begin().await?;
+
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
+    sync_model_config_history(&mut tx, Some(id), None, Utc::now()).await?;
+
+    tx.commit().await?;
+
     Ok(())
 }
 
@@ -373,8 +539,154 @@ pub(crate) async fn delete_model_config(pool: &Pool<Postgres>,
         .and_where(Expr::col(ModelConfig::Id).eq(id))
         .build_sqlx(PostgresQueryBuilder);
 
+    let mut tx = pool.begin().await?;
+
     sqlx::query_with(&sql, values)
-        .execute(pool)
+        .execute(&mut *tx)
+        .await?;
+
+    sync_model_config_history(&mut tx, Some(id), None, Utc::now()).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub(crate) async fn select_model_config_history(pool: &Pool<Postgres>,
+    model_ids: &[Uuid],
+    timestamp: Option<DateTime<Utc>>
+) -> Result<Vec<ModelConfigHistorySchema>, Error>
+{
+    let mut stmt = Query::select()
+        .columns([
+            ModelConfigHistory::ConfigId,
+            ModelConfigHistory::ModelId,
+            ModelConfigHistory::Index,
+            ModelConfigHistory::Name,
+            ModelConfigHistory::Value,
+            ModelConfigHistory::Type,
+            ModelConfigHistory::Category,
+            ModelConfigHistory::ValidFrom,
+            ModelConfigHistory::ValidTo
+        ])
+        .from(ModelConfigHistory::Table)
+        .and_where(Expr::col(ModelConfigHistory::ModelId).is_in(model_ids.to_vec()))
+        .to_owned();
+
+    if let Some(timestamp) = timestamp {
+        stmt = stmt
+            .and_where(Expr::col(ModelConfigHistory::ValidFrom).lte(timestamp))
+            .and_where(Expr::col(ModelConfigHistory::ValidTo).is_null()
+                .or(Expr::col(ModelConfigHistory::ValidTo).gt(timestamp))
+            )
+            .to_owned();
+    }
+    let (sql, values) = stmt
+        .order_by(ModelConfigHistory::ModelId, Order::Asc)
+        .order_by(ModelConfigHistory::Index, Order::Asc)
+        .order_by(ModelConfigHistory::ConfigId, Order::Asc)
+        .order_by(ModelConfigHistory::ValidFrom, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes = row.get(4);
+            let type_ = DataType::from(row.get::<i16,_>(5));
+            ModelConfigHistorySchema {
+                id: row.get(0),
+                model_id: row.get(1),
+                index: row.get(2),
+                name: row.get(3),
+                value: DataValue::from_bytes(bytes, type_),
+                category: row.get(6),
+                valid_from: row.get(7),
+                valid_to: row.get(8)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+// close history of configs which are changed or deleted and open history of new or changed configs
+// a new config is valid since the timestamp it is created, only configs existing before history was recorded are valid since epoch
+pub(crate) async fn sync_model_config_history(conn: &mut PgConnection,
+    id: Option<i32>,
+    model_id: Option<Uuid>,
+    timestamp: DateTime<Utc>
+) -> Result<(), Error>
+{
+    let mut stmt = Query::update()
+        .table(ModelConfigHistory::Table)
+        .value(ModelConfigHistory::ValidTo, timestamp)
+        .and_where(Expr::col(ModelConfigHistory::ValidTo).is_null())
+        .and_where(Expr::exists(Query::select()
+            .expr(Expr::val(1))
+            .from(ModelConfig::Table)
+            .and_where(Expr::col((ModelConfig::Table, ModelConfig::Id)).equals((ModelConfigHistory::Table, ModelConfigHistory::ConfigId)))
+            .and_where(Expr::col((ModelConfig::Table, ModelConfig::Index)).equals((ModelConfigHistory::Table, ModelConfigHistory::Index)))
+            .and_where(Expr::col((ModelConfig::Table, ModelConfig::Name)).equals((ModelConfigHistory::Table, ModelConfigHistory::Name)))
+            .and_where(Expr::col((ModelConfig::Table, ModelConfig::Value)).equals((ModelConfigHistory::Table, ModelConfigHistory::Value)))
+            .and_where(Expr::col((ModelConfig::Table, ModelConfig::Type)).equals((ModelConfigHistory::Table, ModelConfigHistory::Type)))
+            .and_where(Expr::col((ModelConfig::Table, ModelConfig::Category)).equals((ModelConfigHistory::Table, ModelConfigHistory::Category)))
+            .to_owned()
+        ).not())
+        .to_owned();
+    if let Some(id) = id {
+        stmt = stmt.and_where(Expr::col(ModelConfigHistory::ConfigId).eq(id)).to_owned();
+    }
+    if let Some(model_id) = model_id {
+        stmt = stmt.and_where(Expr::col(ModelConfigHistory::ModelId).eq(model_id)).to_owned();
+    }
+    let (sql, values) = stmt.build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    let mut select = Query::select()
+        .columns([
+            (ModelConfig::Table, ModelConfig::Id),
+            (ModelConfig::Table, ModelConfig::ModelId),
+            (ModelConfig::Table, ModelConfig::Index),
+            (ModelConfig::Table, ModelConfig::Name),
+            (ModelConfig::Table, ModelConfig::Value),
+            (ModelConfig::Table, ModelConfig::Type),
+            (ModelConfig::Table, ModelConfig::Category)
+        ])
+        .expr(Expr::val(timestamp))
+        .from(ModelConfig::Table)
+        .and_where(Expr::exists(Query::select()
+            .expr(Expr::val(1))
+            .from(ModelConfigHistory::Table)
+            .and_where(Expr::col((ModelConfigHistory::Table, ModelConfigHistory::ConfigId)).equals((ModelConfig::Table, ModelConfig::Id)))
+            .and_where(Expr::col((ModelConfigHistory::Table, ModelConfigHistory::ValidTo)).is_null())
+            .to_owned()
+        ).not())
+        .to_owned();
+    if let Some(id) = id {
+        select = select.and_where(Expr::col((ModelConfig::Table, ModelConfig::Id)).eq(id)).to_owned();
+    }
+    if let Some(model_id) = model_id {
+        select = select.and_where(Expr::col((ModelConfig::Table, ModelConfig::ModelId)).eq(model_id)).to_owned();
+    }
+    let (sql, values) = Query::insert()
+        .into_table(ModelConfigHistory::Table)
+        .columns([
+            ModelConfigHistory::ConfigId,
+            ModelConfigHistory::ModelId,
+            ModelConfigHistory::Index,
+            ModelConfigHistory::Name,
+            ModelConfigHistory::Value,
+            ModelConfigHistory::Type,
+            ModelConfigHistory::Category,
+            ModelConfigHistory::ValidFrom
+        ])
+        .select_from(select)
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
         .await?;
 
     Ok(())
@@ -482,6 +794,34 @@ pub(crate) async fn select_tag_members_set(pool: &Pool<Postgres>,
     Ok(tags)
 }
 
+pub(crate) async fn select_tag_members_group(pool: &Pool<Postgres>, 
+    group_model_id: Uuid,
+    tag: i16
+) -> Result<Vec<i16>, Error>
+{
+    let (sql, values) = Query::select()
+        .column(ModelTag::Members)
+        .from(ModelTag::Table)
+        .and_where(group_member_any_condition(pool, GroupKind::Model, group_model_id, (ModelTag::Table, ModelTag::ModelId)).await?)
+        .and_where(Expr::col(ModelTag::Tag).eq(tag))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let mut tags: Vec<i16> = vec![tag];
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes: Vec<u8> = row.get(0);
+            for chunk in bytes.chunks_exact(2) {
+                tags.push(i16::from_be_bytes([chunk[0], chunk[1]]));
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
 pub(crate) async fn insert_model_tag(pool: &Pool<Postgres>,
     model_id: Uuid,
     tag: i16,
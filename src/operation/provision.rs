@@ -0,0 +1,164 @@
+use sqlx::{Pool, Row, Error};
+use sqlx::postgres::{Postgres, PgRow};
+use sqlx::types::chrono::Utc;
+use sea_query::{PostgresQueryBuilder, Query, Expr, Condition};
+use sea_query_binder::SqlxBinder;
+use uuid::Uuid;
+
+use crate::schema::device::{Device, DeviceType, DeviceConfig, SerialScope};
+use crate::schema::group::{GroupDevice, GroupKind};
+use crate::schema::provision::{DeviceManifest, DeviceManifestConfig, ProvisionExisting, ProvisionReportSchema, ProvisionStatus};
+use crate::operation::device::{sync_device_config_history, lock_serial_number};
+use crate::operation::types::select_type_config_key;
+use crate::operation::group::insert_group_period;
+
+pub(crate) async fn provision_device(pool: &Pool<Postgres>,
+    manifest: &DeviceManifest,
+    scope: SerialScope
+) -> Result<Vec<ProvisionReportSchema>, Error>
+{
+    let rows = &manifest.rows;
+    let type_ids: Vec<Uuid> = rows.iter().map(|row| row.type_id).collect();
+    let ids: Vec<Uuid> = rows.iter().filter_map(|row| row.id).collect();
+    let gateway_ids: Vec<Uuid> = rows.iter().filter_map(|row| row.gateway_id).collect();
+    let serial_numbers: Vec<String> = rows.iter().map(|row| row.serial_number.clone()).collect();
+    let group_ids: Vec<Uuid> = rows.iter().flat_map(|row| row.group_ids.clone()).collect();
+
+    // validation read the same transaction which the rows are written in
+    let mut tx = pool.begin().await?;
+
+    // serial numbers are locked in order so concurrent provisioning can't deadlock
+    let mut locks = serial_numbers.clone();
+    locks.sort();
+    locks.dedup();
+    for serial_number in &locks {
+        lock_serial_number(&mut tx, serial_number).await?;
+    }
+
+    let (sql, values) = Query::select()
+        .column(DeviceType::TypeId)
+        .from(DeviceType::Table)
+        .and_where(Expr::col(DeviceType::TypeId).is_in(type_ids))
+        .build_sqlx(PostgresQueryBuilder);
+    let type_ids: Vec<Uuid> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let (sql, values) = Query::select()
+        .columns([Device::DeviceId, Device::GatewayId, Device::TypeId, Device::SerialNumber])
+        .from(Device::Table)
+        .cond_where(Condition::any()
+            .add(Expr::col(Device::DeviceId).is_in(ids))
+            .add(Expr::col(Device::DeviceId).is_in(gateway_ids))
+            .add(Expr::col(Device::SerialNumber).is_in(serial_numbers))
+        )
+        .build_sqlx(PostgresQueryBuilder);
+    let devices: Vec<(Uuid, Uuid, Uuid, String)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get(0), row.get(1), row.get(2), row.get(3)))
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let (sql, values) = Query::select()
+        .columns([GroupDevice::GroupId, GroupDevice::Kind])
+        .from(GroupDevice::Table)
+        .and_where(Expr::col(GroupDevice::GroupId).is_in(group_ids))
+        .build_sqlx(PostgresQueryBuilder);
+    let groups: Vec<(Uuid, GroupKind)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get(0), GroupKind::from(row.get::<bool,_>(1))))
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let config_keys = select_type_config_key(&mut tx, &type_ids).await?;
+    let mut manifest = manifest.clone();
+    manifest.coerce_configs(&config_keys);
+    let rows = &manifest.rows;
+
+    let existing = ProvisionExisting {
+        type_ids,
+        gateway_ids: devices.iter().filter(|(id, gateway_id, _, _)| id == gateway_id).map(|(id, _, _, _)| *id).collect(),
+        device_ids: devices.iter().map(|(id, _, _, _)| *id).collect(),
+        serial_numbers: devices.iter().map(|(_, _, type_id, serial_number)| (serial_number.clone(), *type_id)).collect(),
+        group_ids: groups.iter().filter(|(_, kind)| *kind == GroupKind::Device).map(|(id, _)| *id).collect(),
+        gateway_group_ids: groups.iter().filter(|(_, kind)| *kind == GroupKind::Gateway).map(|(id, _)| *id).collect(),
+        config_keys
+    };
+    let mut reports = manifest.validate(&existing, scope);
+    // nothing is written when any row is invalid
+    if reports.iter().any(|report| report.status != ProvisionStatus::Valid) {
+        return Ok(reports);
+    }
+
+    let timestamp = Utc::now();
+
+    for (row, report) in rows.iter().zip(reports.iter_mut()) {
+        let gateway_id = row.gateway_id.unwrap_or(report.id);
+        let parent_id = if gateway_id == report.id { None } else { Some(gateway_id) };
+        let (sql, values) = Query::insert()
+            .into_table(Device::Table)
+            .columns([
+                Device::DeviceId,
+                Device::GatewayId,
+                Device::TypeId,
+                Device::SerialNumber,
+                Device::Name,
+                Device::Description,
+                Device::ParentId
+            ])
+            .values([
+                report.id.into(),
+                gateway_id.into(),
+                row.type_id.into(),
+                row.serial_number.clone().into(),
+                row.name.clone().into(),
+                row.description.clone().into(),
+                parent_id.into()
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?;
+
+        // configs which are not in manifest are created from declared default values
+        let defaults = existing.config_keys.iter()
+            .filter(|key| key.type_id == row.type_id)
+            .filter(|key| !row.configs.iter().any(|cfg| cfg.name == key.name && cfg.category == key.category))
+            .filter_map(|key| key.default.clone().map(|value| DeviceManifestConfig { name: key.name.clone(), value, category: key.category.clone() }));
+        let configs: Vec<DeviceManifestConfig> = row.configs.iter().cloned().chain(defaults).collect();
+        for config in &configs {
+            let (sql, values) = Query::insert()
+                .into_table(DeviceConfig::Table)
+                .columns([
+                    DeviceConfig::DeviceId,
+                    DeviceConfig::Name,
+                    DeviceConfig::Value,
+                    DeviceConfig::Type,
+                    DeviceConfig::Category
+                ])
+                .values([
+                    report.id.into(),
+                    config.name.clone().into(),
+                    config.value.to_bytes().into(),
+                    i16::from(config.value.get_type()).into(),
+                    config.category.clone().into()
+                ])
+                .unwrap_or(&mut sea_query::InsertStatement::default())
+                .build_sqlx(PostgresQueryBuilder);
+            sqlx::query_with(&sql, values)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sync_device_config_history(&mut tx, None, Some(report.id), timestamp).await?;
+
+        for group_id in &row.group_ids {
+            let kind = if gateway_id == report.id { GroupKind::Gateway } else { GroupKind::Device };
+            insert_group_period(&mut tx, &kind, *group_id, report.id, None).await?;
+        }
+        report.status = ProvisionStatus::Created;
+    }
+
+    tx.commit().await?;
+
+    Ok(reports)
+}
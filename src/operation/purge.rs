@@ -0,0 +1,373 @@
+use sqlx::{Pool, Row, Error};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Condition, Func, Asterisk, Order, Iden};
+use sea_query_binder::SqlxBinder;
+use uuid::Uuid;
+
+use crate::schema::model::{Model, ModelConfig, ModelConfigHistory, ModelTag};
+use crate::schema::device::{Device, DeviceType, DeviceTypeModel, DeviceTypeConfig, DeviceConfig, DeviceConfigHistory};
+use crate::schema::group::{GroupModelMap, GroupDeviceMap};
+use crate::schema::set::{SetMap, SetTemplateMap};
+use crate::schema::data::{Data, DataArchive};
+use crate::schema::buffer::{DataBuffer, DataBufferArchive};
+use crate::schema::slice::{SliceData, SliceDataArchive};
+use crate::schema::derived::{ModelDerived, ModelDerivedSource};
+use crate::schema::alarm::Alarm;
+use crate::schema::status::DeviceStatus;
+use crate::schema::label::{DeviceLabel, ModelLabel};
+use crate::schema::location::DeviceLocation;
+use crate::schema::purge::PurgeReportSchema;
+
+pub(crate) async fn purge_device(pool: &Pool<Postgres>,
+    ids: &[Uuid],
+    dry_run: bool,
+    archive: bool
+) -> Result<PurgeReportSchema, Error>
+{
+    let mut tx = pool.begin().await?;
+
+    let mut report = PurgeReportSchema { dry_run, archive, ..Default::default() };
+    let device_ids = select_ids(&mut tx, Device::Table, Device::DeviceId, Device::DeviceId, ids).await?;
+    if device_ids.is_empty() {
+        return Err(Error::RowNotFound);
+    }
+    report.device_ids = select_dependent_device(&mut tx, &device_ids).await?;
+    impact_device(&mut tx, &mut report).await?;
+    if !dry_run {
+        if archive {
+            archive_device(&mut tx, &report.device_ids).await?;
+        }
+        delete_device(&mut tx, &report.device_ids).await?;
+        tx.commit().await?;
+    }
+
+    Ok(report)
+}
+
+pub(crate) async fn purge_model(pool: &Pool<Postgres>,
+    ids: &[Uuid],
+    dry_run: bool,
+    archive: bool
+) -> Result<PurgeReportSchema, Error>
+{
+    let mut tx = pool.begin().await?;
+
+    let mut report = PurgeReportSchema { dry_run, archive, ..Default::default() };
+    let model_ids = select_ids(&mut tx, Model::Table, Model::ModelId, Model::ModelId, ids).await?;
+    if model_ids.is_empty() {
+        return Err(Error::RowNotFound);
+    }
+    push_count(&mut tx, &mut report, Data::Table, Data::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, DataBuffer::Table, DataBuffer::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, SliceData::Table, SliceData::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, SetMap::Table, SetMap::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, SetTemplateMap::Table, SetTemplateMap::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, GroupModelMap::Table, GroupModelMap::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, DeviceTypeModel::Table, DeviceTypeModel::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, ModelConfig::Table, ModelConfig::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, ModelConfigHistory::Table, ModelConfigHistory::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, ModelTag::Table, ModelTag::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, ModelLabel::Table, ModelLabel::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, ModelDerived::Table, ModelDerived::ModelId, &model_ids).await?;
+    // source rows of other derived models which use the purged model are also removed
+    push_count(&mut tx, &mut report, ModelDerivedSource::Table, ModelDerivedSource::SourceModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, Alarm::Table, Alarm::ModelId, &model_ids).await?;
+    push_count(&mut tx, &mut report, Model::Table, Model::ModelId, &model_ids).await?;
+
+    report.set_ids = select_ids(&mut tx, SetMap::Table, SetMap::SetId, SetMap::ModelId, &model_ids).await?;
+    report.set_template_ids = select_ids(&mut tx, SetTemplateMap::Table, SetTemplateMap::TemplateId, SetTemplateMap::ModelId, &model_ids).await?;
+    report.group_ids = select_ids(&mut tx, GroupModelMap::Table, GroupModelMap::GroupId, GroupModelMap::ModelId, &model_ids).await?;
+    report.slice_ids = select_slice_ids(&mut tx, SliceData::ModelId, &model_ids).await?;
+    // other derived models lose the purged model from their sources so their output changes
+    report.derived_ids = select_ids(&mut tx, ModelDerivedSource::Table, ModelDerivedSource::ModelId, ModelDerivedSource::SourceModelId, &model_ids).await?;
+    report.derived_ids.retain(|id| !model_ids.contains(id));
+
+    if !dry_run {
+        if archive {
+            archive_data(&mut tx, Data::ModelId, &model_ids).await?;
+            archive_buffer(&mut tx, DataBuffer::ModelId, &model_ids).await?;
+            archive_slice(&mut tx, SliceData::ModelId, &model_ids).await?;
+        }
+        // data tables do not cascade so they are deleted before the model
+        delete_rows(&mut tx, Data::Table, Data::ModelId, &model_ids).await?;
+        delete_rows(&mut tx, DataBuffer::Table, DataBuffer::ModelId, &model_ids).await?;
+        delete_rows(&mut tx, SliceData::Table, SliceData::ModelId, &model_ids).await?;
+        delete_rows(&mut tx, Model::Table, Model::ModelId, &model_ids).await?;
+        tx.commit().await?;
+    }
+
+    Ok(report)
+}
+
+pub(crate) async fn purge_type(pool: &Pool<Postgres>,
+    ids: &[Uuid],
+    dry_run: bool,
+    archive: bool
+) -> Result<PurgeReportSchema, Error>
+{
+    let mut tx = pool.begin().await?;
+
+    let mut report = PurgeReportSchema { dry_run, archive, ..Default::default() };
+    let type_ids = select_ids(&mut tx, DeviceType::Table, DeviceType::TypeId, DeviceType::TypeId, ids).await?;
+    if type_ids.is_empty() {
+        return Err(Error::RowNotFound);
+    }
+    // every device of the type and devices attached to them are purged before the type itself
+    let device_ids = select_ids(&mut tx, Device::Table, Device::DeviceId, Device::TypeId, &type_ids).await?;
+    report.device_ids = select_dependent_device(&mut tx, &device_ids).await?;
+    if !report.device_ids.is_empty() {
+        impact_device(&mut tx, &mut report).await?;
+    }
+    push_count(&mut tx, &mut report, DeviceTypeModel::Table, DeviceTypeModel::TypeId, &type_ids).await?;
+    push_count(&mut tx, &mut report, DeviceTypeConfig::Table, DeviceTypeConfig::TypeId, &type_ids).await?;
+    push_count(&mut tx, &mut report, SetTemplateMap::Table, SetTemplateMap::TypeId, &type_ids).await?;
+    push_count(&mut tx, &mut report, DeviceType::Table, DeviceType::TypeId, &type_ids).await?;
+    report.set_template_ids = select_ids(&mut tx, SetTemplateMap::Table, SetTemplateMap::TemplateId, SetTemplateMap::TypeId, &type_ids).await?;
+
+    if !dry_run {
+        if !report.device_ids.is_empty() {
+            if archive {
+                archive_device(&mut tx, &report.device_ids).await?;
+            }
+            delete_device(&mut tx, &report.device_ids).await?;
+        }
+        delete_rows(&mut tx, DeviceType::Table, DeviceType::TypeId, &type_ids).await?;
+        tx.commit().await?;
+    }
+
+    Ok(report)
+}
+
+async fn impact_device(conn: &mut PgConnection,
+    report: &mut PurgeReportSchema
+) -> Result<(), Error>
+{
+    let ids = report.device_ids.clone();
+    push_count(conn, report, Data::Table, Data::DeviceId, &ids).await?;
+    push_count(conn, report, DataBuffer::Table, DataBuffer::DeviceId, &ids).await?;
+    push_count(conn, report, SliceData::Table, SliceData::DeviceId, &ids).await?;
+    push_count(conn, report, SetMap::Table, SetMap::DeviceId, &ids).await?;
+    push_count(conn, report, GroupDeviceMap::Table, GroupDeviceMap::DeviceId, &ids).await?;
+    push_count(conn, report, DeviceConfig::Table, DeviceConfig::DeviceId, &ids).await?;
+    push_count(conn, report, DeviceConfigHistory::Table, DeviceConfigHistory::DeviceId, &ids).await?;
+    push_count(conn, report, Alarm::Table, Alarm::DeviceId, &ids).await?;
+    push_count(conn, report, DeviceStatus::Table, DeviceStatus::DeviceId, &ids).await?;
+    push_count(conn, report, DeviceLabel::Table, DeviceLabel::DeviceId, &ids).await?;
+    push_count(conn, report, DeviceLocation::Table, DeviceLocation::DeviceId, &ids).await?;
+    push_count(conn, report, Device::Table, Device::DeviceId, &ids).await?;
+
+    report.set_ids = select_ids(conn, SetMap::Table, SetMap::SetId, SetMap::DeviceId, &ids).await?;
+    report.group_ids = select_ids(conn, GroupDeviceMap::Table, GroupDeviceMap::GroupId, GroupDeviceMap::DeviceId, &ids).await?;
+    report.slice_ids = select_slice_ids(conn, SliceData::DeviceId, &ids).await?;
+
+    Ok(())
+}
+
+// devices which use purged devices as gateway or parent, directly or through other devices,
+// have no foreign key to them so they are purged together
+async fn select_dependent_device(conn: &mut PgConnection,
+    ids: &[Uuid]
+) -> Result<Vec<Uuid>, Error>
+{
+    let mut device_ids = ids.to_vec();
+    let mut parent_ids = ids.to_vec();
+    while !parent_ids.is_empty() {
+        let (sql, values) = Query::select()
+            .column(Device::DeviceId)
+            .from(Device::Table)
+            .cond_where(Condition::any()
+                .add(Expr::col(Device::GatewayId).is_in(parent_ids.clone()))
+                .add(Expr::col(Device::ParentId).is_in(parent_ids))
+            )
+            .and_where(Expr::col(Device::DeviceId).is_not_in(device_ids.clone()))
+            .build_sqlx(PostgresQueryBuilder);
+        parent_ids = sqlx::query_with(&sql, values)
+            .map(|row: PgRow| row.get(0))
+            .fetch_all(&mut *conn)
+            .await?;
+        device_ids.extend_from_slice(&parent_ids);
+    }
+    device_ids.sort();
+
+    Ok(device_ids)
+}
+
+// copy data, buffer and slice rows of devices to the archive tables before they are deleted
+async fn archive_device(conn: &mut PgConnection,
+    ids: &[Uuid]
+) -> Result<(), Error>
+{
+    archive_data(conn, Data::DeviceId, ids).await?;
+    archive_buffer(conn, DataBuffer::DeviceId, ids).await?;
+    archive_slice(conn, SliceData::DeviceId, ids).await?;
+
+    Ok(())
+}
+
+// copy data rows to the archive table before they are deleted
+async fn archive_data(conn: &mut PgConnection,
+    column: Data,
+    ids: &[Uuid]
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::insert()
+        .into_table(DataArchive::Table)
+        .columns([DataArchive::DeviceId, DataArchive::ModelId, DataArchive::Timestamp, DataArchive::Tag, DataArchive::Data])
+        .select_from(Query::select()
+            .columns([Data::DeviceId, Data::ModelId, Data::Timestamp, Data::Tag, Data::Data])
+            .from(Data::Table)
+            .and_where(Expr::col(column).is_in(ids.to_vec()))
+            .to_owned()
+        )
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn archive_buffer(conn: &mut PgConnection,
+    column: DataBuffer,
+    ids: &[Uuid]
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::insert()
+        .into_table(DataBufferArchive::Table)
+        .columns([DataBufferArchive::DeviceId, DataBufferArchive::ModelId, DataBufferArchive::Timestamp, DataBufferArchive::Tag, DataBufferArchive::Data])
+        .select_from(Query::select()
+            .columns([DataBuffer::DeviceId, DataBuffer::ModelId, DataBuffer::Timestamp, DataBuffer::Tag, DataBuffer::Data])
+            .from(DataBuffer::Table)
+            .and_where(Expr::col(column).is_in(ids.to_vec()))
+            .to_owned()
+        )
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn archive_slice(conn: &mut PgConnection,
+    column: SliceData,
+    ids: &[Uuid]
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::insert()
+        .into_table(SliceDataArchive::Table)
+        .columns([SliceDataArchive::DeviceId, SliceDataArchive::ModelId, SliceDataArchive::TimestampBegin, SliceDataArchive::TimestampEnd, SliceDataArchive::Name, SliceDataArchive::Description])
+        .select_from(Query::select()
+            .columns([SliceData::DeviceId, SliceData::ModelId, SliceData::TimestampBegin, SliceData::TimestampEnd, SliceData::Name, SliceData::Description])
+            .from(SliceData::Table)
+            .and_where(Expr::col(column).is_in(ids.to_vec()))
+            .to_owned()
+        )
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn delete_device(conn: &mut PgConnection,
+    ids: &[Uuid]
+) -> Result<(), Error>
+{
+    // data tables do not cascade so they are deleted before the device
+    delete_rows(conn, Data::Table, Data::DeviceId, ids).await?;
+    delete_rows(conn, DataBuffer::Table, DataBuffer::DeviceId, ids).await?;
+    delete_rows(conn, SliceData::Table, SliceData::DeviceId, ids).await?;
+    delete_rows(conn, Device::Table, Device::DeviceId, ids).await?;
+
+    Ok(())
+}
+
+async fn push_count<T: Iden + 'static>(conn: &mut PgConnection,
+    report: &mut PurgeReportSchema,
+    table: T,
+    column: T,
+    ids: &[Uuid]
+) -> Result<(), Error>
+{
+    let name = table.to_string();
+    let (sql, values) = Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from(table)
+        .and_where(Expr::col(column).is_in(ids.to_vec()))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let count: i64 = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_one(&mut *conn)
+        .await?;
+    report.rows.push((name, count));
+
+    Ok(())
+}
+
+async fn select_ids<T: Iden + 'static>(conn: &mut PgConnection,
+    table: T,
+    column: T,
+    filter: T,
+    ids: &[Uuid]
+) -> Result<Vec<Uuid>, Error>
+{
+    let (sql, values) = Query::select()
+        .distinct()
+        .column(column)
+        .from(table)
+        .and_where(Expr::col(filter).is_in(ids.to_vec()))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let mut rows: Vec<Uuid> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(&mut *conn)
+        .await?;
+    rows.sort();
+
+    Ok(rows)
+}
+
+async fn select_slice_ids(conn: &mut PgConnection,
+    filter: SliceData,
+    ids: &[Uuid]
+) -> Result<Vec<i32>, Error>
+{
+    let (sql, values) = Query::select()
+        .column(SliceData::Id)
+        .from(SliceData::Table)
+        .and_where(Expr::col(filter).is_in(ids.to_vec()))
+        .order_by(SliceData::Id, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(&mut *conn)
+        .await
+}
+
+async fn delete_rows<T: Iden + 'static>(conn: &mut PgConnection,
+    table: T,
+    column: T,
+    ids: &[Uuid]
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::delete()
+        .from_table(table)
+        .and_where(Expr::col(column).is_in(ids.to_vec()))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
@@ -1,10 +1,12 @@
 use sqlx::{Pool, Row, Error};
-use sqlx::postgres::{Postgres, PgRow};
-use sea_query::{PostgresQueryBuilder, Query, Expr, Order};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, Condition};
 use sea_query_binder::SqlxBinder;
 use uuid::Uuid;
 
-use crate::schema::set::{Set, SetMap, SetTemplate, SetTemplateMap, SetSchema, SetMember, SetTemplateSchema, SetTemplateMember};
+use crate::schema::set::{Set, SetMap, SetTemplate, SetTemplateMap, SetSchema, SetMember, SetTemplateSchema, SetTemplateMember, SetTemplateBinding};
+use crate::schema::device::Device;
+use crate::operation::{SET_TEMPLATE_INDEX_INVALID, SET_TEMPLATE_TYPE_UNMATCH, SET_TEMPLATE_UNBOUND};
 
 pub(crate) async fn select_set(pool: &Pool<Postgres>, 
     id: Option<Uuid>,
@@ -122,6 +124,130 @@ pub(crate) async fn insert_set(pool: &Pool<Postgres>,
     Ok(id)
 }
 
+pub(crate) async fn select_set_template_binding(pool: &Pool<Postgres>,
+    template_id: Uuid,
+    bindings: &[SetTemplateBinding],
+    gateway_id: Option<Uuid>
+) -> Result<Vec<SetMember>, Error>
+{
+    let template = match select_set_template(pool, Some(template_id), None, None).await?.into_iter().next() {
+        Some(value) => value,
+        None => return Err(Error::RowNotFound)
+    };
+    let mut slots: Vec<Option<Uuid>> = vec![None; template.members.len()];
+    for binding in bindings {
+        match slots.get_mut(binding.template_index) {
+            Some(slot) => *slot = Some(binding.device_id),
+            None => return Err(Error::InvalidArgument(SET_TEMPLATE_INDEX_INVALID.to_string()))
+        }
+    }
+
+    // type of bound devices and devices of the gateway used for auto binding
+    let device_ids: Vec<Uuid> = slots.iter().flatten().copied().collect();
+    let mut condition = Condition::any().add(Expr::col(Device::DeviceId).is_in(device_ids));
+    if let Some(gateway_id) = gateway_id {
+        condition = condition.add(Expr::col(Device::GatewayId).eq(gateway_id));
+    }
+    let (sql, values) = Query::select()
+        .columns([Device::DeviceId, Device::GatewayId, Device::TypeId])
+        .from(Device::Table)
+        .cond_where(condition)
+        .build_sqlx(PostgresQueryBuilder);
+    let devices: Vec<(Uuid, Uuid, Uuid)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get(0), row.get(1), row.get(2)))
+        .fetch_all(pool)
+        .await?;
+
+    let mut members: Vec<SetMember> = Vec::new();
+    for (slot, template_member) in slots.iter().zip(template.members) {
+        let device_id = match slot {
+            Some(device_id) => {
+                match devices.iter().find(|(id, _, _)| id == device_id) {
+                    Some((_, _, type_id)) if *type_id == template_member.type_id => *device_id,
+                    Some(_) => return Err(Error::InvalidArgument(SET_TEMPLATE_TYPE_UNMATCH.to_string())),
+                    None => return Err(Error::RowNotFound)
+                }
+            },
+            None => {
+                let matched: Vec<Uuid> = devices.iter()
+                    .filter(|(_, gateway, type_id)| Some(*gateway) == gateway_id && *type_id == template_member.type_id)
+                    .map(|(id, _, _)| *id)
+                    .collect();
+                match matched.as_slice() {
+                    [device_id] => *device_id,
+                    _ => return Err(Error::InvalidArgument(SET_TEMPLATE_UNBOUND.to_string()))
+                }
+            }
+        };
+        members.push(SetMember { device_id, model_id: template_member.model_id, data_index: template_member.data_index });
+    }
+    Ok(members)
+}
+
+pub(crate) async fn insert_set_from_template(pool: &Pool<Postgres>,
+    id: Uuid,
+    template_id: Uuid,
+    name: &str,
+    description: Option<&str>,
+    members: &[SetMember]
+) -> Result<Uuid, Error>
+{
+    let mut tx = pool.begin().await?;
+
+    let (sql, values) = Query::insert()
+        .into_table(Set::Table)
+        .columns([
+            Set::SetId,
+            Set::TemplateId,
+            Set::Name,
+            Set::Description
+        ])
+        .values([
+            id.into(),
+            template_id.into(),
+            name.into(),
+            description.unwrap_or_default().into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+    sqlx::query_with(&sql, values)
+        .execute(&mut *tx)
+        .await?;
+
+    let number = members.iter().fold(0, |acc, e| acc + e.data_index.len());
+    let mut position = 0;
+    for member in members {
+        let (sql, values) = Query::insert()
+            .into_table(SetMap::Table)
+            .columns([
+                SetMap::SetId,
+                SetMap::DeviceId,
+                SetMap::ModelId,
+                SetMap::DataIndex,
+                SetMap::SetPosition,
+                SetMap::SetNumber
+            ])
+            .values([
+                id.into(),
+                member.device_id.into(),
+                member.model_id.into(),
+                member.data_index.clone().into(),
+                (position as i16).into(),
+                (number as i16).into()
+            ])
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?;
+        position += member.data_index.len();
+    }
+
+    tx.commit().await?;
+
+    Ok(id)
+}
+
 pub(crate) async fn update_set(pool: &Pool<Postgres>,
     id: Uuid,
     template_id: Option<Uuid>,
@@ -341,6 +467,16 @@ pub(crate) async fn select_set_template(pool: &Pool<Postgres>,
     ids: Option<&[Uuid]>,
     name: Option<&str>
 ) -> Result<Vec<SetTemplateSchema>, Error>
+{
+    let mut conn = pool.acquire().await?;
+    read_set_template(&mut conn, id, ids, name).await
+}
+
+pub(crate) async fn read_set_template(conn: &mut PgConnection,
+    id: Option<Uuid>,
+    ids: Option<&[Uuid]>,
+    name: Option<&str>
+) -> Result<Vec<SetTemplateSchema>, Error>
 {
     let mut stmt = Query::select()
         .columns([
@@ -409,7 +545,7 @@ pub(crate) async fn select_set_template(pool: &Pool<Postgres>,
             // update template_schema_vec with updated template_schema
             template_schema_vec.push(template_schema.clone());
         })
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await?;
 
     Ok(template_schema_vec)
@@ -0,0 +1,160 @@
+use sqlx::{Pool, Row, Error};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
+use sqlx::types::chrono::{DateTime, Utc};
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, Func, OnConflict, Alias};
+use sea_query_binder::SqlxBinder;
+use uuid::Uuid;
+
+use crate::schema::value::{DataValue, DataType};
+use crate::schema::device::{Device, DeviceConfig, DeviceLifecycle};
+use crate::schema::status::{DeviceStatus, DeviceStatusSchema};
+
+pub(crate) enum StatusKind {
+    Data,
+    Buffer,
+    Log
+}
+
+pub(crate) async fn select_device_status(pool: &Pool<Postgres>,
+    device_id: Option<Uuid>
+) -> Result<Vec<DeviceStatusSchema>, Error>
+{
+    let mut stmt = Query::select()
+        .column((Device::Table, Device::DeviceId))
+        .columns([
+            (DeviceStatus::Table, DeviceStatus::LastData),
+            (DeviceStatus::Table, DeviceStatus::LastBuffer),
+            (DeviceStatus::Table, DeviceStatus::LastLog)
+        ])
+        .from(Device::Table)
+        .left_join(DeviceStatus::Table,
+            Expr::col((Device::Table, Device::DeviceId))
+            .equals((DeviceStatus::Table, DeviceStatus::DeviceId))
+        )
+        .to_owned();
+
+    if let Some(id) = device_id {
+        stmt = stmt.and_where(Expr::col((Device::Table, Device::DeviceId)).eq(id)).to_owned();
+    }
+    else {
+        // decommissioned devices are expected to be silent
+        let decommissioned = i16::from(DeviceLifecycle::Decommissioned);
+        stmt = stmt.and_where(Expr::col((Device::Table, Device::Lifecycle)).ne(decommissioned)).to_owned();
+    }
+    let (sql, values) = stmt
+        .order_by((Device::Table, Device::DeviceId), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            DeviceStatusSchema {
+                device_id: row.get(0),
+                last_data: row.get(1),
+                last_buffer: row.get(2),
+                last_log: row.get(3)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+pub(crate) async fn select_device_stale(pool: &Pool<Postgres>,
+    since: DateTime<Utc>,
+    now: DateTime<Utc>
+) -> Result<Vec<DeviceStatusSchema>, Error>
+{
+    let status_vec = select_device_status(pool, None).await?;
+
+    let (sql, values) = Query::select()
+        .columns([
+            DeviceConfig::DeviceId,
+            DeviceConfig::Value,
+            DeviceConfig::Type
+        ])
+        .from(DeviceConfig::Table)
+        .and_where(Expr::col(DeviceConfig::Name).eq(DeviceStatusSchema::PERIOD_NAME))
+        .and_where(Expr::col(DeviceConfig::Category).eq(DeviceStatusSchema::PERIOD_CATEGORY))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let periods: Vec<(Uuid, Option<f64>)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes: Vec<u8> = row.get(1);
+            let type_ = DataType::from(row.get::<i16,_>(2));
+            (row.get(0), DataValue::from_bytes(&bytes, type_).to_f64())
+        })
+        .fetch_all(pool)
+        .await?;
+
+    let stale_vec = status_vec.into_iter()
+        .filter(|status| {
+            let period = periods.iter()
+                .find(|(device_id, _)| *device_id == status.device_id)
+                .and_then(|(_, period)| *period);
+            status.is_stale(since, now, period)
+        })
+        .collect();
+
+    Ok(stale_vec)
+}
+
+pub(crate) async fn update_device_status(pool: &Pool<Postgres>,
+    kind: StatusKind,
+    device_ids: &[Uuid],
+    timestamps: &[DateTime<Utc>]
+) -> Result<(), Error>
+{
+    let mut conn = pool.acquire().await?;
+    write_device_status(&mut conn, kind, device_ids, timestamps).await
+}
+
+pub(crate) async fn write_device_status(conn: &mut PgConnection,
+    kind: StatusKind,
+    device_ids: &[Uuid],
+    timestamps: &[DateTime<Utc>]
+) -> Result<(), Error>
+{
+    // only latest timestamp of every device is written
+    let mut latest: Vec<(Uuid, DateTime<Utc>)> = Vec::new();
+    for (&device_id, &timestamp) in device_ids.iter().zip(timestamps) {
+        match latest.iter_mut().find(|(id, _)| *id == device_id) {
+            Some(item) => item.1 = item.1.max(timestamp),
+            None => latest.push((device_id, timestamp))
+        }
+    }
+    let column = || match kind {
+        StatusKind::Data => DeviceStatus::LastData,
+        StatusKind::Buffer => DeviceStatus::LastBuffer,
+        StatusKind::Log => DeviceStatus::LastLog
+    };
+
+    for (device_id, timestamp) in latest {
+        // select from device table so status of an unregistered device (e.g. from log) is skipped
+        let select = Query::select()
+            .column(Device::DeviceId)
+            .expr(Expr::val(timestamp))
+            .from(Device::Table)
+            .and_where(Expr::col(Device::DeviceId).eq(device_id))
+            .to_owned();
+        let (sql, values) = Query::insert()
+            .into_table(DeviceStatus::Table)
+            .columns([DeviceStatus::DeviceId, column()])
+            .select_from(select)
+            .unwrap_or(&mut sea_query::InsertStatement::default())
+            .on_conflict(OnConflict::column(DeviceStatus::DeviceId)
+                .value(column(), Func::greatest([
+                    Expr::col((DeviceStatus::Table, column())).into(),
+                    Expr::col((Alias::new("excluded"), column())).into()
+                ]))
+                .to_owned()
+            )
+            .build_sqlx(PostgresQueryBuilder);
+
+        sqlx::query_with(&sql, values)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
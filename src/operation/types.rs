@@ -1,16 +1,31 @@
 use sqlx::{Pool, Row, Error};
-use sqlx::postgres::{Postgres, PgRow};
-use sea_query::{PostgresQueryBuilder, Query, Expr, Order};
+use sqlx::postgres::{Postgres, PgRow, PgConnection};
+use sqlx::types::chrono::Utc;
+use sea_query::{PostgresQueryBuilder, Query, Expr, Order, Func, OnConflict};
 use sea_query_binder::SqlxBinder;
 use uuid::Uuid;
 
-use crate::schema::device::{DeviceType, DeviceTypeModel, TypeSchema};
+use crate::schema::value::{DataValue, DataType};
+use crate::schema::device::{Device, DeviceType, DeviceTypeModel, DeviceTypeConfig, TypeSchema, TypeConfigSchema, CompatibilityMode, DeviceModelIncompatible, DeviceTypeConfigKey, TypeConfigKeySchema};
+use crate::operation::log::write_log;
+use crate::operation::{DEVICE_MODEL_INCOMPATIBLE, CONFIG_KEY_DEFAULT_INVALID};
+use crate::utility::tag as Tag;
 
 pub(crate) async fn select_device_type(pool: &Pool<Postgres>, 
     id: Option<Uuid>,
     ids: Option<&[Uuid]>,
     name: Option<&str>
 ) -> Result<Vec<TypeSchema>, Error>
+{
+    let mut conn = pool.acquire().await?;
+    read_device_type(&mut conn, id, ids, name).await
+}
+
+pub(crate) async fn read_device_type(conn: &mut PgConnection,
+    id: Option<Uuid>,
+    ids: Option<&[Uuid]>,
+    name: Option<&str>
+) -> Result<Vec<TypeSchema>, Error>
 {
     let mut stmt = Query::select()
         .columns([
@@ -74,7 +89,7 @@ pub(crate) async fn select_device_type(pool: &Pool<Postgres>,
             // update type_schema_vec with updated type_schema
             type_schema_vec.push(type_schema.clone());
         })
-        .fetch_all(pool)
+        .fetch_all(&mut *conn)
         .await?;
 
     Ok(type_schema_vec)
@@ -194,3 +209,334 @@ pub(crate) async fn delete_device_type_model(pool: &Pool<Postgres>,
 
     Ok(())
 }
+
+pub(crate) async fn select_device_type_config(pool: &Pool<Postgres>,
+    id: Option<i32>,
+    type_id: Option<Uuid>
+) -> Result<Vec<TypeConfigSchema>, Error>
+{
+    let mut stmt = Query::select()
+        .columns([
+            DeviceTypeConfig::Id,
+            DeviceTypeConfig::TypeId,
+            DeviceTypeConfig::Name,
+            DeviceTypeConfig::Value,
+            DeviceTypeConfig::Type,
+            DeviceTypeConfig::Category
+        ])
+        .from(DeviceTypeConfig::Table)
+        .to_owned();
+
+    if let Some(id) = id {
+        stmt = stmt.and_where(Expr::col(DeviceTypeConfig::Id).eq(id)).to_owned();
+    }
+    else if let Some(type_id) = type_id {
+        stmt = stmt.and_where(Expr::col(DeviceTypeConfig::TypeId).eq(type_id)).to_owned();
+    }
+    let (sql, values) = stmt
+        .order_by(DeviceTypeConfig::TypeId, Order::Asc)
+        .order_by(DeviceTypeConfig::Id, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let bytes = row.get(3);
+            let type_ = DataType::from(row.get::<i16,_>(4));
+            TypeConfigSchema {
+                id: row.get(0),
+                type_id: row.get(1),
+                name: row.get(2),
+                value: DataValue::from_bytes(bytes, type_),
+                category: row.get(5)
+            }
+        })
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows)
+}
+
+pub(crate) async fn insert_device_type_config(pool: &Pool<Postgres>,
+    type_id: Uuid,
+    name: &str,
+    value: DataValue,
+    category: &str
+) -> Result<i32, Error>
+{
+    let config_value = value.to_bytes();
+    let config_type = i16::from(value.get_type());
+    let (sql, values) = Query::insert()
+        .into_table(DeviceTypeConfig::Table)
+        .columns([
+            DeviceTypeConfig::TypeId,
+            DeviceTypeConfig::Name,
+            DeviceTypeConfig::Value,
+            DeviceTypeConfig::Type,
+            DeviceTypeConfig::Category
+        ])
+        .values([
+            type_id.into(),
+            name.into(),
+            config_value.into(),
+            config_type.into(),
+            category.into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?;
+
+    let sql = Query::select()
+        .expr(Func::max(Expr::col(DeviceTypeConfig::Id)))
+        .from(DeviceTypeConfig::Table)
+        .to_string(PostgresQueryBuilder);
+    let id: i32 = sqlx::query(&sql)
+        .map(|row: PgRow| row.get(0))
+        .fetch_one(pool)
+        .await?;
+
+    Ok(id)
+}
+
+pub(crate) async fn update_device_type_config(pool: &Pool<Postgres>,
+    id: i32,
+    name: Option<&str>,
+    value: Option<DataValue>,
+    category: Option<&str>
+) -> Result<(), Error>
+{
+    let mut stmt = Query::update()
+        .table(DeviceTypeConfig::Table)
+        .to_owned();
+
+    if let Some(value) = name {
+        stmt = stmt.value(DeviceTypeConfig::Name, value).to_owned();
+    }
+    if let Some(value) = value {
+        let bytes = value.to_bytes();
+        let type_ = i16::from(value.get_type());
+        stmt = stmt
+            .value(DeviceTypeConfig::Value, bytes)
+            .value(DeviceTypeConfig::Type, type_).to_owned();
+    }
+    if let Some(value) = category {
+        stmt = stmt.value(DeviceTypeConfig::Category, value).to_owned();
+    }
+
+    let (sql, values) = stmt
+        .and_where(Expr::col(DeviceTypeConfig::Id).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn delete_device_type_config(pool: &Pool<Postgres>,
+    id: i32
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::delete()
+        .from_table(DeviceTypeConfig::Table)
+        .and_where(Expr::col(DeviceTypeConfig::Id).eq(id))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn select_device_type_config_key(pool: &Pool<Postgres>,
+    type_id: Uuid
+) -> Result<Vec<TypeConfigKeySchema>, Error>
+{
+    let mut conn = pool.acquire().await?;
+    select_type_config_key(&mut conn, &[type_id]).await
+}
+
+pub(crate) async fn select_type_config_key(conn: &mut PgConnection,
+    type_ids: &[Uuid]
+) -> Result<Vec<TypeConfigKeySchema>, Error>
+{
+    let (sql, values) = Query::select()
+        .columns([
+            DeviceTypeConfigKey::TypeId,
+            DeviceTypeConfigKey::Name,
+            DeviceTypeConfigKey::Category,
+            DeviceTypeConfigKey::Type,
+            DeviceTypeConfigKey::Required,
+            DeviceTypeConfigKey::DefaultValue,
+            DeviceTypeConfigKey::Min,
+            DeviceTypeConfigKey::Max
+        ])
+        .from(DeviceTypeConfigKey::Table)
+        .and_where(Expr::col(DeviceTypeConfigKey::TypeId).is_in(type_ids.to_vec()))
+        .order_by(DeviceTypeConfigKey::TypeId, Order::Asc)
+        .order_by(DeviceTypeConfigKey::Category, Order::Asc)
+        .order_by(DeviceTypeConfigKey::Name, Order::Asc)
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| {
+            let type_ = DataType::from(row.get::<i16,_>(3));
+            let default: Option<Vec<u8>> = row.get(5);
+            TypeConfigKeySchema {
+                type_id: row.get(0),
+                name: row.get(1),
+                category: row.get(2),
+                type_: type_.clone(),
+                required: row.get(4),
+                default: default.map(|bytes| DataValue::from_bytes(&bytes, type_)),
+                min: row.get(6),
+                max: row.get(7)
+            }
+        })
+        .fetch_all(conn)
+        .await?;
+
+    Ok(rows)
+}
+
+pub(crate) async fn insert_device_type_config_key(pool: &Pool<Postgres>,
+    key: &TypeConfigKeySchema
+) -> Result<(), Error>
+{
+    if key.default.as_ref().and_then(|value| key.check(value)).is_some() {
+        return Err(Error::InvalidArgument(CONFIG_KEY_DEFAULT_INVALID.to_string()));
+    }
+    let (sql, values) = Query::insert()
+        .into_table(DeviceTypeConfigKey::Table)
+        .columns([
+            DeviceTypeConfigKey::TypeId,
+            DeviceTypeConfigKey::Name,
+            DeviceTypeConfigKey::Category,
+            DeviceTypeConfigKey::Type,
+            DeviceTypeConfigKey::Required,
+            DeviceTypeConfigKey::DefaultValue,
+            DeviceTypeConfigKey::Min,
+            DeviceTypeConfigKey::Max
+        ])
+        .values([
+            key.type_id.into(),
+            key.name.clone().into(),
+            key.category.clone().into(),
+            i16::from(key.type_.clone()).into(),
+            key.required.into(),
+            key.default.as_ref().map(|value| value.to_bytes()).into(),
+            key.min.into(),
+            key.max.into()
+        ])
+        .unwrap_or(&mut sea_query::InsertStatement::default())
+        .on_conflict(OnConflict::columns([DeviceTypeConfigKey::TypeId, DeviceTypeConfigKey::Category, DeviceTypeConfigKey::Name])
+            .update_columns([
+                DeviceTypeConfigKey::Type,
+                DeviceTypeConfigKey::Required,
+                DeviceTypeConfigKey::DefaultValue,
+                DeviceTypeConfigKey::Min,
+                DeviceTypeConfigKey::Max
+            ])
+            .to_owned()
+        )
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn delete_device_type_config_key(pool: &Pool<Postgres>,
+    type_id: Uuid,
+    name: &str,
+    category: &str
+) -> Result<(), Error>
+{
+    let (sql, values) = Query::delete()
+        .from_table(DeviceTypeConfigKey::Table)
+        .and_where(Expr::col(DeviceTypeConfigKey::TypeId).eq(type_id))
+        .and_where(Expr::col(DeviceTypeConfigKey::Name).eq(name))
+        .and_where(Expr::col(DeviceTypeConfigKey::Category).eq(category))
+        .build_sqlx(PostgresQueryBuilder);
+
+    sqlx::query_with(&sql, values)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn select_device_model_incompatible(pool: &Pool<Postgres>,
+    device_ids: &[Uuid],
+    model_ids: &[Uuid]
+) -> Result<Vec<(Uuid, Uuid)>, Error>
+{
+    let (sql, values) = Query::select()
+        .column((Device::Table, Device::DeviceId))
+        .column((DeviceTypeModel::Table, DeviceTypeModel::ModelId))
+        .from(Device::Table)
+        .left_join(DeviceTypeModel::Table,
+            Expr::col((Device::Table, Device::TypeId))
+            .equals((DeviceTypeModel::Table, DeviceTypeModel::TypeId))
+        )
+        .and_where(Expr::col((Device::Table, Device::DeviceId)).is_in(device_ids.to_vec()))
+        .build_sqlx(PostgresQueryBuilder);
+
+    let rows: Vec<(Uuid, Option<Uuid>)> = sqlx::query_with(&sql, values)
+        .map(|row: PgRow| (row.get(0), row.get(1)))
+        .fetch_all(pool)
+        .await?;
+
+    // non existing device is left to be rejected by the write itself
+    let mut pairs: Vec<(Uuid, Uuid)> = Vec::new();
+    for (&device_id, &model_id) in device_ids.iter().zip(model_ids) {
+        let exists = rows.iter().any(|(id, _)| *id == device_id);
+        let linked = rows.iter().any(|(id, model)| *id == device_id && *model == Some(model_id));
+        if exists && !linked && !pairs.contains(&(device_id, model_id)) {
+            pairs.push((device_id, model_id));
+        }
+    }
+    Ok(pairs)
+}
+
+// incompatible pairs are rejected in strict mode and returned in warn mode so they are logged after the write
+pub(crate) async fn check_device_model(pool: &Pool<Postgres>,
+    mode: CompatibilityMode,
+    device_ids: &[Uuid],
+    model_ids: &[Uuid]
+) -> Result<Vec<(Uuid, Uuid)>, Error>
+{
+    if mode == CompatibilityMode::Disabled {
+        return Ok(Vec::new());
+    }
+    let pairs = select_device_model_incompatible(pool, device_ids, model_ids).await?;
+    if let (CompatibilityMode::Strict, Some(&(device_id, model_id))) = (mode, pairs.first()) {
+        return Err(DeviceModelIncompatible::new(device_id, model_id, DEVICE_MODEL_INCOMPATIBLE).into());
+    }
+    Ok(pairs)
+}
+
+pub(crate) async fn log_device_model(pool: &Pool<Postgres>,
+    pairs: &[(Uuid, Uuid)]
+) -> Result<(), Error>
+{
+    let mut conn = pool.acquire().await?;
+    write_device_model_log(&mut conn, pairs).await
+}
+
+pub(crate) async fn write_device_model_log(conn: &mut PgConnection,
+    pairs: &[(Uuid, Uuid)]
+) -> Result<(), Error>
+{
+    for &(device_id, model_id) in pairs {
+        let message = DeviceModelIncompatible::new(device_id, model_id, DEVICE_MODEL_INCOMPATIBLE).to_string();
+        write_log(conn, Utc::now(), Some(device_id), Some(model_id), DataValue::String(message), Some(Tag::ERROR_COMPATIBILITY)).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use sqlx::types::chrono::{DateTime, Utc, TimeZone};
+use chrono::{Datelike, TimeDelta};
+use uuid::Uuid;
+use crate::schema::value::{DataType, DataValue};
+use crate::utility::tag as Tag;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregatePeriod {
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Annual
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum AggregateFunction {
+    #[default]
+    Sum,
+    Average,
+    Min,
+    Max
+}
+
+// data of a device group model aggregated into a group tagged series of the output device,
+// one function for every field of the model
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupAggregate {
+    pub group_device_id: Uuid,
+    pub model_id: Uuid,
+    pub output_device_id: Uuid,
+    pub period: AggregatePeriod,
+    pub functions: Vec<AggregateFunction>
+}
+
+impl AggregatePeriod {
+    pub fn tag(&self) -> i16
+    {
+        match self {
+            Self::Minutely => Tag::GROUP_MINUTELY,
+            Self::Hourly => Tag::GROUP_HOURLY,
+            Self::Daily => Tag::GROUP_DAILY,
+            Self::Weekly => Tag::GROUP_WEEKLY,
+            Self::Monthly => Tag::GROUP_MONTHLY,
+            Self::Annual => Tag::GROUP_ANNUAL
+        }
+    }
+
+    // start of the period containing the timestamp, weeks start on monday
+    pub fn truncate(&self, timestamp: DateTime<Utc>) -> DateTime<Utc>
+    {
+        let seconds = timestamp.timestamp();
+        let seconds = match self {
+            Self::Minutely => seconds - seconds.rem_euclid(60),
+            Self::Hourly => seconds - seconds.rem_euclid(3600),
+            Self::Daily => seconds - seconds.rem_euclid(86400),
+            Self::Weekly => {
+                // unix epoch is on thursday
+                let day = seconds.div_euclid(86400);
+                (day - (day + 3).rem_euclid(7)) * 86400
+            },
+            Self::Monthly | Self::Annual => {
+                let month = match self {
+                    Self::Monthly => timestamp.month(),
+                    _ => 1
+                };
+                return Utc.with_ymd_and_hms(timestamp.year(), month, 1, 0, 0, 0).single().unwrap_or(timestamp);
+            }
+        };
+        Utc.timestamp_opt(seconds, 0).single().unwrap_or(timestamp)
+    }
+
+    // start of the period following the period containing the timestamp
+    pub fn next(&self, timestamp: DateTime<Utc>) -> DateTime<Utc>
+    {
+        let start = self.truncate(timestamp);
+        let (year, month) = match self {
+            Self::Minutely => return start + TimeDelta::minutes(1),
+            Self::Hourly => return start + TimeDelta::hours(1),
+            Self::Daily => return start + TimeDelta::days(1),
+            Self::Weekly => return start + TimeDelta::weeks(1),
+            Self::Monthly if start.month() == 12 => (start.year() + 1, 1),
+            Self::Monthly => (start.year(), start.month() + 1),
+            Self::Annual => (start.year() + 1, 1)
+        };
+        Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single().unwrap_or(start)
+    }
+}
+
+impl AggregateFunction {
+    pub(crate) fn apply(&self, values: &[f64]) -> Option<f64>
+    {
+        if values.is_empty() {
+            return None;
+        }
+        match self {
+            Self::Sum => Some(values.iter().sum()),
+            Self::Average => Some(values.iter().sum::<f64>() / values.len() as f64),
+            Self::Min => values.iter().copied().reduce(f64::min),
+            Self::Max => values.iter().copied().reduce(f64::max)
+        }
+    }
+
+    // function used to reduce samples of a single device within a period,
+    // sum and average of a device are its mean value so that sum gives a fleet total
+    pub(crate) fn device_function(&self) -> Self
+    {
+        match self {
+            Self::Sum | Self::Average => Self::Average,
+            Self::Min => Self::Min,
+            Self::Max => Self::Max
+        }
+    }
+}
+
+// value written for fields without numeric values so stored bytes keep the model layout
+fn typed_default(type_: &DataType) -> DataValue
+{
+    match type_ {
+        DataType::CharT => DataValue::Char('\0'),
+        DataType::StringT => DataValue::String(String::new()),
+        DataType::BytesT => DataValue::Bytes(Vec::new()),
+        _ => DataValue::from_f64(0.0, type_.clone())
+    }
+}
+
+// combine data of several devices field by field, samples of every device are reduced first
+// then the devices are combined, non numeric fields and fields without values get a typed default
+pub(crate) fn aggregate_data(devices: &[Vec<Vec<DataValue>>], functions: &[AggregateFunction], types: &[DataType]) -> Vec<DataValue>
+{
+    types.iter().enumerate().map(|(index, type_)| {
+        let function = functions.get(index).copied().unwrap_or_default();
+        let values: Vec<f64> = devices.iter().filter_map(|rows| {
+            let samples: Vec<f64> = rows.iter().filter_map(|row| row.get(index).and_then(|value| value.to_f64())).collect();
+            function.device_function().apply(&samples)
+        }).collect();
+        match function.apply(&values) {
+            Some(value) => DataValue::from_f64(value, type_.clone()),
+            None => typed_default(type_)
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::value::ArrayDataValue;
+    use DataValue::{I32, F64};
+
+    #[test]
+    fn aggregate_period_truncate()
+    {
+        let timestamp = DateTime::parse_from_rfc3339("2025-05-15T10:42:17Z").unwrap().with_timezone(&Utc);
+        let truncate = |period: AggregatePeriod| period.truncate(timestamp).to_rfc3339();
+        assert_eq!(truncate(AggregatePeriod::Minutely), "2025-05-15T10:42:00+00:00");
+        assert_eq!(truncate(AggregatePeriod::Hourly), "2025-05-15T10:00:00+00:00");
+        assert_eq!(truncate(AggregatePeriod::Daily), "2025-05-15T00:00:00+00:00");
+        assert_eq!(truncate(AggregatePeriod::Weekly), "2025-05-12T00:00:00+00:00");
+        assert_eq!(truncate(AggregatePeriod::Monthly), "2025-05-01T00:00:00+00:00");
+        assert_eq!(truncate(AggregatePeriod::Annual), "2025-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn aggregate_period_next()
+    {
+        let timestamp = DateTime::parse_from_rfc3339("2025-12-31T23:59:17Z").unwrap().with_timezone(&Utc);
+        let next = |period: AggregatePeriod| period.next(timestamp).to_rfc3339();
+        assert_eq!(next(AggregatePeriod::Minutely), "2026-01-01T00:00:00+00:00");
+        assert_eq!(next(AggregatePeriod::Hourly), "2026-01-01T00:00:00+00:00");
+        assert_eq!(next(AggregatePeriod::Daily), "2026-01-01T00:00:00+00:00");
+        assert_eq!(next(AggregatePeriod::Weekly), "2026-01-05T00:00:00+00:00");
+        assert_eq!(next(AggregatePeriod::Monthly), "2026-01-01T00:00:00+00:00");
+        assert_eq!(next(AggregatePeriod::Annual), "2026-01-01T00:00:00+00:00");
+        let timestamp = DateTime::parse_from_rfc3339("2025-05-15T10:42:17Z").unwrap().with_timezone(&Utc);
+        assert_eq!(AggregatePeriod::Monthly.next(timestamp).to_rfc3339(), "2025-06-01T00:00:00+00:00");
+        assert_eq!(AggregatePeriod::Hourly.next(timestamp).to_rfc3339(), "2025-05-15T11:00:00+00:00");
+    }
+
+    #[test]
+    fn aggregate_data_fields()
+    {
+        let devices = vec![
+            vec![vec![I32(10), F64(1.5), DataValue::String("a".to_owned())]],
+            vec![vec![I32(4), F64(2.5), DataValue::String("b".to_owned())]],
+            vec![vec![I32(7)]]
+        ];
+        let types = [DataType::I32T, DataType::F64T, DataType::StringT];
+        let functions = [AggregateFunction::Sum, AggregateFunction::Average];
+        assert_eq!(aggregate_data(&devices, &functions, &types), [I32(21), F64(2.0), DataValue::String(String::new())]);
+        let functions = [AggregateFunction::Min, AggregateFunction::Max, AggregateFunction::Max];
+        assert_eq!(aggregate_data(&devices, &functions, &types), [I32(4), F64(2.5), DataValue::String(String::new())]);
+    }
+
+    #[test]
+    fn aggregate_data_device_samples()
+    {
+        // two samples of first device are averaged before summing the devices
+        let devices = vec![
+            vec![vec![F64(10.0)], vec![F64(20.0)]],
+            vec![vec![F64(5.0)]]
+        ];
+        let types = [DataType::F64T];
+        assert_eq!(aggregate_data(&devices, &[AggregateFunction::Sum], &types), [F64(20.0)]);
+        assert_eq!(aggregate_data(&devices, &[AggregateFunction::Average], &types), [F64(10.0)]);
+        assert_eq!(aggregate_data(&devices, &[AggregateFunction::Max], &types), [F64(20.0)]);
+        assert_eq!(aggregate_data(&devices, &[AggregateFunction::Min], &types), [F64(5.0)]);
+    }
+
+    #[test]
+    fn aggregate_data_layout()
+    {
+        // non numeric field between numeric fields must not shift the following fields
+        let devices = vec![vec![vec![F64(1.5), DataValue::String("a".to_owned()), F64(2.5)]]];
+        let types = [DataType::F64T, DataType::StringT, DataType::F64T];
+        let functions = [AggregateFunction::Sum; 3];
+        let data = aggregate_data(&devices, &functions, &types);
+        let bytes = ArrayDataValue::from_vec(&data).to_bytes();
+        let values = ArrayDataValue::from_bytes(&bytes, &types).to_vec();
+        assert_eq!(values.len(), 3);
+        assert_eq!((values[0].clone(), values[2].clone()), (F64(1.5), F64(2.5)));
+        // field without any value
+        let data = aggregate_data(&[vec![vec![F64(1.0)]]], &functions, &types);
+        let bytes = ArrayDataValue::from_vec(&data).to_bytes();
+        assert_eq!(ArrayDataValue::from_bytes(&bytes, &types).to_vec()[2], F64(0.0));
+    }
+
+}
@@ -0,0 +1,209 @@
+use sea_query::Iden;
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::schema::model::{ModelConfigSchema, ModelConfigHistorySchema};
+use crate::schema::device::{DeviceConfigSchema, EffectiveConfigHistory};
+
+#[derive(Iden)]
+pub(crate) enum Alarm {
+    Table,
+    DeviceId,
+    ModelId,
+    Index,
+    Kind,
+    Value,
+    Timestamp
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum AlarmKind {
+    #[default]
+    Upper,
+    Lower
+}
+
+impl From<i16> for AlarmKind {
+    fn from(value: i16) -> Self {
+        match value {
+            1 => Self::Lower,
+            _ => Self::Upper
+        }
+    }
+}
+
+impl From<AlarmKind> for i16 {
+    fn from(value: AlarmKind) -> Self {
+        match value {
+            AlarmKind::Upper => 0,
+            AlarmKind::Lower => 1
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct AlarmSchema {
+    pub device_id: Uuid,
+    pub model_id: Uuid,
+    pub index: u8,
+    pub kind: AlarmKind,
+    pub value: f64,
+    pub timestamp: DateTime<Utc>
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub(crate) struct Threshold {
+    pub(crate) upper: Option<f64>,
+    pub(crate) lower: Option<f64>,
+    pub(crate) deadband: f64
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum AlarmTransition {
+    Raise(AlarmKind),
+    Clear,
+    None
+}
+
+impl Threshold {
+    // model config category of thresholds, the config index is the data field index
+    // device configs with the same category and name suffixed by field index (e.g. upper_threshold_0) override model configs
+    pub(crate) const CATEGORY: &'static str = "THRESHOLD";
+    pub(crate) const UPPER: &'static str = "upper_threshold";
+    pub(crate) const LOWER: &'static str = "lower_threshold";
+    pub(crate) const DEADBAND: &'static str = "deadband";
+
+    pub(crate) fn from_configs(index: usize, model_configs: &[ModelConfigSchema], device_configs: &[DeviceConfigSchema]) -> Option<Self>
+    {
+        let param = |name: &str| -> Option<f64> {
+            let device_name = format!("{}_{}", name, index);
+            device_configs.iter()
+                .find(|cfg| cfg.category == Self::CATEGORY && cfg.name == device_name)
+                .and_then(|cfg| cfg.value.to_f64())
+                .or(model_configs.iter()
+                    .find(|cfg| cfg.category == Self::CATEGORY && cfg.index as usize == index && cfg.name == name)
+                    .and_then(|cfg| cfg.value.to_f64())
+                )
+        };
+        let upper = param(Self::UPPER);
+        let lower = param(Self::LOWER);
+        if upper.is_none() && lower.is_none() {
+            return None;
+        }
+        let deadband = param(Self::DEADBAND).unwrap_or(0.0).abs();
+        Some(Self { upper, lower, deadband })
+    }
+
+    pub(crate) fn evaluate(&self, value: f64, active: Option<AlarmKind>) -> AlarmTransition
+    {
+        // an active alarm is kept until the value return inside threshold minus deadband
+        match active {
+            Some(AlarmKind::Upper) if self.upper.map(|upper| value > upper - self.deadband).unwrap_or(false) => return AlarmTransition::None,
+            Some(AlarmKind::Lower) if self.lower.map(|lower| value < lower + self.deadband).unwrap_or(false) => return AlarmTransition::None,
+            _ => {}
+        }
+        // a cleared alarm is raised again at once when the value crosses the opposite threshold
+        if self.upper.map(|upper| value > upper).unwrap_or(false) {
+            AlarmTransition::Raise(AlarmKind::Upper)
+        }
+        else if self.lower.map(|lower| value < lower).unwrap_or(false) {
+            AlarmTransition::Raise(AlarmKind::Lower)
+        }
+        else if active.is_some() {
+            AlarmTransition::Clear
+        }
+        else {
+            AlarmTransition::None
+        }
+    }
+}
+
+// every version of threshold configs of a device and model so data written in one call load them once
+#[derive(Debug, Default, PartialEq, Clone)]
+pub(crate) struct ThresholdHistory {
+    pub(crate) device_id: Uuid,
+    pub(crate) model_id: Uuid,
+    pub(crate) model_configs: Vec<ModelConfigHistorySchema>,
+    pub(crate) device_configs: EffectiveConfigHistory
+}
+
+impl ThresholdHistory {
+    pub(crate) fn at(&self, timestamp: DateTime<Utc>, number: usize) -> Vec<Option<Threshold>>
+    {
+        let model_configs: Vec<ModelConfigSchema> = self.model_configs.iter()
+            .filter(|cfg| cfg.valid_at(timestamp))
+            .map(|cfg| cfg.clone().into_config())
+            .collect();
+        let device_configs: Vec<DeviceConfigSchema> = self.device_configs.at(timestamp)
+            .into_iter()
+            .map(|cfg| cfg.into_device_config())
+            .collect();
+        (0..number)
+            .map(|index| Threshold::from_configs(index, &model_configs, &device_configs))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::value::DataValue::{I32, F64};
+
+    #[test]
+    fn threshold_config()
+    {
+        let model_configs = [
+            ModelConfigSchema { index: 0, name: "upper_threshold".to_owned(), value: I32(250), category: "THRESHOLD".to_owned(), ..Default::default() },
+            ModelConfigSchema { index: 0, name: "deadband".to_owned(), value: F64(-5.0), category: "THRESHOLD".to_owned(), ..Default::default() }
+        ];
+        let device_configs = [
+            DeviceConfigSchema { name: "upper_threshold_0".to_owned(), value: I32(200), category: "THRESHOLD".to_owned(), ..Default::default() },
+            DeviceConfigSchema { name: "lower_threshold_0".to_owned(), value: I32(10), category: "THRESHOLD".to_owned(), ..Default::default() }
+        ];
+        assert_eq!(Threshold::from_configs(0, &model_configs, &[]), Some(Threshold { upper: Some(250.0), lower: None, deadband: 5.0 }));
+        assert_eq!(Threshold::from_configs(0, &model_configs, &device_configs), Some(Threshold { upper: Some(200.0), lower: Some(10.0), deadband: 5.0 }));
+        assert_eq!(Threshold::from_configs(1, &model_configs, &device_configs), None);
+    }
+
+    #[test]
+    fn threshold_evaluate()
+    {
+        let threshold = Threshold { upper: Some(100.0), lower: Some(0.0), deadband: 5.0 };
+        assert_eq!(threshold.evaluate(50.0, None), AlarmTransition::None);
+        assert_eq!(threshold.evaluate(101.0, None), AlarmTransition::Raise(AlarmKind::Upper));
+        assert_eq!(threshold.evaluate(-1.0, None), AlarmTransition::Raise(AlarmKind::Lower));
+        assert_eq!(threshold.evaluate(97.0, Some(AlarmKind::Upper)), AlarmTransition::None);
+        assert_eq!(threshold.evaluate(95.0, Some(AlarmKind::Upper)), AlarmTransition::Clear);
+        assert_eq!(threshold.evaluate(3.0, Some(AlarmKind::Lower)), AlarmTransition::None);
+        assert_eq!(threshold.evaluate(5.0, Some(AlarmKind::Lower)), AlarmTransition::Clear);
+    }
+
+    #[test]
+    fn threshold_evaluate_opposite()
+    {
+        // value jumps across both thresholds while an alarm is active
+        let threshold = Threshold { upper: Some(100.0), lower: Some(0.0), deadband: 5.0 };
+        assert_eq!(threshold.evaluate(-1.0, Some(AlarmKind::Upper)), AlarmTransition::Raise(AlarmKind::Lower));
+        assert_eq!(threshold.evaluate(101.0, Some(AlarmKind::Lower)), AlarmTransition::Raise(AlarmKind::Upper));
+        // alarm of a removed threshold is cleared
+        let threshold = Threshold { upper: None, lower: Some(0.0), deadband: 5.0 };
+        assert_eq!(threshold.evaluate(150.0, Some(AlarmKind::Upper)), AlarmTransition::Clear);
+    }
+
+    #[test]
+    fn threshold_history()
+    {
+        let time = |secs: i64| DateTime::<Utc>::from_timestamp(secs, 0).unwrap();
+        let config = |value: i32, valid_from: i64, valid_to: Option<i64>| ModelConfigHistorySchema {
+            index: 0, name: "upper_threshold".to_owned(), value: I32(value), category: "THRESHOLD".to_owned(),
+            valid_from: time(valid_from), valid_to: valid_to.map(time), ..Default::default()
+        };
+        let history = ThresholdHistory {
+            model_configs: vec![config(250, 100, Some(200)), config(300, 200, None)],
+            ..Default::default()
+        };
+        assert_eq!(history.at(time(50), 2), vec![None, None]);
+        assert_eq!(history.at(time(150), 1), vec![Some(Threshold { upper: Some(250.0), lower: None, deadband: 0.0 })]);
+        assert_eq!(history.at(time(200), 1), vec![Some(Threshold { upper: Some(300.0), lower: None, deadband: 0.0 })]);
+    }
+
+}
@@ -15,6 +15,16 @@ pub(crate) enum DataBuffer {
     Data
 }
 
+#[derive(Iden)]
+pub(crate) enum DataBufferArchive {
+    Table,
+    DeviceId,
+    ModelId,
+    Timestamp,
+    Tag,
+    Data
+}
+
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct BufferSchema {
     pub id: i32,
@@ -0,0 +1,63 @@
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use crate::schema::model::ModelSchema;
+use crate::schema::device::TypeSchema;
+use crate::schema::set::SetTemplateSchema;
+use crate::schema::group::GroupModelSchema;
+
+pub const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BundleSchema {
+    pub version: u32,
+    pub models: Vec<ModelSchema>,
+    pub types: Vec<TypeSchema>,
+    pub set_templates: Vec<SetTemplateSchema>,
+    pub group_models: Vec<GroupModelSchema>
+}
+
+impl BundleSchema {
+    pub fn to_json(&self) -> Result<String, serde_json::Error>
+    {
+        serde_json::to_string_pretty(self)
+    }
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error>
+    {
+        serde_json::from_str(json)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleKind {
+    Model,
+    Type,
+    SetTemplate,
+    GroupModel
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleChange {
+    Created,
+    Updated,
+    Unchanged
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleDiffSchema {
+    pub kind: BundleKind,
+    pub id: Uuid,
+    pub name: String,
+    pub change: BundleChange
+}
+
+impl BundleDiffSchema {
+    pub(crate) fn new(kind: BundleKind, id: Uuid, name: &str, exists: bool, equal: bool) -> Self
+    {
+        let change = match (exists, equal) {
+            (false, _) => BundleChange::Created,
+            (true, false) => BundleChange::Updated,
+            (true, true) => BundleChange::Unchanged
+        };
+        Self { kind, id, name: name.to_owned(), change }
+    }
+}
@@ -0,0 +1,136 @@
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::schema::value::DataValue;
+use crate::schema::device::DeviceConfigSchema;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Calibration {
+    Linear(f64, f64),
+    Polynomial(Vec<f64>),
+    Lookup(Vec<(f64, f64)>)
+}
+
+impl Calibration {
+    // model config category which select a calibration for the field of config index
+    // the config name is the transform kind and the config value is the device config category of the parameters
+    pub const CATEGORY: &'static str = "CALIBRATION";
+    pub const LINEAR: &'static str = "linear";
+    pub const POLYNOMIAL: &'static str = "polynomial";
+    pub const LOOKUP: &'static str = "lookup";
+
+    pub(crate) fn from_configs(kind: &str, configs: &[DeviceConfigSchema]) -> Option<Self>
+    {
+        let param = |name: String| -> Option<f64> {
+            configs.iter().find(|cfg| cfg.name == name).and_then(|cfg| cfg.value.to_f64())
+        };
+        match kind {
+            Self::LINEAR => {
+                // calibrated value = (raw - coef_0) * coef_1
+                let offset = param(String::from("coef_0")).unwrap_or(0.0);
+                let gain = param(String::from("coef_1")).unwrap_or(1.0);
+                Some(Self::Linear(offset, gain))
+            },
+            Self::POLYNOMIAL => {
+                // calibrated value = coef_0 + coef_1 * raw + coef_2 * raw^2 + ...
+                let coefs: Vec<f64> = (0..).map_while(|i| param(format!("coef_{}", i))).collect();
+                if coefs.is_empty() {
+                    return None;
+                }
+                Some(Self::Polynomial(coefs))
+            },
+            Self::LOOKUP => {
+                // table points are x_0 and y_0, x_1 and y_1, ...
+                let mut points: Vec<(f64, f64)> = (0..)
+                    .map_while(|i| Some((param(format!("x_{}", i))?, param(format!("y_{}", i))?)))
+                    .collect();
+                if points.is_empty() {
+                    return None;
+                }
+                points.sort_by(|a, b| a.0.total_cmp(&b.0));
+                Some(Self::Lookup(points))
+            },
+            _ => None
+        }
+    }
+
+    pub fn apply(&self, raw: f64) -> f64
+    {
+        match self {
+            Self::Linear(offset, gain) => (raw - offset) * gain,
+            Self::Polynomial(coefs) => {
+                coefs.iter().rev().fold(0.0, |acc, coef| acc * raw + coef)
+            },
+            Self::Lookup(points) => {
+                // linear interpolation between table points and clamped outside the table
+                let first = points[0];
+                let last = points[points.len() - 1];
+                if raw <= first.0 {
+                    return first.1;
+                }
+                if raw >= last.0 {
+                    return last.1;
+                }
+                let i = points.iter().position(|p| p.0 >= raw).unwrap_or(points.len() - 1);
+                let (x0, y0) = points[i - 1];
+                let (x1, y1) = points[i];
+                y0 + (raw - x0) * (y1 - y0) / (x1 - x0)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct DataCalibratedSchema {
+    pub device_id: Uuid,
+    pub model_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub raw: Vec<DataValue>,
+    pub data: Vec<DataValue>,
+    pub tag: i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DataValue::{I32, F64};
+
+    fn config(name: &str, value: DataValue) -> DeviceConfigSchema
+    {
+        DeviceConfigSchema { name: name.to_owned(), value, category: "CONVERSION".to_owned(), ..Default::default() }
+    }
+
+    #[test]
+    fn calibration_linear()
+    {
+        let configs = [config("coef_0", I32(-21)), config("coef_1", F64(0.1934))];
+        let calibration = Calibration::from_configs(Calibration::LINEAR, &configs).unwrap();
+        assert_eq!(calibration, Calibration::Linear(-21.0, 0.1934));
+        assert_eq!(calibration.apply(1231.0), (1231.0 + 21.0) * 0.1934);
+    }
+
+    #[test]
+    fn calibration_polynomial()
+    {
+        let configs = [config("coef_0", F64(1.0)), config("coef_1", F64(2.0)), config("coef_2", F64(0.5))];
+        let calibration = Calibration::from_configs(Calibration::POLYNOMIAL, &configs).unwrap();
+        assert_eq!(calibration.apply(2.0), 1.0 + 2.0 * 2.0 + 0.5 * 4.0);
+        assert_eq!(Calibration::from_configs(Calibration::POLYNOMIAL, &[]), None);
+    }
+
+    #[test]
+    fn calibration_lookup()
+    {
+        let configs = [
+            config("x_0", I32(0)), config("y_0", F64(0.0)),
+            config("x_1", I32(200)), config("y_1", F64(10.0)),
+            config("x_2", I32(100)), config("y_2", F64(8.0))
+        ];
+        let calibration = Calibration::from_configs(Calibration::LOOKUP, &configs).unwrap();
+        assert_eq!(calibration, Calibration::Lookup(vec![(0.0, 0.0), (100.0, 8.0), (200.0, 10.0)]));
+        assert_eq!(calibration.apply(-5.0), 0.0);
+        assert_eq!(calibration.apply(50.0), 4.0);
+        assert_eq!(calibration.apply(150.0), 9.0);
+        assert_eq!(calibration.apply(300.0), 10.0);
+    }
+
+}
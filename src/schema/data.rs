@@ -14,6 +14,16 @@ pub(crate) enum Data {
     Data
 }
 
+#[derive(Iden)]
+pub(crate) enum DataArchive {
+    Table,
+    DeviceId,
+    ModelId,
+    Timestamp,
+    Tag,
+    Data
+}
+
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct DataSchema {
     pub device_id: Uuid,
@@ -0,0 +1,250 @@
+use sea_query::Iden;
+use uuid::Uuid;
+
+#[derive(Iden)]
+pub(crate) enum ModelDerived {
+    Table,
+    ModelId,
+    Index,
+    Expression
+}
+
+#[derive(Iden)]
+pub(crate) enum ModelDerivedSource {
+    Table,
+    ModelId,
+    SourceIndex,
+    SourceModelId,
+    SourceField
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct DerivedModelSchema {
+    pub model_id: Uuid,
+    pub sources: Vec<DerivedSource>,
+    pub expressions: Vec<String>
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct DerivedSource {
+    pub model_id: Uuid,
+    pub index: u8
+}
+
+// arithmetic expression of a derived model field, source values are referenced as $0, $1, ...
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expression {
+    Number(f64),
+    Variable(usize),
+    Negate(Box<Expression>),
+    Binary(char, Box<Expression>, Box<Expression>),
+    Function(String, Vec<Expression>)
+}
+
+impl Expression {
+    pub(crate) fn parse(text: &str) -> Option<Self>
+    {
+        let tokens: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut position = 0;
+        let expression = Self::parse_sum(&tokens, &mut position)?;
+        if position != tokens.len() {
+            return None;
+        }
+        Some(expression)
+    }
+
+    fn parse_sum(tokens: &[char], position: &mut usize) -> Option<Self>
+    {
+        let mut left = Self::parse_product(tokens, position)?;
+        while let Some(&op) = tokens.get(*position) {
+            if op != '+' && op != '-' {
+                break;
+            }
+            *position += 1;
+            let right = Self::parse_product(tokens, position)?;
+            left = Self::Binary(op, Box::new(left), Box::new(right));
+        }
+        Some(left)
+    }
+
+    fn parse_product(tokens: &[char], position: &mut usize) -> Option<Self>
+    {
+        let mut left = Self::parse_unary(tokens, position)?;
+        while let Some(&op) = tokens.get(*position) {
+            if op != '*' && op != '/' {
+                break;
+            }
+            *position += 1;
+            let right = Self::parse_unary(tokens, position)?;
+            left = Self::Binary(op, Box::new(left), Box::new(right));
+        }
+        Some(left)
+    }
+
+    fn parse_unary(tokens: &[char], position: &mut usize) -> Option<Self>
+    {
+        if tokens.get(*position) == Some(&'-') {
+            *position += 1;
+            let value = Self::parse_unary(tokens, position)?;
+            return Some(Self::Negate(Box::new(value)));
+        }
+        Self::parse_power(tokens, position)
+    }
+
+    fn parse_power(tokens: &[char], position: &mut usize) -> Option<Self>
+    {
+        let base = Self::parse_primary(tokens, position)?;
+        if tokens.get(*position) == Some(&'^') {
+            *position += 1;
+            // power is right associative and binds tighter than negation on its left
+            let exponent = Self::parse_unary(tokens, position)?;
+            return Some(Self::Binary('^', Box::new(base), Box::new(exponent)));
+        }
+        Some(base)
+    }
+
+    fn parse_primary(tokens: &[char], position: &mut usize) -> Option<Self>
+    {
+        let take = |position: &mut usize, f: fn(&char) -> bool| -> String {
+            let begin = *position;
+            while tokens.get(*position).map(f).unwrap_or(false) {
+                *position += 1;
+            }
+            tokens[begin..*position].iter().collect()
+        };
+        match tokens.get(*position)? {
+            '(' => {
+                *position += 1;
+                let value = Self::parse_sum(tokens, position)?;
+                if tokens.get(*position) != Some(&')') {
+                    return None;
+                }
+                *position += 1;
+                Some(value)
+            },
+            '$' => {
+                *position += 1;
+                let index = take(position, |c| c.is_ascii_digit());
+                index.parse().ok().map(Self::Variable)
+            },
+            c if c.is_ascii_digit() || *c == '.' => {
+                let number = take(position, |c| c.is_ascii_digit() || *c == '.');
+                number.parse().ok().map(Self::Number)
+            },
+            c if c.is_ascii_alphabetic() => {
+                let name = take(position, |c| c.is_ascii_alphanumeric() || *c == '_');
+                if tokens.get(*position) != Some(&'(') {
+                    return None;
+                }
+                *position += 1;
+                let mut args = vec![Self::parse_sum(tokens, position)?];
+                while tokens.get(*position) == Some(&',') {
+                    *position += 1;
+                    args.push(Self::parse_sum(tokens, position)?);
+                }
+                if tokens.get(*position) != Some(&')') {
+                    return None;
+                }
+                *position += 1;
+                let arity = match name.as_str() {
+                    "abs" | "sqrt" | "exp" | "ln" | "log10" | "sin" | "cos" | "tan" => 1,
+                    "min" | "max" | "pow" | "atan2" => 2,
+                    _ => return None
+                };
+                if args.len() != arity {
+                    return None;
+                }
+                Some(Self::Function(name, args))
+            },
+            _ => None
+        }
+    }
+
+    pub(crate) fn max_variable(&self) -> Option<usize>
+    {
+        match self {
+            Self::Number(_) => None,
+            Self::Variable(index) => Some(*index),
+            Self::Negate(value) => value.max_variable(),
+            Self::Binary(_, left, right) => left.max_variable().max(right.max_variable()),
+            Self::Function(_, args) => args.iter().filter_map(|arg| arg.max_variable()).max()
+        }
+    }
+
+    pub(crate) fn evaluate(&self, values: &[f64]) -> Option<f64>
+    {
+        let result = match self {
+            Self::Number(value) => *value,
+            Self::Variable(index) => *values.get(*index)?,
+            Self::Negate(value) => -value.evaluate(values)?,
+            Self::Binary(op, left, right) => {
+                let left = left.evaluate(values)?;
+                let right = right.evaluate(values)?;
+                match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    _ => left.powf(right)
+                }
+            },
+            Self::Function(name, args) => {
+                let args: Vec<f64> = args.iter().map(|arg| arg.evaluate(values)).collect::<Option<Vec<f64>>>()?;
+                match name.as_str() {
+                    "abs" => args[0].abs(),
+                    "sqrt" => args[0].sqrt(),
+                    "exp" => args[0].exp(),
+                    "ln" => args[0].ln(),
+                    "log10" => args[0].log10(),
+                    "sin" => args[0].sin(),
+                    "cos" => args[0].cos(),
+                    "tan" => args[0].tan(),
+                    "min" => args[0].min(args[1]),
+                    "max" => args[0].max(args[1]),
+                    "pow" => args[0].powf(args[1]),
+                    _ => args[0].atan2(args[1])
+                }
+            }
+        };
+        match result.is_finite() {
+            true => Some(result),
+            false => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expression_evaluate()
+    {
+        let expression = Expression::parse("$0 * $1").unwrap();
+        assert_eq!(expression.evaluate(&[220.0, 1.5]), Some(330.0));
+        let expression = Expression::parse("sqrt($0^2 + $1 ^ 2)").unwrap();
+        assert_eq!(expression.evaluate(&[3.0, 4.0]), Some(5.0));
+        let expression = Expression::parse("-(1 + 2) * 3 - 2^3^2 / 64").unwrap();
+        assert_eq!(expression.evaluate(&[]), Some(-17.0));
+        let expression = Expression::parse("-2^2 + 2^-1").unwrap();
+        assert_eq!(expression.evaluate(&[]), Some(-3.5));
+        let expression = Expression::parse("max($0, $2) / 2").unwrap();
+        assert_eq!(expression.max_variable(), Some(2));
+        assert_eq!(expression.evaluate(&[1.0, 2.0, 5.0]), Some(2.5));
+        // missing variable and division by zero
+        assert_eq!(expression.evaluate(&[1.0]), None);
+        assert_eq!(Expression::parse("$0 / 0").unwrap().evaluate(&[1.0]), None);
+    }
+
+    #[test]
+    fn expression_invalid()
+    {
+        assert_eq!(Expression::parse(""), None);
+        assert_eq!(Expression::parse("$0 +"), None);
+        assert_eq!(Expression::parse("($0 * 2"), None);
+        assert_eq!(Expression::parse("unknown($0)"), None);
+        assert_eq!(Expression::parse("max($0)"), None);
+        assert_eq!(Expression::parse("$"), None);
+    }
+
+}
@@ -1,4 +1,7 @@
 use sea_query::Iden;
+use serde::{Serialize, Deserialize};
+use sqlx::Error;
+use sqlx::types::chrono::{DateTime, Utc};
 use uuid::Uuid;
 use crate::schema::value::{DataValue, DataType};
 use rmcs_resource_api::device;
@@ -11,7 +14,21 @@ pub(crate) enum Device {
     TypeId,
     SerialNumber,
     Name,
-    Description
+    Description,
+    Lifecycle,
+    LifecycleTimestamp,
+    ParentId,
+    Latitude,
+    Longitude,
+    Altitude
+}
+
+#[derive(Iden)]
+pub(crate) enum DeviceTree {
+    Table,
+    DeviceId,
+    ParentId,
+    Depth
 }
 
 #[derive(Iden)]
@@ -29,6 +46,43 @@ pub(crate) enum DeviceTypeModel {
     ModelId
 }
 
+#[derive(Iden)]
+pub(crate) enum DeviceConfigHistory {
+    Table,
+    ConfigId,
+    DeviceId,
+    Name,
+    Value,
+    Type,
+    Category,
+    ValidFrom,
+    ValidTo
+}
+
+#[derive(Iden)]
+pub(crate) enum DeviceTypeConfigKey {
+    Table,
+    TypeId,
+    Name,
+    Category,
+    Type,
+    Required,
+    DefaultValue,
+    Min,
+    Max
+}
+
+#[derive(Iden)]
+pub(crate) enum DeviceTypeConfig {
+    Table,
+    Id,
+    TypeId,
+    Name,
+    Value,
+    Type,
+    Category
+}
+
 #[derive(Iden)]
 pub(crate) enum DeviceConfig {
     Table,
@@ -37,7 +91,11 @@ pub(crate) enum DeviceConfig {
     Name,
     Value,
     Type,
-    Category
+    Category,
+    DesiredTimestamp,
+    ReportedValue,
+    ReportedType,
+    ReportedTimestamp
 }
 
 pub(crate) enum DeviceKind {
@@ -45,6 +103,112 @@ pub(crate) enum DeviceKind {
     Gateway
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DeviceLifecycle {
+    Provisioned,
+    #[default]
+    Active,
+    Maintenance,
+    Decommissioned
+}
+
+impl From<i16> for DeviceLifecycle {
+    fn from(value: i16) -> Self {
+        match value {
+            0 => Self::Provisioned,
+            2 => Self::Maintenance,
+            3 => Self::Decommissioned,
+            _ => Self::Active
+        }
+    }
+}
+
+impl DeviceLifecycle {
+    pub(crate) const ALL: [DeviceLifecycle; 4] = [Self::Provisioned, Self::Active, Self::Maintenance, Self::Decommissioned];
+}
+
+// combine included and excluded lifecycle states into the states a device query is restricted to
+pub(crate) fn lifecycle_filter(include: Option<&[DeviceLifecycle]>, exclude: &[DeviceLifecycle]) -> Option<Vec<DeviceLifecycle>>
+{
+    if include.is_none() && exclude.is_empty() {
+        return None;
+    }
+    let include = include.unwrap_or(&DeviceLifecycle::ALL);
+    Some(include.iter().filter(|lifecycle| !exclude.contains(lifecycle)).copied().collect())
+}
+
+impl From<DeviceLifecycle> for i16 {
+    fn from(value: DeviceLifecycle) -> Self {
+        match value {
+            DeviceLifecycle::Provisioned => 0,
+            DeviceLifecycle::Active => 1,
+            DeviceLifecycle::Maintenance => 2,
+            DeviceLifecycle::Decommissioned => 3
+        }
+    }
+}
+
+// per type uniqueness is enforced by a unique index, global uniqueness is only checked before writes
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SerialScope {
+    Global,
+    #[default]
+    Type
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CompatibilityMode {
+    #[default]
+    Disabled,
+    Warn,
+    Strict
+}
+
+// error of strict compatibility mode, it is returned as Error::InvalidArgument
+// and the device and model pair is read back with DeviceModelIncompatible::try_from
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceModelIncompatible {
+    pub device_id: Uuid,
+    pub model_id: Uuid,
+    message: String
+}
+
+impl DeviceModelIncompatible {
+    pub(crate) fn new(device_id: Uuid, model_id: Uuid, message: &str) -> Self {
+        let message = format!("{} (device {}, model {})", message, device_id, model_id);
+        Self { device_id, model_id, message }
+    }
+}
+
+impl std::fmt::Display for DeviceModelIncompatible {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<DeviceModelIncompatible> for Error {
+    fn from(value: DeviceModelIncompatible) -> Self {
+        Error::InvalidArgument(value.message)
+    }
+}
+
+impl TryFrom<&Error> for DeviceModelIncompatible {
+    type Error = ();
+    fn try_from(error: &Error) -> Result<Self, Self::Error> {
+        let message = match error {
+            Error::InvalidArgument(message) => message,
+            _ => return Err(())
+        };
+        let (_, pair) = message.rsplit_once(" (device ").ok_or(())?;
+        let (device_id, model_id) = pair.strip_suffix(')').and_then(|pair| pair.split_once(", model ")).ok_or(())?;
+        Ok(Self {
+            device_id: Uuid::parse_str(device_id).map_err(|_| ())?,
+            model_id: Uuid::parse_str(model_id).map_err(|_| ())?,
+            message: message.to_owned()
+        })
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Clone)]
 pub struct DeviceSchema {
     pub id: Uuid,
@@ -53,7 +217,13 @@ pub struct DeviceSchema {
     pub name: String,
     pub description: String,
     pub type_: TypeSchema,
-    pub configs: Vec<DeviceConfigSchema>
+    pub configs: Vec<DeviceConfigSchema>,
+    pub lifecycle: DeviceLifecycle,
+    pub lifecycle_timestamp: DateTime<Utc>,
+    pub parent_id: Option<Uuid>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude: Option<f64>
 }
 
 #[derive(Debug, Default, PartialEq, Clone)]
@@ -63,7 +233,13 @@ pub struct GatewaySchema {
     pub name: String,
     pub description: String,
     pub type_: TypeSchema,
-    pub configs: Vec<GatewayConfigSchema>
+    pub configs: Vec<GatewayConfigSchema>,
+    pub lifecycle: DeviceLifecycle,
+    pub lifecycle_timestamp: DateTime<Utc>,
+    pub parent_id: Option<Uuid>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude: Option<f64>
 }
 
 impl DeviceSchema {
@@ -75,12 +251,48 @@ impl DeviceSchema {
             name: self.name,
             description: self.description,
             type_: self.type_,
-            configs: self.configs.into_iter().map(|el| el.into_gateway_config()).collect()
+            configs: self.configs.into_iter().map(|el| el.into_gateway_config()).collect(),
+            lifecycle: self.lifecycle,
+            lifecycle_timestamp: self.lifecycle_timestamp,
+            parent_id: self.parent_id,
+            latitude: self.latitude,
+            longitude: self.longitude,
+            altitude: self.altitude
         }
     }
 }
 
 #[derive(Debug, Default, PartialEq, Clone)]
+pub struct DeviceTreeSchema {
+    pub device: DeviceSchema,
+    pub depth: u32,
+    pub children: Vec<DeviceTreeSchema>
+}
+
+impl DeviceTreeSchema {
+    // build nested tree from flat (device_id, parent_id, depth) nodes returned by recursive query
+    pub(crate) fn build(root_id: Uuid, nodes: &[(Uuid, Option<Uuid>, i32)], devices: &[DeviceSchema]) -> Option<Self>
+    {
+        let device = devices.iter().find(|device| device.id == root_id)?.clone();
+        let depth = nodes.iter().find(|(id, _, _)| *id == root_id).map(|(_, _, depth)| *depth as u32).unwrap_or_default();
+        let children = nodes.iter()
+            .filter(|(id, parent_id, node_depth)| *parent_id == Some(root_id) && *id != root_id && *node_depth as u32 == depth + 1)
+            .filter_map(|(id, _, _)| Self::build(*id, nodes, devices))
+            .collect();
+        Some(Self { device, depth, children })
+    }
+
+    pub fn device_ids(&self) -> Vec<Uuid>
+    {
+        let mut ids = vec![self.device.id];
+        for child in &self.children {
+            ids.extend(child.device_ids());
+        }
+        ids
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TypeSchema {
     pub id: Uuid,
     pub name: String,
@@ -94,7 +306,10 @@ pub struct DeviceConfigSchema {
     pub device_id: Uuid,
     pub name: String,
     pub value: DataValue,
-    pub category: String
+    pub category: String,
+    pub desired_timestamp: DateTime<Utc>,
+    pub reported: Option<DataValue>,
+    pub reported_timestamp: Option<DateTime<Utc>>
 }
 
 #[derive(Debug, Default, PartialEq, Clone)]
@@ -103,10 +318,213 @@ pub struct GatewayConfigSchema {
     pub gateway_id: Uuid,
     pub name: String,
     pub value: DataValue,
+    pub category: String,
+    pub desired_timestamp: DateTime<Utc>,
+    pub reported: Option<DataValue>,
+    pub reported_timestamp: Option<DateTime<Utc>>
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct DeviceConfigHistorySchema {
+    pub id: i32,
+    pub device_id: Uuid,
+    pub name: String,
+    pub value: DataValue,
+    pub category: String,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: Option<DateTime<Utc>>
+}
+
+impl DeviceConfigHistorySchema {
+    pub(crate) fn valid_at(&self, timestamp: DateTime<Utc>) -> bool
+    {
+        self.valid_from <= timestamp && self.valid_to.map(|valid_to| timestamp < valid_to).unwrap_or(true)
+    }
+
+    pub(crate) fn into_config(self) -> DeviceConfigSchema
+    {
+        DeviceConfigSchema {
+            id: self.id,
+            device_id: self.device_id,
+            name: self.name,
+            value: self.value,
+            category: self.category,
+            desired_timestamp: self.valid_from,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TypeConfigSchema {
+    pub id: i32,
+    pub type_id: Uuid,
+    pub name: String,
+    pub value: DataValue,
     pub category: String
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ConfigSource {
+    #[default]
+    Type,
+    Gateway,
+    Device
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct EffectiveConfigSchema {
+    pub id: i32,
+    pub device_id: Uuid,
+    pub name: String,
+    pub value: DataValue,
+    pub category: String,
+    pub source: ConfigSource
+}
+
+impl EffectiveConfigSchema {
+    // merge configs with the same category and name, gateway configs override type configs and device configs override both
+    pub(crate) fn merge(device_id: Uuid, type_configs: &[TypeConfigSchema], gateway_configs: &[DeviceConfigSchema], device_configs: &[DeviceConfigSchema]) -> Vec<Self>
+    {
+        let mut configs: Vec<Self> = Vec::new();
+        let type_iter = type_configs.iter()
+            .map(|cfg| (cfg.id, &cfg.name, &cfg.value, &cfg.category, ConfigSource::Type));
+        let gateway_iter = gateway_configs.iter()
+            .map(|cfg| (cfg.id, &cfg.name, &cfg.value, &cfg.category, ConfigSource::Gateway));
+        let device_iter = device_configs.iter()
+            .map(|cfg| (cfg.id, &cfg.name, &cfg.value, &cfg.category, ConfigSource::Device));
+        for (id, name, value, category, source) in type_iter.chain(gateway_iter).chain(device_iter) {
+            let config = Self {
+                id,
+                device_id,
+                name: name.clone(),
+                value: value.clone(),
+                category: category.clone(),
+                source
+            };
+            match configs.iter_mut().find(|el| &el.name == name && &el.category == category) {
+                Some(el) => *el = config,
+                None => configs.push(config)
+            }
+        }
+        configs
+    }
+
+    pub(crate) fn into_device_config(self) -> DeviceConfigSchema
+    {
+        DeviceConfigSchema {
+            id: self.id,
+            device_id: self.device_id,
+            name: self.name,
+            value: self.value,
+            category: self.category,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub(crate) struct EffectiveConfigHistory {
+    pub(crate) device_id: Uuid,
+    pub(crate) type_configs: Vec<TypeConfigSchema>,
+    pub(crate) gateway_configs: Vec<DeviceConfigHistorySchema>,
+    pub(crate) device_configs: Vec<DeviceConfigHistorySchema>
+}
+
+impl EffectiveConfigHistory {
+    pub(crate) fn at(&self, timestamp: DateTime<Utc>) -> Vec<EffectiveConfigSchema>
+    {
+        let filter = |configs: &[DeviceConfigHistorySchema]| -> Vec<DeviceConfigSchema> {
+            configs.iter()
+                .filter(|cfg| cfg.valid_at(timestamp))
+                .map(|cfg| cfg.clone().into_config())
+                .collect()
+        };
+        EffectiveConfigSchema::merge(self.device_id, &self.type_configs, &filter(&self.gateway_configs), &filter(&self.device_configs))
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TypeConfigKeySchema {
+    pub type_id: Uuid,
+    pub name: String,
+    pub category: String,
+    pub type_: DataType,
+    pub required: bool,
+    pub default: Option<DataValue>,
+    pub min: Option<f64>,
+    pub max: Option<f64>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigViolation {
+    Missing,
+    Undeclared,
+    TypeUnmatch(DataType),
+    OutOfRange(f64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigViolationSchema {
+    pub device_id: Uuid,
+    pub name: String,
+    pub category: String,
+    pub violation: ConfigViolation
+}
+
+impl TypeConfigKeySchema {
+    pub(crate) fn check(&self, value: &DataValue) -> Option<ConfigViolation>
+    {
+        if value.get_type() != self.type_ {
+            return Some(ConfigViolation::TypeUnmatch(value.get_type()));
+        }
+        match value.to_f64() {
+            Some(number) if self.min.map(|min| number < min).unwrap_or(false)
+                || self.max.map(|max| number > max).unwrap_or(false) => Some(ConfigViolation::OutOfRange(number)),
+            _ => None
+        }
+    }
+
+    // check configs of a device against config keys declared by its type, type configs may provide required keys
+    // a type without any declared config keys accepts every config
+    pub(crate) fn validate(device_id: Uuid, keys: &[Self], type_configs: &[TypeConfigSchema], configs: &[DeviceConfigSchema]) -> Vec<ConfigViolationSchema>
+    {
+        let mut violations = Vec::new();
+        if keys.is_empty() {
+            return violations;
+        }
+        let mut push = |name: &str, category: &str, violation: ConfigViolation| violations.push(ConfigViolationSchema {
+            device_id,
+            name: name.to_owned(),
+            category: category.to_owned(),
+            violation
+        });
+        for config in configs {
+            match keys.iter().find(|key| key.name == config.name && key.category == config.category) {
+                Some(key) => if let Some(violation) = key.check(&config.value) {
+                    push(&config.name, &config.category, violation);
+                },
+                None => push(&config.name, &config.category, ConfigViolation::Undeclared)
+            }
+        }
+        for key in keys.iter().filter(|key| key.required) {
+            let exists = configs.iter().any(|cfg| cfg.name == key.name && cfg.category == key.category)
+                || type_configs.iter().any(|cfg| cfg.name == key.name && cfg.category == key.category);
+            if !exists {
+                push(&key.name, &key.category, ConfigViolation::Missing);
+            }
+        }
+        violations
+    }
+}
+
 impl DeviceConfigSchema {
+    // desired value is not applied until the device reports the same value
+    pub fn is_drift(&self) -> bool
+    {
+        self.reported.as_ref() != Some(&self.value)
+    }
+
     pub(crate) fn into_gateway_config(self) -> GatewayConfigSchema
     {
         GatewayConfigSchema {
@@ -114,11 +532,21 @@ impl DeviceConfigSchema {
             gateway_id: self.device_id,
             name: self.name,
             value: self.value,
-            category: self.category
+            category: self.category,
+            desired_timestamp: self.desired_timestamp,
+            reported: self.reported,
+            reported_timestamp: self.reported_timestamp
         }
     }
 }
 
+impl GatewayConfigSchema {
+    pub fn is_drift(&self) -> bool
+    {
+        self.reported.as_ref() != Some(&self.value)
+    }
+}
+
 impl From<device::DeviceSchema> for DeviceSchema {
     fn from(value: device::DeviceSchema) -> Self {
         Self {
@@ -128,7 +556,8 @@ impl From<device::DeviceSchema> for DeviceSchema {
             name: value.name,
             description: value.description,
             type_: value.device_type.map(|s| s.into()).unwrap_or_default(),
-            configs: value.configs.into_iter().map(|e| e.into()).collect()
+            configs: value.configs.into_iter().map(|e| e.into()).collect(),
+            ..Default::default()
         }
     }
 }
@@ -155,7 +584,8 @@ impl From<device::GatewaySchema> for GatewaySchema {
             name: value.name,
             description: value.description,
             type_:  value.gateway_type.map(|s| s.into()).unwrap_or_default(),
-            configs: value.configs.into_iter().map(|e| e.into()).collect()
+            configs: value.configs.into_iter().map(|e| e.into()).collect(),
+            ..Default::default()
         }
     }
 }
@@ -183,7 +613,8 @@ impl From<device::ConfigSchema> for DeviceConfigSchema {
                 &value.config_bytes,
                 DataType::from(value.config_type)
             ),
-            category: value.category
+            category: value.category,
+            ..Default::default()
         }
     }
 }
@@ -211,7 +642,8 @@ impl From<device::ConfigSchema> for GatewayConfigSchema {
                 &value.config_bytes,
                 DataType::from(value.config_type)
             ),
-            category: value.category
+            category: value.category,
+            ..Default::default()
         }
     }
 }
@@ -250,3 +682,140 @@ impl Into<device::TypeSchema> for TypeSchema {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DataValue::I32;
+
+    #[test]
+    fn effective_config_merge()
+    {
+        let device_id = Uuid::new_v4();
+        let type_configs = [
+            TypeConfigSchema { id: 1, name: "coef_0".to_owned(), value: I32(0), category: "CONVERSION".to_owned(), ..Default::default() },
+            TypeConfigSchema { id: 2, name: "coef_1".to_owned(), value: I32(1), category: "CONVERSION".to_owned(), ..Default::default() },
+            TypeConfigSchema { id: 3, name: "period".to_owned(), value: I32(60), category: "NETWORK".to_owned(), ..Default::default() }
+        ];
+        let gateway_configs = [
+            DeviceConfigSchema { id: 10, name: "period".to_owned(), value: I32(30), category: "NETWORK".to_owned(), ..Default::default() }
+        ];
+        let device_configs = [
+            DeviceConfigSchema { id: 20, name: "coef_0".to_owned(), value: I32(-21), category: "CONVERSION".to_owned(), ..Default::default() },
+            DeviceConfigSchema { id: 21, name: "period".to_owned(), value: I32(10), category: "SAMPLING".to_owned(), ..Default::default() }
+        ];
+        let configs = EffectiveConfigSchema::merge(device_id, &type_configs, &gateway_configs, &device_configs);
+        let summary: Vec<(i32, &str, DataValue, ConfigSource)> = configs.iter()
+            .map(|cfg| (cfg.id, cfg.name.as_str(), cfg.value.clone(), cfg.source))
+            .collect();
+        assert_eq!(summary, vec![
+            (20, "coef_0", I32(-21), ConfigSource::Device),
+            (2, "coef_1", I32(1), ConfigSource::Type),
+            (10, "period", I32(30), ConfigSource::Gateway),
+            (21, "period", I32(10), ConfigSource::Device)
+        ]);
+        assert!(configs.iter().all(|cfg| cfg.device_id == device_id));
+    }
+
+    #[test]
+    fn effective_config_history_at()
+    {
+        let device_id = Uuid::new_v4();
+        let time = |seconds: i64| DateTime::from_timestamp(seconds, 0).unwrap_or_default();
+        let history = EffectiveConfigHistory {
+            device_id,
+            type_configs: vec![
+                TypeConfigSchema { id: 1, name: "coef_1".to_owned(), value: I32(1), category: "CONVERSION".to_owned(), ..Default::default() }
+            ],
+            gateway_configs: vec![],
+            device_configs: vec![
+                DeviceConfigHistorySchema { id: 20, name: "coef_1".to_owned(), value: I32(2), category: "CONVERSION".to_owned(), valid_from: time(0), valid_to: Some(time(100)), ..Default::default() },
+                DeviceConfigHistorySchema { id: 20, name: "coef_1".to_owned(), value: I32(3), category: "CONVERSION".to_owned(), valid_from: time(100), valid_to: Some(time(200)), ..Default::default() }
+            ]
+        };
+        assert_eq!(history.at(time(50))[0].value, I32(2));
+        assert_eq!(history.at(time(100))[0].value, I32(3));
+        // device config deleted at 200 so type config is in effect afterward
+        assert_eq!((history.at(time(250))[0].value.clone(), history.at(time(250))[0].source), (I32(1), ConfigSource::Type));
+    }
+
+    #[test]
+    fn device_tree_build()
+    {
+        let ids: Vec<Uuid> = (1..=5).map(Uuid::from_u128).collect();
+        let devices: Vec<DeviceSchema> = ids.iter().map(|&id| DeviceSchema { id, ..Default::default() }).collect();
+        // site controller -> gateway -> (sub gateway -> sensor, sensor)
+        let nodes = [
+            (ids[0], None, 0),
+            (ids[1], Some(ids[0]), 1),
+            (ids[2], Some(ids[1]), 2),
+            (ids[3], Some(ids[2]), 3),
+            (ids[4], Some(ids[1]), 2)
+        ];
+        let tree = DeviceTreeSchema::build(ids[0], &nodes, &devices).unwrap();
+        assert_eq!(tree.depth, 0);
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].children.iter().map(|c| c.device.id).collect::<Vec<Uuid>>(), [ids[2], ids[4]]);
+        assert_eq!(tree.children[0].children[0].children[0].depth, 3);
+        assert_eq!(tree.device_ids(), [ids[0], ids[1], ids[2], ids[3], ids[4]]);
+        assert_eq!(DeviceTreeSchema::build(Uuid::nil(), &nodes, &devices), None);
+    }
+
+    #[test]
+    fn type_config_key_validate()
+    {
+        let device_id = Uuid::new_v4();
+        let key = |name: &str, type_: DataType, required: bool| TypeConfigKeySchema {
+            name: name.to_owned(), category: "CONVERSION".to_owned(), type_, required, ..Default::default()
+        };
+        let keys = [
+            TypeConfigKeySchema { min: Some(1.0), max: Some(3600.0), category: "NETWORK".to_owned(), ..key("period", DataType::I32T, true) },
+            key("coef_0", DataType::F64T, true),
+            key("coef_1", DataType::F64T, true),
+            key("offset", DataType::F64T, false)
+        ];
+        let type_configs = [
+            TypeConfigSchema { name: "coef_1".to_owned(), value: DataValue::F64(1.0), category: "CONVERSION".to_owned(), ..Default::default() }
+        ];
+        let config = |name: &str, value: DataValue, category: &str| DeviceConfigSchema {
+            device_id, name: name.to_owned(), value, category: category.to_owned(), ..Default::default()
+        };
+        let configs = [
+            config("period", I32(0), "NETWORK"),
+            config("coef_o", DataValue::F64(0.5), "CONVERSION"),
+            config("offset", I32(2), "CONVERSION")
+        ];
+        let violations: Vec<(String, ConfigViolation)> = TypeConfigKeySchema::validate(device_id, &keys, &type_configs, &configs)
+            .into_iter().map(|v| (v.name, v.violation)).collect();
+        assert_eq!(violations, [
+            ("period".to_owned(), ConfigViolation::OutOfRange(0.0)),
+            ("coef_o".to_owned(), ConfigViolation::Undeclared),
+            ("offset".to_owned(), ConfigViolation::TypeUnmatch(DataType::I32T)),
+            ("coef_0".to_owned(), ConfigViolation::Missing)
+        ]);
+        assert!(TypeConfigKeySchema::validate(device_id, &[], &type_configs, &configs).is_empty());
+    }
+
+    #[test]
+    fn lifecycle_filter_include_exclude()
+    {
+        use DeviceLifecycle::*;
+        assert_eq!(lifecycle_filter(None, &[]), None);
+        assert_eq!(lifecycle_filter(None, &[Decommissioned]), Some(vec![Provisioned, Active, Maintenance]));
+        assert_eq!(lifecycle_filter(Some(&[Active, Decommissioned]), &[Decommissioned]), Some(vec![Active]));
+        assert_eq!(lifecycle_filter(Some(&[Maintenance]), &[]), Some(vec![Maintenance]));
+    }
+
+    #[test]
+    fn device_model_incompatible_error()
+    {
+        let (device_id, model_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let incompatible = DeviceModelIncompatible::new(device_id, model_id, "Model is not linked");
+        let error = Error::from(incompatible.clone());
+        assert!(matches!(error, Error::InvalidArgument(_)));
+        assert_eq!(DeviceModelIncompatible::try_from(&error), Ok(incompatible));
+        assert!(DeviceModelIncompatible::try_from(&Error::InvalidArgument("Model is not linked".to_owned())).is_err());
+        assert!(DeviceModelIncompatible::try_from(&Error::RowNotFound).is_err());
+    }
+
+}
@@ -1,6 +1,8 @@
 use sea_query::Iden;
+use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use rmcs_resource_api::group;
+use crate::schema::label::LabelSelector;
 
 #[derive(Iden)]
 pub enum GroupModel {
@@ -15,7 +17,9 @@ pub enum GroupModel {
 pub enum GroupModelMap {
     Table,
     GroupId,
-    ModelId
+    ModelId,
+    ValidFrom,
+    ValidTo
 }
 
 #[derive(Iden)]
@@ -32,8 +36,51 @@ pub enum GroupDevice {
 pub enum GroupDeviceMap {
     Table,
     GroupId,
-    DeviceId
+    DeviceId,
+    ValidFrom,
+    ValidTo
 }
+
+#[derive(Iden)]
+pub enum GroupModelChild {
+    Table,
+    GroupId,
+    ChildId
+}
+
+#[derive(Iden)]
+pub enum GroupDeviceChild {
+    Table,
+    GroupId,
+    ChildId
+}
+
+#[derive(Iden)]
+pub enum GroupModelRule {
+    Table,
+    GroupId,
+    TypeId,
+    Name,
+    Category,
+    Label
+}
+
+#[derive(Iden)]
+pub enum GroupDeviceRule {
+    Table,
+    GroupId,
+    TypeId,
+    GatewayId,
+    Name,
+    Label
+}
+
+#[derive(Iden)]
+pub(crate) enum GroupTree {
+    Table,
+    GroupId
+}
+
 #[derive(Clone, PartialEq)]
 pub(crate) enum GroupKind {
     Model,
@@ -102,7 +149,38 @@ impl GroupSchema {
     }
 }
 
+// membership criteria of a dynamic group, name is a LIKE pattern,
+// gateway_id only applies to device groups and category only to model groups
 #[derive(Debug, Default, PartialEq, Clone)]
+pub struct GroupRuleSchema {
+    pub type_id: Option<Uuid>,
+    pub gateway_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub category: Option<String>,
+    pub labels: LabelSelector
+}
+
+// members added to and removed from a group when its member list is replaced
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct GroupMemberDiff {
+    pub added: Vec<Uuid>,
+    pub removed: Vec<Uuid>
+}
+
+impl GroupMemberDiff {
+    pub(crate) fn new(current: &[Uuid], members: &[Uuid]) -> Self
+    {
+        let mut added: Vec<Uuid> = members.iter().filter(|id| !current.contains(id)).copied().collect();
+        added.sort();
+        added.dedup();
+        let mut removed: Vec<Uuid> = current.iter().filter(|id| !members.contains(id)).copied().collect();
+        removed.sort();
+        removed.dedup();
+        Self { added, removed }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct GroupModelSchema {
     pub id: Uuid,
     pub name: String,
@@ -200,3 +278,19 @@ impl Into<group::GroupDeviceSchema> for GroupGatewaySchema {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_member_diff()
+    {
+        let ids: Vec<Uuid> = (1..=4).map(Uuid::from_u128).collect();
+        let diff = GroupMemberDiff::new(&ids[..3], &[ids[3], ids[1], ids[3], ids[0]]);
+        assert_eq!(diff.added, [ids[3]]);
+        assert_eq!(diff.removed, [ids[2]]);
+        let diff = GroupMemberDiff::new(&ids, &ids);
+        assert_eq!(diff, GroupMemberDiff::default());
+    }
+}
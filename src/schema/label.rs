@@ -0,0 +1,181 @@
+use sea_query::Iden;
+use uuid::Uuid;
+
+#[derive(Iden)]
+pub(crate) enum DeviceLabel {
+    Table,
+    DeviceId,
+    Key,
+    Value
+}
+
+#[derive(Iden)]
+pub(crate) enum ModelLabel {
+    Table,
+    ModelId,
+    Key,
+    Value
+}
+
+#[derive(Iden)]
+pub(crate) enum SetLabel {
+    Table,
+    SetId,
+    Key,
+    Value
+}
+
+pub(crate) enum LabelKind {
+    Device,
+    Model,
+    Set
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct LabelSchema {
+    pub id: Uuid,
+    pub key: String,
+    pub value: String
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LabelRequirement {
+    Equal(String, String),
+    NotEqual(String, String),
+    In(String, Vec<String>),
+    NotIn(String, Vec<String>),
+    Exists(String),
+    NotExists(String)
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct LabelSelector {
+    pub requirements: Vec<LabelRequirement>
+}
+
+impl LabelSelector {
+    // comma separated requirements, e.g. "site=north,line in (3,4),env!=dev,calibrated,!retired"
+    pub fn parse(selector: &str) -> Result<Self, String>
+    {
+        let mut requirements = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        for (index, c) in selector.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    requirements.push(parse_requirement(&selector[start..index])?);
+                    start = index + 1;
+                },
+                _ => {}
+            }
+        }
+        if !selector[start..].trim().is_empty() || !requirements.is_empty() {
+            requirements.push(parse_requirement(&selector[start..])?);
+        }
+        Ok(Self { requirements })
+    }
+
+    pub fn matches(&self, labels: &[(&str, &str)]) -> bool
+    {
+        let value = |key: &str| labels.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+        self.requirements.iter().all(|requirement| match requirement {
+            LabelRequirement::Equal(key, val) => value(key) == Some(val.as_str()),
+            LabelRequirement::NotEqual(key, val) => value(key) != Some(val.as_str()),
+            LabelRequirement::In(key, vals) => value(key).map(|v| vals.iter().any(|val| val == v)).unwrap_or(false),
+            LabelRequirement::NotIn(key, vals) => value(key).map(|v| vals.iter().all(|val| val != v)).unwrap_or(true),
+            LabelRequirement::Exists(key) => value(key).is_some(),
+            LabelRequirement::NotExists(key) => value(key).is_none()
+        })
+    }
+}
+
+impl std::fmt::Display for LabelSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let terms: Vec<String> = self.requirements.iter().map(|requirement| match requirement {
+            LabelRequirement::Equal(key, value) => format!("{}={}", key, value),
+            LabelRequirement::NotEqual(key, value) => format!("{}!={}", key, value),
+            LabelRequirement::In(key, values) => format!("{} in ({})", key, values.join(",")),
+            LabelRequirement::NotIn(key, values) => format!("{} notin ({})", key, values.join(",")),
+            LabelRequirement::Exists(key) => key.clone(),
+            LabelRequirement::NotExists(key) => format!("!{}", key)
+        }).collect();
+        write!(f, "{}", terms.join(","))
+    }
+}
+
+fn parse_requirement(term: &str) -> Result<LabelRequirement, String>
+{
+    let term = term.trim();
+    let invalid = || format!("invalid label requirement '{}'", term);
+    let key = |key: &str| -> Result<String, String> {
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) || key.contains(['=', '!', '(', ')']) {
+            return Err(invalid());
+        }
+        Ok(key.to_owned())
+    };
+    let values = |values: &str| -> Result<Vec<String>, String> {
+        let values = values.trim().strip_prefix('(').and_then(|v| v.strip_suffix(')')).ok_or(invalid())?;
+        Ok(values.split(',').map(|v| v.trim().to_owned()).filter(|v| !v.is_empty()).collect())
+    };
+    if let Some(k) = term.strip_prefix('!') {
+        Ok(LabelRequirement::NotExists(key(k)?))
+    } else if let Some((k, v)) = term.split_once(" notin ") {
+        Ok(LabelRequirement::NotIn(key(k)?, values(v)?))
+    } else if let Some((k, v)) = term.split_once(" in ") {
+        Ok(LabelRequirement::In(key(k)?, values(v)?))
+    } else if let Some((k, v)) = term.split_once("!=") {
+        Ok(LabelRequirement::NotEqual(key(k)?, v.trim().to_owned()))
+    } else if let Some((k, v)) = term.split_once("==").or(term.split_once('=')) {
+        Ok(LabelRequirement::Equal(key(k)?, v.trim().to_owned()))
+    } else {
+        Ok(LabelRequirement::Exists(key(term)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LabelRequirement::*;
+
+    #[test]
+    fn label_selector_parse()
+    {
+        let selector = LabelSelector::parse("site=north, line in (3, 4),env!=dev,zone notin (a),calibrated,!retired").unwrap();
+        assert_eq!(selector.requirements, [
+            Equal("site".to_owned(), "north".to_owned()),
+            In("line".to_owned(), vec!["3".to_owned(), "4".to_owned()]),
+            NotEqual("env".to_owned(), "dev".to_owned()),
+            NotIn("zone".to_owned(), vec!["a".to_owned()]),
+            Exists("calibrated".to_owned()),
+            NotExists("retired".to_owned())
+        ]);
+        assert_eq!(LabelSelector::parse("").unwrap().requirements, []);
+        assert!(LabelSelector::parse("site=north,").is_err());
+        assert!(LabelSelector::parse("line in 3").is_err());
+    }
+
+    #[test]
+    fn label_selector_matches()
+    {
+        let labels = [("site", "north"), ("line", "3"), ("calibrated", "")];
+        assert!(LabelSelector::parse("site=north,line in (3,4),calibrated,!retired").unwrap().matches(&labels));
+        assert!(LabelSelector::parse("env!=dev,zone notin (a)").unwrap().matches(&labels));
+        assert!(!LabelSelector::parse("site=south").unwrap().matches(&labels));
+        assert!(!LabelSelector::parse("line notin (3)").unwrap().matches(&labels));
+        assert!(!LabelSelector::parse("!calibrated").unwrap().matches(&labels));
+    }
+
+    #[test]
+    fn label_selector_display()
+    {
+        let text = "site=north,line in (3,4),env!=dev,zone notin (a),calibrated,!retired";
+        let selector = LabelSelector::parse(text).unwrap();
+        assert_eq!(selector.to_string(), text);
+        assert_eq!(LabelSelector::parse(&selector.to_string()).unwrap(), selector);
+        assert_eq!(LabelSelector::default().to_string(), "");
+    }
+
+}
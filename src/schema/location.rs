@@ -0,0 +1,88 @@
+use sea_query::Iden;
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Iden)]
+pub(crate) enum DeviceLocation {
+    Table,
+    DeviceId,
+    Timestamp,
+    Latitude,
+    Longitude,
+    Altitude
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct DeviceLocationSchema {
+    pub device_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>
+}
+
+// mean earth radius in meters
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+// great circle distance in meters between two coordinates in degrees using haversine formula
+pub fn distance(latitude_1: f64, longitude_1: f64, latitude_2: f64, longitude_2: f64) -> f64
+{
+    let d_lat = (latitude_2 - latitude_1).to_radians();
+    let d_lon = (longitude_2 - longitude_1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + latitude_1.to_radians().cos() * latitude_2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+}
+
+pub(crate) fn is_valid(latitude: f64, longitude: f64) -> bool
+{
+    (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)
+}
+
+// bounding box (min_lat, min_lon, max_lat, max_lon) which contains every coordinate within radius of a center
+// min_lon is greater than max_lon when the box crosses the antimeridian
+pub(crate) fn radius_bounds(latitude: f64, longitude: f64, meters: f64) -> (f64, f64, f64, f64)
+{
+    let d_lat = (meters / EARTH_RADIUS).to_degrees();
+    let min_lat = latitude - d_lat;
+    let max_lat = latitude + d_lat;
+    if min_lat <= -90.0 || max_lat >= 90.0 {
+        return (min_lat.max(-90.0), -180.0, max_lat.min(90.0), 180.0);
+    }
+    let d_lon = (((meters / EARTH_RADIUS).sin() / latitude.to_radians().cos()).min(1.0)).asin().to_degrees();
+    if d_lon >= 180.0 {
+        return (min_lat, -180.0, max_lat, 180.0);
+    }
+    let wrap = |lon: f64| if lon < -180.0 { lon + 360.0 } else if lon > 180.0 { lon - 360.0 } else { lon };
+    (min_lat, wrap(longitude - d_lon), max_lat, wrap(longitude + d_lon))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn location_distance()
+    {
+        // one degree of latitude is about 111.2 km
+        assert!((distance(0.0, 0.0, 1.0, 0.0) - 111_195.0).abs() < 10.0);
+        // paris to london
+        assert!((distance(48.8566, 2.3522, 51.5074, -0.1278) - 343_550.0).abs() < 1000.0);
+        assert_eq!(distance(-6.2, 106.8, -6.2, 106.8), 0.0);
+    }
+
+    #[test]
+    fn location_radius_bounds()
+    {
+        let (min_lat, min_lon, max_lat, max_lon) = radius_bounds(-6.2, 106.8, 10_000.0);
+        assert!(min_lat < -6.2 && max_lat > -6.2 && min_lon < 106.8 && max_lon > 106.8);
+        let east = radius_bounds(-6.2, 106.8, 10_000.0);
+        assert!(distance(-6.2, 106.8, -6.2, east.3) >= 9_999.0);
+        // box crossing the antimeridian
+        let (_, min_lon, _, max_lon) = radius_bounds(0.0, 179.99, 10_000.0);
+        assert!(min_lon > max_lon);
+        // radius reaching the pole covers all longitude
+        assert_eq!(radius_bounds(89.99, 0.0, 10_000.0).1, -180.0);
+    }
+
+}
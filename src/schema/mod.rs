@@ -7,3 +7,13 @@ pub mod data;
 pub mod buffer;
 pub mod slice;
 pub mod log;
+pub mod bundle;
+pub mod calibration;
+pub mod derived;
+pub mod alarm;
+pub mod purge;
+pub mod status;
+pub mod provision;
+pub mod label;
+pub mod location;
+pub mod aggregate;
@@ -1,4 +1,6 @@
 use sea_query::Iden;
+use serde::{Serialize, Deserialize};
+use sqlx::types::chrono::{DateTime, Utc};
 use uuid::Uuid;
 use crate::schema::value::{DataValue, DataType};
 use rmcs_resource_api::model;
@@ -25,6 +27,20 @@ pub(crate) enum ModelConfig {
     Category
 }
 
+#[derive(Iden)]
+pub(crate) enum ModelConfigHistory {
+    Table,
+    ConfigId,
+    ModelId,
+    Index,
+    Name,
+    Value,
+    Type,
+    Category,
+    ValidFrom,
+    ValidTo
+}
+
 #[derive(Iden)]
 pub(crate) enum ModelTag {
     Table,
@@ -34,7 +50,7 @@ pub(crate) enum ModelTag {
     Members
 }
 
-#[derive(Debug, Default, PartialEq, Clone)]
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ModelSchema {
     pub id: Uuid,
     pub category: String,
@@ -45,7 +61,7 @@ pub struct ModelSchema {
     pub configs: Vec<Vec<ModelConfigSchema>>
 }
 
-#[derive(Debug, Default, PartialEq, Clone)]
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TagSchema {
     pub model_id: Uuid,
     pub tag: i16,
@@ -53,7 +69,7 @@ pub struct TagSchema {
     pub members: Vec<i16>
 }
 
-#[derive(Debug, Default, PartialEq, Clone)]
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ModelConfigSchema {
     pub id: i32,
     pub model_id: Uuid,
@@ -63,6 +79,37 @@ pub struct ModelConfigSchema {
     pub category: String
 }
 
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ModelConfigHistorySchema {
+    pub id: i32,
+    pub model_id: Uuid,
+    pub index: i16,
+    pub name: String,
+    pub value: DataValue,
+    pub category: String,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: Option<DateTime<Utc>>
+}
+
+impl ModelConfigHistorySchema {
+    pub(crate) fn valid_at(&self, timestamp: DateTime<Utc>) -> bool
+    {
+        self.valid_from <= timestamp && self.valid_to.map(|valid_to| timestamp < valid_to).unwrap_or(true)
+    }
+
+    pub(crate) fn into_config(self) -> ModelConfigSchema
+    {
+        ModelConfigSchema {
+            id: self.id,
+            model_id: self.model_id,
+            index: self.index,
+            name: self.name,
+            value: self.value,
+            category: self.category
+        }
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Clone)]
 pub(crate) struct ModelSchemaFlat {
     pub id: Uuid,
@@ -0,0 +1,350 @@
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use crate::schema::value::DataValue;
+use crate::schema::device::{TypeConfigKeySchema, ConfigViolation, SerialScope};
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DeviceManifestConfig {
+    pub name: String,
+    pub value: DataValue,
+    pub category: String
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DeviceManifestRow {
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    #[serde(default)]
+    pub gateway_id: Option<Uuid>,
+    pub type_id: Uuid,
+    pub serial_number: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub configs: Vec<DeviceManifestConfig>,
+    #[serde(default)]
+    pub group_ids: Vec<Uuid>
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DeviceManifest {
+    pub rows: Vec<DeviceManifestRow>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProvisionStatus {
+    Created,
+    Valid,
+    Invalid(String)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvisionReportSchema {
+    pub row: usize,
+    pub id: Uuid,
+    pub serial_number: String,
+    pub status: ProvisionStatus
+}
+
+// existing resources in database which manifest rows are validated against
+#[derive(Debug, Default)]
+pub(crate) struct ProvisionExisting {
+    pub(crate) type_ids: Vec<Uuid>,
+    pub(crate) gateway_ids: Vec<Uuid>,
+    pub(crate) device_ids: Vec<Uuid>,
+    pub(crate) serial_numbers: Vec<(String, Uuid)>,
+    pub(crate) group_ids: Vec<Uuid>,
+    pub(crate) gateway_group_ids: Vec<Uuid>,
+    pub(crate) config_keys: Vec<TypeConfigKeySchema>
+}
+
+impl DeviceManifest {
+    pub(crate) fn coerce_configs(&mut self, config_keys: &[TypeConfigKeySchema])
+    {
+        for row in self.rows.iter_mut() {
+            row.coerce_configs(config_keys);
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error>
+    {
+        serde_json::from_str(json)
+    }
+
+    // header row is required, columns: id, gateway_id, type_id, serial_number, name, description, group_ids, configs
+    // group_ids are separated by ';' and configs are written as CATEGORY:name=value separated by ';'
+    pub fn from_csv(csv: &str) -> Result<Self, String>
+    {
+        let mut lines = csv.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let header: Vec<String> = match lines.next() {
+            Some((_, line)) => split_csv_line(line).into_iter().map(|column| column.trim().to_lowercase()).collect(),
+            None => return Ok(Self::default())
+        };
+        for column in ["type_id", "serial_number", "name"] {
+            if !header.iter().any(|h| h == column) {
+                return Err(format!("missing column {}", column));
+            }
+        }
+        let mut rows = Vec::new();
+        for (number, line) in lines {
+            let fields = split_csv_line(line);
+            let field = |column: &str| -> &str {
+                header.iter().position(|h| h == column)
+                    .and_then(|index| fields.get(index))
+                    .map(|value| value.trim())
+                    .unwrap_or_default()
+            };
+            let uuid = |column: &str| -> Result<Option<Uuid>, String> {
+                match field(column) {
+                    "" => Ok(None),
+                    value => Uuid::parse_str(value).map(Some).map_err(|_| format!("line {}: invalid {}", number + 1, column))
+                }
+            };
+            let group_ids = field("group_ids").split(';')
+                .filter(|value| !value.trim().is_empty())
+                .map(|value| Uuid::parse_str(value.trim()).map_err(|_| format!("line {}: invalid group_ids", number + 1)))
+                .collect::<Result<Vec<Uuid>, String>>()?;
+            let configs = field("configs").split(';')
+                .filter(|value| !value.trim().is_empty())
+                .map(|value| parse_csv_config(value).ok_or(format!("line {}: invalid configs", number + 1)))
+                .collect::<Result<Vec<DeviceManifestConfig>, String>>()?;
+            rows.push(DeviceManifestRow {
+                id: uuid("id")?,
+                gateway_id: uuid("gateway_id")?,
+                type_id: uuid("type_id")?.ok_or(format!("line {}: missing type_id", number + 1))?,
+                serial_number: field("serial_number").to_owned(),
+                name: field("name").to_owned(),
+                description: field("description").to_owned(),
+                configs,
+                group_ids
+            });
+        }
+        Ok(Self { rows })
+    }
+
+    pub(crate) fn validate(&self, existing: &ProvisionExisting, scope: SerialScope) -> Vec<ProvisionReportSchema>
+    {
+        let ids: Vec<Uuid> = self.rows.iter().map(|row| row.id.unwrap_or_else(Uuid::new_v4)).collect();
+        // serial numbers collide within a type or within every type depending on the scope
+        let collide = |row: &DeviceManifestRow, serial_number: &str, type_id: Uuid| {
+            row.serial_number == serial_number && (scope == SerialScope::Global || row.type_id == type_id)
+        };
+        // a device without gateway is a gateway itself
+        let gateway_ids: Vec<Uuid> = self.rows.iter().zip(&ids).map(|(row, id)| row.gateway_id.unwrap_or(*id)).collect();
+        self.rows.iter().enumerate().map(|(index, row)| {
+            let id = ids[index];
+            let gateway_id = gateway_ids[index];
+            let invalid = if existing.device_ids.contains(&id) || ids.iter().filter(|&&other| other == id).count() > 1 {
+                Some("duplicate device id".to_owned())
+            } else if row.serial_number.is_empty() {
+                Some("empty serial number".to_owned())
+            } else if existing.serial_numbers.iter().any(|(serial_number, type_id)| collide(row, serial_number, *type_id))
+                || self.rows.iter().filter(|other| collide(row, &other.serial_number, other.type_id)).count() > 1 {
+                Some("duplicate serial number".to_owned())
+            } else if !existing.type_ids.contains(&row.type_id) {
+                Some("type doesn't exist".to_owned())
+            } else if gateway_id != id && !existing.gateway_ids.contains(&gateway_id)
+                && !ids.iter().zip(&gateway_ids).any(|(other, other_gateway)| *other == gateway_id && other_gateway == other) {
+                Some("gateway doesn't exist".to_owned())
+            } else {
+                // a gateway can only be a member of gateway groups and a device of device groups
+                let (group_ids, other_group_ids) = if gateway_id == id {
+                    (&existing.gateway_group_ids, &existing.group_ids)
+                } else {
+                    (&existing.group_ids, &existing.gateway_group_ids)
+                };
+                row.group_ids.iter()
+                    .find(|group_id| !group_ids.contains(group_id))
+                    .map(|group_id| match other_group_ids.contains(group_id) {
+                        true => format!("group {} has another kind", group_id),
+                        false => format!("group {} doesn't exist", group_id)
+                    })
+                    .or_else(|| row.check_configs(&existing.config_keys))
+            };
+            ProvisionReportSchema {
+                row: index,
+                id,
+                serial_number: row.serial_number.clone(),
+                status: invalid.map(ProvisionStatus::Invalid).unwrap_or(ProvisionStatus::Valid)
+            }
+        }).collect()
+    }
+}
+
+impl DeviceManifestRow {
+    // text values are parsed to the type of the declared config key, a value which doesn't parse is kept
+    // so validation reports the type mismatch, type of values of undeclared keys is inferred
+    fn coerce_configs(&mut self, config_keys: &[TypeConfigKeySchema])
+    {
+        for config in self.configs.iter_mut() {
+            let text = match &config.value {
+                DataValue::String(text) => text.clone(),
+                _ => continue
+            };
+            let key = config_keys.iter()
+                .find(|key| key.type_id == self.type_id && key.name == config.name && key.category == config.category);
+            config.value = match key {
+                Some(key) => DataValue::parse(&text, key.type_.clone()).unwrap_or(DataValue::String(text)),
+                None => infer_config_value(&text)
+            };
+        }
+    }
+
+    fn check_configs(&self, config_keys: &[TypeConfigKeySchema]) -> Option<String>
+    {
+        let keys: Vec<&TypeConfigKeySchema> = config_keys.iter().filter(|key| key.type_id == self.type_id).collect();
+        if keys.is_empty() {
+            return None;
+        }
+        self.configs.iter().find_map(|config| {
+            let violation = match keys.iter().find(|key| key.name == config.name && key.category == config.category) {
+                Some(key) => key.check(&config.value),
+                None => Some(ConfigViolation::Undeclared)
+            };
+            violation.map(|violation| format!("config {}:{} {:?}", config.category, config.name, violation))
+        })
+    }
+}
+
+fn split_csv_line(line: &str) -> Vec<String>
+{
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => { field.push('"'); chars.next(); },
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c)
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// value is kept as text until its type is known from the config keys of the device type
+fn parse_csv_config(value: &str) -> Option<DeviceManifestConfig>
+{
+    let (category, rest) = value.trim().split_once(':')?;
+    let (name, value) = rest.split_once('=')?;
+    let value = DataValue::String(value.trim().to_owned());
+    Some(DeviceManifestConfig { name: name.trim().to_owned(), value, category: category.trim().to_owned() })
+}
+
+fn infer_config_value(text: &str) -> DataValue
+{
+    if let Ok(v) = text.parse::<bool>() { DataValue::Bool(v) }
+    else if let Ok(v) = text.parse::<i32>() { DataValue::I32(v) }
+    else if let Ok(v) = text.parse::<i64>() { DataValue::I64(v) }
+    else if let Ok(v) = text.parse::<f64>() { DataValue::F64(v) }
+    else { DataValue::String(text.to_owned()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DataValue::{I32, F64, String};
+    use crate::schema::value::DataType;
+
+    #[test]
+    fn manifest_csv()
+    {
+        let type_id = Uuid::from_u128(1);
+        let csv = format!("serial_number,name,type_id,description,configs\n\
+            SN01,Sensor 1,{},\"near pump, north\",NETWORK:period=60;CONVERSION:coef_1=0.25;LABEL:site=plant a\n\
+            \n\
+            SN02,Sensor 2,{},,\n", type_id, type_id);
+        let manifest = DeviceManifest::from_csv(&csv).unwrap();
+        assert_eq!(manifest.rows.len(), 2);
+        assert_eq!(manifest.rows[0].description, "near pump, north");
+        assert_eq!(manifest.rows[0].configs.iter().map(|c| c.value.clone()).collect::<Vec<DataValue>>(),
+            [String("60".to_owned()), String("0.25".to_owned()), String("plant a".to_owned())]);
+        assert_eq!(manifest.rows[1].id, None);
+        assert!(DeviceManifest::from_csv("name,type_id\nSensor,xyz").is_err());
+    }
+
+    #[test]
+    fn manifest_coerce()
+    {
+        let type_id = Uuid::from_u128(1);
+        let csv = format!("type_id,serial_number,name,configs\n\
+            {},SN01,Sensor 1,NETWORK:period=60;CONVERSION:coef_1=1;LABEL:code=007;LABEL:rack=7\n", type_id);
+        let mut manifest = DeviceManifest::from_csv(&csv).unwrap();
+        let key = |name: &str, category: &str, type_: DataType| TypeConfigKeySchema {
+            type_id, name: name.to_owned(), category: category.to_owned(), type_, ..Default::default()
+        };
+        manifest.coerce_configs(&[key("period", "NETWORK", DataType::U16T), key("coef_1", "CONVERSION", DataType::F64T), key("code", "LABEL", DataType::StringT)]);
+        // declared keys take their declared type and undeclared key is inferred
+        assert_eq!(manifest.rows[0].configs.iter().map(|c| c.value.clone()).collect::<Vec<DataValue>>(),
+            [DataValue::U16(60), F64(1.0), String("007".to_owned()), I32(7)]);
+    }
+
+    #[test]
+    fn manifest_validate()
+    {
+        let type_id = Uuid::from_u128(1);
+        let gateway_id = Uuid::from_u128(10);
+        let row = |id: u128, gateway_id: Option<Uuid>, serial: &str| DeviceManifestRow {
+            id: Some(Uuid::from_u128(id)), gateway_id, type_id, serial_number: serial.to_owned(), ..Default::default()
+        };
+        let manifest = DeviceManifest { rows: vec![
+            row(11, Some(gateway_id), "SN01"),
+            row(12, None, "SN02"),
+            row(13, Some(Uuid::from_u128(12)), "SN03"),
+            row(14, Some(Uuid::from_u128(99)), "SN04"),
+            row(15, Some(gateway_id), "SN01"),
+            row(16, Some(gateway_id), "SN00"),
+            DeviceManifestRow { configs: vec![DeviceManifestConfig { name: "period".to_owned(), value: I32(0), category: "NETWORK".to_owned() }], ..row(17, Some(gateway_id), "SN05") }
+        ]};
+        let existing = ProvisionExisting {
+            type_ids: vec![type_id],
+            gateway_ids: vec![gateway_id],
+            serial_numbers: vec![("SN00".to_owned(), type_id)],
+            config_keys: vec![TypeConfigKeySchema { type_id, name: "period".to_owned(), category: "NETWORK".to_owned(), type_: DataType::I32T, min: Some(1.0), ..Default::default() }],
+            ..Default::default()
+        };
+        let status: Vec<ProvisionStatus> = manifest.validate(&existing, SerialScope::Global).into_iter().map(|report| report.status).collect();
+        assert_eq!(status, [
+            ProvisionStatus::Invalid("duplicate serial number".to_owned()),
+            ProvisionStatus::Valid,
+            ProvisionStatus::Valid,
+            ProvisionStatus::Invalid("gateway doesn't exist".to_owned()),
+            ProvisionStatus::Invalid("duplicate serial number".to_owned()),
+            ProvisionStatus::Invalid("duplicate serial number".to_owned()),
+            ProvisionStatus::Invalid("config NETWORK:period OutOfRange(0.0)".to_owned())
+        ]);
+
+        // per type scope allow a serial number of another type and group kind must match the row kind
+        let other_type_id = Uuid::from_u128(2);
+        let device_group_id = Uuid::from_u128(30);
+        let gateway_group_id = Uuid::from_u128(31);
+        let manifest = DeviceManifest { rows: vec![
+            DeviceManifestRow { type_id: other_type_id, ..row(21, Some(gateway_id), "SN00") },
+            DeviceManifestRow { group_ids: vec![device_group_id], ..row(22, Some(gateway_id), "SN06") },
+            DeviceManifestRow { group_ids: vec![gateway_group_id], ..row(23, Some(gateway_id), "SN07") },
+            DeviceManifestRow { group_ids: vec![gateway_group_id], ..row(24, None, "SN08") }
+        ]};
+        let existing = ProvisionExisting {
+            type_ids: vec![type_id, other_type_id],
+            gateway_ids: vec![gateway_id],
+            serial_numbers: vec![("SN00".to_owned(), type_id)],
+            group_ids: vec![device_group_id],
+            gateway_group_ids: vec![gateway_group_id],
+            ..Default::default()
+        };
+        let status: Vec<ProvisionStatus> = manifest.validate(&existing, SerialScope::Type).into_iter().map(|report| report.status).collect();
+        assert_eq!(status, [
+            ProvisionStatus::Valid,
+            ProvisionStatus::Valid,
+            ProvisionStatus::Invalid(format!("group {} has another kind", gateway_group_id)),
+            ProvisionStatus::Valid
+        ]);
+        let status = manifest.validate(&existing, SerialScope::Global).into_iter().next().map(|report| report.status);
+        assert_eq!(status, Some(ProvisionStatus::Invalid("duplicate serial number".to_owned())));
+    }
+
+}
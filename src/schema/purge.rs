@@ -0,0 +1,21 @@
+use uuid::Uuid;
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct PurgeReportSchema {
+    pub dry_run: bool,
+    pub archive: bool,
+    pub rows: Vec<(String, i64)>,
+    pub device_ids: Vec<Uuid>,
+    pub set_ids: Vec<Uuid>,
+    pub set_template_ids: Vec<Uuid>,
+    pub group_ids: Vec<Uuid>,
+    pub slice_ids: Vec<i32>,
+    pub derived_ids: Vec<Uuid>
+}
+
+impl PurgeReportSchema {
+    pub fn row_count(&self, table: &str) -> i64
+    {
+        self.rows.iter().filter(|(name, _)| name == table).map(|(_, count)| count).sum()
+    }
+}
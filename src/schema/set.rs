@@ -1,4 +1,5 @@
 use sea_query::Iden;
+use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use rmcs_resource_api::set;
 
@@ -56,7 +57,7 @@ pub struct SetMember {
     pub data_index: Vec<u8>
 }
 
-#[derive(Debug, Default, PartialEq, Clone)]
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SetTemplateSchema {
     pub id: Uuid,
     pub name: String,
@@ -64,13 +65,20 @@ pub struct SetTemplateSchema {
     pub members: Vec<SetTemplateMember>
 }
 
-#[derive(Debug, Default, PartialEq, Clone)]
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SetTemplateMember {
     pub type_id: Uuid,
     pub model_id: Uuid,
     pub data_index: Vec<u8>
 }
 
+// device bound to a set template slot, identified by its template index
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SetTemplateBinding {
+    pub template_index: usize,
+    pub device_id: Uuid
+}
+
 impl From<set::SetSchema> for SetSchema {
     fn from(value: set::SetSchema) -> Self {
         Self {
@@ -15,6 +15,17 @@ pub(crate) enum SliceData {
     Description
 }
 
+#[derive(Iden)]
+pub(crate) enum SliceDataArchive {
+    Table,
+    DeviceId,
+    ModelId,
+    TimestampBegin,
+    TimestampEnd,
+    Name,
+    Description
+}
+
 #[derive(Iden)]
 pub(crate) enum SliceDataSet {
     Table,
@@ -0,0 +1,74 @@
+use sea_query::Iden;
+use sqlx::types::chrono::{DateTime, Utc};
+use chrono::TimeDelta;
+use uuid::Uuid;
+
+#[derive(Iden)]
+pub(crate) enum DeviceStatus {
+    Table,
+    DeviceId,
+    LastData,
+    LastBuffer,
+    LastLog
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct DeviceStatusSchema {
+    pub device_id: Uuid,
+    pub last_data: Option<DateTime<Utc>>,
+    pub last_buffer: Option<DateTime<Utc>>,
+    pub last_log: Option<DateTime<Utc>>
+}
+
+impl DeviceStatusSchema {
+    // device config which define expected reporting period of a device in seconds
+    pub(crate) const PERIOD_NAME: &'static str = "period";
+    pub(crate) const PERIOD_CATEGORY: &'static str = "NETWORK";
+
+    pub fn last_seen(&self) -> Option<DateTime<Utc>>
+    {
+        [self.last_data, self.last_buffer, self.last_log].into_iter().flatten().max()
+    }
+
+    pub(crate) fn is_stale(&self, since: DateTime<Utc>, now: DateTime<Utc>, period: Option<f64>) -> bool
+    {
+        // a device is not stale while its expected period since last seen has not elapsed
+        // a period too large for a timestamp never elapses, float to integer cast saturates
+        let threshold = match period {
+            Some(period) if period > 0.0 => since.min(now
+                .checked_sub_signed(TimeDelta::microseconds((period * 1_000_000.0) as i64))
+                .unwrap_or(DateTime::<Utc>::MIN_UTC)
+            ),
+            _ => since
+        };
+        match self.last_seen() {
+            Some(last_seen) => last_seen < threshold,
+            None => true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_status_stale()
+    {
+        let time = |seconds: i64| DateTime::from_timestamp(seconds, 0).unwrap_or_default();
+        let status = DeviceStatusSchema { last_data: Some(time(100)), last_log: Some(time(150)), ..Default::default() };
+        assert_eq!(status.last_seen(), Some(time(150)));
+        assert!(!status.is_stale(time(120), time(200), None));
+        assert!(status.is_stale(time(180), time(200), None));
+        // expected period of 60 seconds has not elapsed since last seen
+        assert!(!status.is_stale(time(180), time(200), Some(60.0)));
+        assert!(status.is_stale(time(180), time(220), Some(60.0)));
+        assert!(DeviceStatusSchema::default().is_stale(time(0), time(200), Some(60.0)));
+        // large or invalid period doesn't overflow
+        assert!(!status.is_stale(time(180), time(200), Some(f64::MAX)));
+        assert!(!status.is_stale(time(180), time(200), Some(1e15)));
+        assert!(status.is_stale(time(180), time(200), Some(f64::NAN)));
+        assert!(status.is_stale(time(180), time(200), Some(-1e300)));
+    }
+
+}
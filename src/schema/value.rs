@@ -1,7 +1,8 @@
+use serde::{Serialize, Deserialize};
 use DataValue::{I8, I16, I32, I64, I128, U8, U16, U32, U64, U128, F32, F64, Bool, Char};
 use DataType::{I8T, I16T, I32T, I64T, I128T, U8T, U16T, U32T, U64T, U128T, F32T, F64T, BoolT, CharT, StringT, BytesT};
 
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DataType {
     #[default]
     NullT,
@@ -95,7 +96,7 @@ impl From<DataType> for u32 {
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DataValue {
     #[default]
     Null,
@@ -217,6 +218,41 @@ impl DataValue {
             _ => None
         }
     }
+    pub(crate) fn to_f64(&self) -> Option<f64> {
+        match *self {
+            I8(value) => Some(value as f64),
+            I16(value) => Some(value as f64),
+            I32(value) => Some(value as f64),
+            I64(value) => Some(value as f64),
+            I128(value) => Some(value as f64),
+            U8(value) => Some(value as f64),
+            U16(value) => Some(value as f64),
+            U32(value) => Some(value as f64),
+            U64(value) => Some(value as f64),
+            U128(value) => Some(value as f64),
+            F32(value) => Some(value as f64),
+            F64(value) => Some(value),
+            _ => None
+        }
+    }
+    pub(crate) fn from_f64(value: f64, type_: DataType) -> Self {
+        match type_ {
+            I8T => I8(value as i8),
+            I16T => I16(value as i16),
+            I32T => I32(value as i32),
+            I64T => I64(value as i64),
+            I128T => I128(value as i128),
+            U8T => U8(value as u8),
+            U16T => U16(value as u16),
+            U32T => U32(value as u32),
+            U64T => U64(value as u64),
+            U128T => U128(value as u128),
+            F32T => F32(value as f32),
+            F64T => F64(value),
+            BoolT => Bool(value != 0.0),
+            _ => Self::Null
+        }
+    }
     pub fn convert(self, type_: DataType) -> Option<Self> {
         let type_group = | t: DataType | -> u8 {
             match t {
@@ -246,6 +282,26 @@ impl DataValue {
             _ => Some(self)
         }
     }
+    pub(crate) fn parse(text: &str, type_: DataType) -> Option<Self> {
+        match type_ {
+            I8T => text.parse().ok().map(I8),
+            I16T => text.parse().ok().map(I16),
+            I32T => text.parse().ok().map(I32),
+            I64T => text.parse().ok().map(I64),
+            I128T => text.parse().ok().map(I128),
+            U8T => text.parse().ok().map(U8),
+            U16T => text.parse().ok().map(U16),
+            U32T => text.parse().ok().map(U32),
+            U64T => text.parse().ok().map(U64),
+            U128T => text.parse().ok().map(U128),
+            F32T => text.parse().ok().map(F32),
+            F64T => text.parse().ok().map(F64),
+            BoolT => text.parse().ok().map(Bool),
+            CharT => text.parse().ok().map(Char),
+            StringT => Some(DataValue::String(text.to_owned())),
+            _ => None
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -433,6 +489,10 @@ mod tests {
         let value: Vec<u8> = vec![101, 102, 103, 104, 105];
         let data = DataValue::from(value.clone());
         assert_eq!(value, TryInto::<Vec<u8>>::try_into(data).unwrap());
+
+        assert_eq!(DataValue::parse("007", StringT), Some(DataValue::String("007".to_owned())));
+        assert_eq!(DataValue::parse("1", F64T), Some(F64(1.0)));
+        assert_eq!(DataValue::parse("300", U8T), None);
     }
 
     #[test]
@@ -64,3 +64,5 @@ pub const FAIL_UPDATE: i16 = -9;
 pub const FAIL_DELETE: i16 = -10;
 pub const INVALID_TOKEN: i16 = -11;
 pub const INVALID_REQUEST: i16 = -12;
+pub const ERROR_THRESHOLD: i16 = -13;
+pub const ERROR_COMPATIBILITY: i16 = -14;
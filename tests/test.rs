@@ -7,7 +7,18 @@ mod tests {
     use uuid::Uuid;
     use rmcs_resource_db::{ModelConfigSchema, DeviceConfigSchema};
     use rmcs_resource_db::{Resource, DataType::*, DataValue::{*, self}};
-    use rmcs_resource_db::SetMember;
+    use rmcs_resource_db::{SetMember, SetTemplateBinding};
+    use rmcs_resource_db::{BundleSchema, BundleChange};
+    use rmcs_resource_db::AlarmKind;
+    use rmcs_resource_db::{ConfigSource, DeviceLifecycle, SerialScope};
+    use rmcs_resource_db::{DeviceManifest, ProvisionStatus};
+    use rmcs_resource_db::{LabelSelector, LabelRequirement};
+    use rmcs_resource_db::GroupMemberDiff;
+    use rmcs_resource_db::{CompatibilityMode, DeviceModelIncompatible};
+    use rmcs_resource_db::{TypeConfigKeySchema, ConfigViolation};
+    use rmcs_resource_db::GroupRuleSchema;
+    use rmcs_resource_db::{AggregatePeriod, AggregateFunction, GroupAggregate};
+    use rmcs_resource_db::DerivedSource;
     use rmcs_resource_db::tag;
 
     async fn get_connection_pool() -> Result<Pool<Postgres>, Error>
@@ -22,7 +33,7 @@ mod tests {
 
     async fn truncate_tables(pool: &Pool<Postgres>) -> Result<(), Error>
     {
-        let sql = "TRUNCATE TABLE \"system_log\", \"slice_data_set\", \"slice_data\", \"data_buffer\", \"data\", \"set_map\", \"set_template_map\", \"set\", \"set_template\", \"group_model_map\", \"group_device_map\", \"group_model\", \"group_device\", \"device_config\", \"device\", \"device_type_model\", \"device_type\", \"model_tag\", \"model_config\", \"model\";";
+        let sql = "TRUNCATE TABLE \"system_log\", \"device_label\", \"model_label\", \"set_label\", \"slice_data_set\", \"slice_data\", \"data_buffer\", \"data_archive\", \"data_buffer_archive\", \"slice_data_archive\", \"data\", \"set_map\", \"set_template_map\", \"set\", \"set_template\", \"group_model_rule\", \"group_device_rule\", \"group_model_child\", \"group_device_child\", \"group_model_map\", \"group_device_map\", \"group_model\", \"group_device\", \"alarm\", \"device_status\", \"device_location\", \"device_config_history\", \"device_config\", \"device\", \"device_type_config_key\", \"device_type_config\", \"device_type_model\", \"device_type\", \"model_derived_source\", \"model_derived\", \"model_tag\", \"model_config_history\", \"model_config\", \"model\";";
         sqlx::query(sql)
             .execute(pool)
             .await?;
@@ -35,7 +46,7 @@ mod tests {
         unsafe { std::env::set_var("RUST_BACKTRACE", "1"); }
 
         let pool = get_connection_pool().await.unwrap();
-        let resource = Resource::new_with_pool(pool);
+        let mut resource = Resource::new_with_pool(pool);
 
         // truncate all resource database tables before test
         truncate_tables(&resource.pool).await.unwrap();
@@ -69,13 +80,15 @@ mod tests {
         resource.create_device_config(device_id2, "coef_1", F64(0.2192), "CONVERSION").await.unwrap();
         let device_cfg_id = resource.create_device_config(device_id2, "period", I32(120), "NETWORK").await.unwrap();
 
+        // memberships are backfilled so data written before the members were added are addressed by the groups
+        let member_since = DateTime::<Utc>::default();
         // create new group and register newly created models as its member
         let group_model_id = resource.create_group_model(Uuid::new_v4(), "data", "APPLICATION", None).await.unwrap();
-        resource.add_group_model_member(group_model_id, model_id).await.unwrap();
+        resource.add_group_model_member_at(group_model_id, model_id, member_since).await.unwrap();
         // create new group and register newly created devices as its member
         let group_device_id = resource.create_group_device(Uuid::new_v4(), "sensor", "APPLICATION", None).await.unwrap();
-        resource.add_group_device_member(group_device_id, device_id1).await.unwrap();
-        resource.add_group_device_member(group_device_id, device_id2).await.unwrap();
+        resource.add_group_device_member_at(group_device_id, device_id1, member_since).await.unwrap();
+        resource.add_group_device_member_at(group_device_id, device_id2, member_since).await.unwrap();
 
         // read model
         let model = resource.read_model(model_id).await.unwrap();
@@ -95,6 +108,17 @@ mod tests {
         }
         assert_eq!(model_configs, config_vec);
 
+        // clone model with its configurations and type links
+        let model_clone_id = resource.clone_model(model_id, Uuid::new_v4(), None, Some("speed and direction clone"), None, true).await.unwrap();
+        let model_clone = resource.read_model(model_clone_id).await.unwrap();
+        assert_eq!(model_clone.name, "speed and direction clone");
+        assert_eq!(model_clone.data_type, [F32T,F32T]);
+        let clone_configs = resource.list_model_config_by_model(model_clone_id).await.unwrap();
+        assert_eq!(clone_configs.len(), model_configs.len());
+        let models = resource.list_model_by_type(type_id).await.unwrap();
+        assert!(models.iter().any(|m| m.id == model_clone_id));
+        resource.delete_model(model_clone_id).await.unwrap();
+
         // read device
         let device1 = resource.read_device(device_id1).await.unwrap();
         let devices = resource.list_device_by_gateway(gateway_id).await.unwrap();
@@ -109,6 +133,16 @@ mod tests {
         // read device configurations
         let device_configs = resource.list_device_config_by_device(device_id1).await.unwrap();
         assert_eq!(device1.configs, device_configs);
+        // read effective configurations with type default overridden by device configuration
+        let type_cfg_id = resource.create_type_config(type_id, "period", I32(30), "NETWORK").await.unwrap();
+        resource.create_type_config(type_id, "retry", I32(3), "NETWORK").await.unwrap();
+        let effective_configs = resource.read_device_effective_config(device_id1).await.unwrap();
+        let period = effective_configs.iter().find(|cfg| cfg.name == "period").unwrap();
+        assert_eq!((period.value.clone(), period.source), (I32(60), ConfigSource::Device));
+        let retry = effective_configs.iter().find(|cfg| cfg.name == "retry").unwrap();
+        assert_eq!((retry.value.clone(), retry.source), (I32(3), ConfigSource::Type));
+        resource.delete_type_config(type_cfg_id).await.unwrap();
+        assert_eq!(resource.list_type_config_by_type(type_id).await.unwrap().len(), 1);
 
         // read group model
         let groups = resource.list_group_model_by_category("APPLICATION").await.unwrap();
@@ -128,9 +162,16 @@ mod tests {
         assert_eq!(model.name, "buffer 2 integer");
         assert_eq!(model.data_type, [I32T,I32T]);
         // update model configurations
+        let timestamp_update = Utc::now();
         resource.update_model_config(model_cfg_id, None, Some(I32(238)), None).await.unwrap();
         let config = resource.read_model_config(model_cfg_id).await.unwrap();
         assert_eq!(config.value, I32(238));
+        // read model configuration history before and after update
+        let configs_before = resource.read_model_config_at(model_id, timestamp_update).await.unwrap();
+        assert_eq!(configs_before.iter().find(|cfg| cfg.id == model_cfg_id).unwrap().value, I32(250));
+        let configs_after = resource.read_model_config_at(model_id, Utc::now()).await.unwrap();
+        assert_eq!(configs_after.iter().find(|cfg| cfg.id == model_cfg_id).unwrap().value, I32(238));
+        assert_eq!(resource.list_model_config_history(model_id).await.unwrap().iter().filter(|cfg| cfg.id == model_cfg_id).count(), 2);
 
         // update type
         resource.update_type(type_id, None, Some("Speedometer and compass sensor")).await.unwrap();
@@ -142,9 +183,12 @@ mod tests {
         let device2 = resource.read_device(device_id2).await.unwrap();
         assert_eq!(device2.description, "E-bike speedometer and compass sensor 2");
         // update device config
+        let timestamp_update = Utc::now();
         resource.update_device_config(device_cfg_id, None, Some(I32(60)), None).await.unwrap();
         let config = resource.read_device_config(device_cfg_id).await.unwrap();
         assert_eq!(config.value, I32(60));
+        let configs_before = resource.read_device_config_at(device_id2, timestamp_update).await.unwrap();
+        assert_eq!(configs_before.iter().find(|cfg| cfg.id == device_cfg_id).unwrap().value, I32(120));
 
         // update group model
         resource.update_group_model(group_model_id, None, None, Some("Data models")).await.unwrap();
@@ -176,6 +220,52 @@ mod tests {
         assert_eq!(set.members[0], SetMember { device_id: device_id1, model_id, data_index: vec![1] });
         assert_eq!(set.members[1], SetMember { device_id: device_id2, model_id, data_index: vec![1] });
 
+        // create set from set template with explicit and automatic device binding
+        let binding = |template_index: usize, device_id: Uuid| SetTemplateBinding { template_index, device_id };
+        let set_bound_id = resource.create_set_from_template(Uuid::new_v4(), template_id, "multiple compass 2", None, &[binding(0, device_id1)], None).await.unwrap();
+        let set = resource.read_set(set_bound_id).await.unwrap();
+        assert_eq!(set.members, [SetMember { device_id: device_id1, model_id, data_index: vec![1] }]);
+        resource.delete_set(set_bound_id).await.unwrap();
+        let gateway_bind_id = resource.create_gateway(Uuid::new_v4(), type_id, "GATEWAY_BIND", "Binding gateway", None).await.unwrap();
+        let set_bound_id = resource.create_set_from_template(Uuid::new_v4(), template_id, "multiple compass 3", None, &[], Some(gateway_bind_id)).await.unwrap();
+        assert_eq!(resource.read_set(set_bound_id).await.unwrap().members[0].device_id, gateway_bind_id);
+        resource.delete_set(set_bound_id).await.unwrap();
+        let type_other_id = resource.create_type(Uuid::new_v4(), "Display", None).await.unwrap();
+        let device_other_id = resource.create_device(Uuid::new_v4(), gateway_bind_id, type_other_id, "DISPLAY01", "Display", None).await.unwrap();
+        let result = resource.create_set_from_template(Uuid::new_v4(), template_id, "mismatch", None, &[binding(0, device_other_id)], None).await;
+        assert!(matches!(result, Err(Error::InvalidArgument(_))));
+        resource.delete_device(device_other_id).await.unwrap();
+        resource.delete_gateway(gateway_bind_id).await.unwrap();
+        resource.delete_type(type_other_id).await.unwrap();
+        assert!(resource.create_set_from_template(Uuid::new_v4(), template_id, "invalid", None, &[binding(1, device_id1)], None).await.is_err());
+        // gateway has two devices of the template type so the slot can't be bound automatically
+        assert!(resource.create_set_from_template(Uuid::new_v4(), template_id, "ambiguous", None, &[], Some(gateway_id)).await.is_err());
+        assert_eq!(resource.list_set_by_template(template_id).await.unwrap().len(), 1);
+
+        // export metadata bundle then import it back without any change
+        let bundle = resource.export_bundle(&[], &[type_id], &[template_id], &[group_model_id]).await.unwrap();
+        let bundle = BundleSchema::from_json(&bundle.to_json().unwrap()).unwrap();
+        assert!(bundle.models.iter().any(|m| m.id == model_id));
+        let diffs = resource.import_bundle(&bundle).await.unwrap();
+        assert!(diffs.iter().all(|d| d.change == BundleChange::Unchanged));
+        // import a changed bundle with a new model and an updated model description
+        let mut bundle_changed = bundle.clone();
+        let mut model_bundle = bundle_changed.models.iter().find(|m| m.id == model_id).unwrap().clone();
+        model_bundle.id = Uuid::new_v4();
+        model_bundle.name = "speed and direction copy".to_owned();
+        bundle_changed.models.push(model_bundle.clone());
+        bundle_changed.models.iter_mut().find(|m| m.id == model_id).unwrap().description = "Imported description".to_owned();
+        let diffs = resource.import_bundle(&bundle_changed).await.unwrap();
+        assert_eq!(diffs.iter().find(|d| d.id == model_bundle.id).unwrap().change, BundleChange::Created);
+        assert_eq!(diffs.iter().find(|d| d.id == model_id).unwrap().change, BundleChange::Updated);
+        assert_eq!(diffs.iter().filter(|d| d.change == BundleChange::Unchanged).count(), diffs.len() - 2);
+        assert_eq!(resource.read_model(model_bundle.id).await.unwrap().configs.iter().flatten().count(), model_bundle.configs.iter().flatten().count());
+        assert_eq!(resource.read_model(model_id).await.unwrap().description, "Imported description");
+        // import the original bundle back to restore the description
+        let diffs = resource.import_bundle(&bundle).await.unwrap();
+        assert_eq!(diffs.iter().find(|d| d.id == model_id).unwrap().change, BundleChange::Updated);
+        resource.delete_model(model_bundle.id).await.unwrap();
+
         // generate raw data and create buffers
         let timestamp_1 = DateTime::parse_from_str("2023-05-07 07:08:48.123456 +0000", "%Y-%m-%d %H:%M:%S.%6f %z").unwrap().into();
         let timestamp_2 = DateTime::parse_from_str("2025-06-11 14:49:36.123456 +0000", "%Y-%m-%d %H:%M:%S.%6f %z").unwrap().into();
@@ -196,6 +286,18 @@ mod tests {
         assert_eq!(buffers_group[0].data, raw_1);
         assert_eq!(buffers_group[1].data, raw_2);
 
+        // read buffers addressed by device group and model group
+        resource.add_group_model_member_at(group_model_id, model_buf_id, member_since).await.unwrap();
+        let buffers_group = resource.list_buffer_by_group_first(100, group_device_id, group_model_id, None).await.unwrap();
+        assert_eq!(buffers_group.len(), 4);
+        let buffers_group = resource.list_buffer_by_group_time(group_device_id, group_model_id, timestamp_1, Some(tag::ANALYSIS_1)).await.unwrap();
+        assert_eq!(buffers_group.len(), 2);
+        assert!(buffers_group.iter().any(|b| b.data == raw_1) && buffers_group.iter().any(|b| b.data == raw_2));
+        resource.remove_group_model_member(group_model_id, model_buf_id).await.unwrap();
+        // removed member keeps its closed period so buffers written while it was a member are still addressed
+        assert_eq!(resource.list_buffer_by_group_first(100, group_device_id, group_model_id, None).await.unwrap().len(), 4);
+        assert!(resource.read_group_model(group_model_id).await.unwrap().model_ids.iter().all(|id| *id != model_buf_id));
+
         // get model config value then convert buffer data
         let conf_val = |model_configs: &[DeviceConfigSchema], name: &str| -> DataValue {
             model_configs.iter().filter(|&cfg| cfg.name == name.to_owned())
@@ -214,6 +316,21 @@ mod tests {
         resource.create_data(device_id1, model_id, timestamp_1, &[F32(speed1), F32(direction1)], None).await.unwrap();
         resource.create_data(device_id2, model_id, timestamp_1, &[F32(speed2), F32(direction2)], None).await.unwrap();
         resource.create_data_multiple(&[device_id1, device_id2], &[model_id, model_id], &[timestamp_2, timestamp_2], &[&[F32(speed1), F32(direction1)], &[F32(speed2), F32(direction2)]], None).await.unwrap();
+        // model data type can't be changed by a bundle import once the model has data
+        let mut bundle_changed = bundle.clone();
+        bundle_changed.models.iter_mut().find(|m| m.id == model_id).unwrap().data_type = vec![F64T, F64T];
+        assert!(matches!(resource.import_bundle(&bundle_changed).await, Err(Error::InvalidArgument(_))));
+        assert_eq!(resource.read_model(model_id).await.unwrap().data_type, [F32T, F32T]);
+
+        // read device status updated by data and buffer insertion
+        let status = resource.read_device_status(device_id1).await.unwrap();
+        assert_eq!((status.last_data, status.last_buffer, status.last_log), (Some(timestamp_2), Some(timestamp_2), None));
+        assert_eq!(status.last_seen(), Some(timestamp_2));
+        // devices are stale when not seen since now even with their expected period
+        let stale = resource.list_device_stale(timestamp_1).await.unwrap();
+        assert!(stale.iter().all(|status| status.device_id != device_id1 && status.device_id != device_id2));
+        let stale = resource.list_device_stale(Utc::now()).await.unwrap();
+        assert!(stale.iter().any(|status| status.device_id == device_id1));
 
         // read data
         let datas = resource.list_data_by_number_before(device_id1, model_id, timestamp_1, 100, None).await.unwrap();
@@ -222,6 +339,70 @@ mod tests {
         assert_eq!(timestamp_1, data.timestamp);
         assert_eq!(tag::DEFAULT, data.tag);
 
+        // read calibrated data using linear calibration of first field with CONVERSION device configs
+        let calibration_cfg_id = resource.create_model_config(model_id, 0, "linear", String("CONVERSION".to_owned()), "CALIBRATION").await.unwrap();
+        let timestamp_calibrated = Utc::now();
+        resource.create_data(device_id1, model_id, timestamp_calibrated, &[F32(speed1), F32(direction1)], None).await.unwrap();
+        let data_calibrated = resource.list_data_by_number_before_calibrated(device_id1, model_id, timestamp_calibrated, 1, None).await.unwrap();
+        assert_eq!(data_calibrated[0].raw, vec![F32(speed1), F32(direction1)]);
+        assert_eq!(data_calibrated[0].data, vec![F64((speed1 as f64 - coef0 as f64) * coef1), F32(direction1)]);
+        // data recorded before the calibration config was created keep raw value
+        let data_calibrated = resource.list_data_by_number_before_calibrated(device_id1, model_id, timestamp_1, 1, None).await.unwrap();
+        assert_eq!(data_calibrated[0].data, vec![F32(speed1), F32(direction1)]);
+        resource.delete_data(device_id1, model_id, timestamp_calibrated, None).await.unwrap();
+        resource.delete_model_config(calibration_cfg_id).await.unwrap();
+
+        // evaluate upper threshold of first field on data ingest then clear the alarm inside deadband
+        resource.set_alarm(true);
+        // thresholds apply to data recorded after their config was created
+        let timestamp_alarm = Utc::now();
+        resource.create_data(device_id1, model_id, timestamp_alarm, &[F32(300.0), F32(direction1)], None).await.unwrap();
+        let alarms = resource.list_alarm_active_by_device(device_id1).await.unwrap();
+        assert_eq!(alarms.len(), 1);
+        assert_eq!((alarms[0].index, alarms[0].kind, alarms[0].value), (0, AlarmKind::Upper, 300.0));
+        let logs = resource.list_log_by_time(timestamp_alarm, Some(device_id1), Some(model_id), Some(tag::ERROR_THRESHOLD)).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        resource.delete_data(device_id1, model_id, timestamp_alarm, None).await.unwrap();
+        resource.create_data(device_id1, model_id, timestamp_alarm, &[F32(100.0), F32(direction1)], None).await.unwrap();
+        assert!(resource.list_alarm_active_by_device(device_id1).await.unwrap().is_empty());
+        resource.delete_data(device_id1, model_id, timestamp_alarm, None).await.unwrap();
+        resource.delete_log_by_time(timestamp_alarm, Some(device_id1), Some(model_id), Some(tag::ERROR_THRESHOLD)).await.unwrap();
+        resource.set_alarm(false);
+
+        // reject or log data of a model which is not linked to the device type
+        let model_unlinked_id = resource.create_model(Uuid::new_v4(), &[F32T], "UPLINK", "unlinked", None).await.unwrap();
+        resource.set_compatibility(CompatibilityMode::Strict);
+        let result = resource.create_data(device_id1, model_unlinked_id, timestamp_alarm, &[F32(1.0)], None).await;
+        let error = result.unwrap_err();
+        let incompatible = DeviceModelIncompatible::try_from(&error).unwrap();
+        assert_eq!((incompatible.device_id, incompatible.model_id), (device_id1, model_unlinked_id));
+        assert!(resource.create_buffer(device_id1, model_unlinked_id, timestamp_alarm, &[F32(1.0)], None).await.is_err());
+        assert!(resource.add_set_member(set_id, device_id1, model_unlinked_id, &[0]).await.is_err());
+        assert!(resource.create_slice(device_id1, model_unlinked_id, timestamp_1, timestamp_2, "unlinked", None).await.is_err());
+        let template_unlinked_id = resource.create_set_template(Uuid::new_v4(), "unlinked", None).await.unwrap();
+        resource.add_set_template_member(template_unlinked_id, type_id, model_unlinked_id, &[0]).await.unwrap();
+        assert!(resource.create_set_from_template(Uuid::new_v4(), template_unlinked_id, "unlinked", None, &[binding(0, device_id1)], None).await.is_err());
+        resource.delete_set_template(template_unlinked_id).await.unwrap();
+        resource.set_compatibility(CompatibilityMode::Disabled);
+        let slice_unlinked_id = resource.create_slice(device_id1, model_unlinked_id, timestamp_1, timestamp_2, "unlinked", None).await.unwrap();
+        resource.set_compatibility(CompatibilityMode::Strict);
+        assert!(resource.update_slice(slice_unlinked_id, None, None, Some("unlinked slice"), None).await.is_err());
+        resource.delete_slice(slice_unlinked_id).await.unwrap();
+        resource.create_data(device_id1, model_id, timestamp_alarm, &[F32(100.0), F32(direction1)], None).await.unwrap();
+        resource.delete_data(device_id1, model_id, timestamp_alarm, None).await.unwrap();
+        resource.set_compatibility(CompatibilityMode::Warn);
+        resource.create_data(device_id1, model_unlinked_id, timestamp_alarm, &[F32(1.0)], None).await.unwrap();
+        let logs = resource.list_log_first(10, Some(device_id1), Some(model_unlinked_id), Some(tag::ERROR_COMPATIBILITY)).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        // failed write in warn mode leave no warning
+        assert!(resource.create_data(device_id1, model_unlinked_id, timestamp_alarm, &[F32(1.0)], None).await.is_err());
+        let logs = resource.list_log_first(10, Some(device_id1), Some(model_unlinked_id), Some(tag::ERROR_COMPATIBILITY)).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        resource.delete_log(logs[0].id).await.unwrap();
+        resource.delete_data(device_id1, model_unlinked_id, timestamp_alarm, None).await.unwrap();
+        resource.delete_model(model_unlinked_id).await.unwrap();
+        resource.set_compatibility(CompatibilityMode::Disabled);
+
         // read data from a device group
         let data_group = resource.list_data_group_by_time(&group_device.device_ids, &[model_id], timestamp_1, None).await.unwrap();
         let data_values_vec: Vec<Vec<DataValue>> = data_group.iter().map(|d| d.data.clone()).collect();
@@ -229,6 +410,137 @@ mod tests {
         assert!(data_values.contains(&F32(speed1)));
         assert!(data_values.contains(&F32(speed2)));
 
+        // read data addressed by device group and model group
+        let data_group = resource.list_data_by_group_time(group_device_id, group_model_id, timestamp_1, None).await.unwrap();
+        assert_eq!(data_group.len(), 2);
+        let data_group = resource.list_data_by_group_range(group_device_id, group_model_id, timestamp_1, timestamp_2, None).await.unwrap();
+        assert_eq!(resource.count_data_by_group_range(group_device_id, group_model_id, timestamp_1, timestamp_2, None).await.unwrap(), data_group.len());
+        assert_eq!(resource.count_data_by_group(group_device_id, group_model_id, None).await.unwrap(), data_group.len());
+        let timestamps = resource.list_data_timestamp_by_group_range(group_device_id, group_model_id, timestamp_1, timestamp_2, None).await.unwrap();
+        assert_eq!(timestamps, [timestamp_1, timestamp_2]);
+        assert_eq!(resource.read_data_timestamp_by_group(group_device_id, group_model_id, timestamp_2, None).await.unwrap(), timestamp_2);
+        assert_eq!(resource.list_data_by_group_number_before(group_device_id, group_model_id, timestamp_2, 1, None).await.unwrap().len(), 1);
+
+        // aggregate data of a device group into daily group series of an output device
+        let fleet_id = resource.create_device(Uuid::new_v4(), gateway_id, type_id, "FLEET01", "Fleet total", None).await.unwrap();
+        // second sample of a device in the same day is averaged before the devices are combined
+        let timestamp_sample: DateTime<Utc> = DateTime::parse_from_rfc3339("2023-05-07T08:08:48Z").unwrap().into();
+        let (speed_sample, direction_sample) = (speed1 + 10.0, direction1 + 10.0);
+        resource.create_data(device_id1, model_id, timestamp_sample, &[F32(speed_sample), F32(direction_sample)], None).await.unwrap();
+        let mut aggregate = GroupAggregate {
+            group_device_id, model_id, output_device_id: fleet_id, period: AggregatePeriod::Daily,
+            functions: vec![AggregateFunction::Sum]
+        };
+        assert!(resource.aggregate_group_data(&aggregate, timestamp_1, timestamp_2).await.is_err());
+        aggregate.functions.push(AggregateFunction::Average);
+        let number = resource.aggregate_group_data(&aggregate, timestamp_1, timestamp_2).await.unwrap();
+        assert_eq!(number, 2);
+        assert_eq!(resource.aggregate_group_data(&aggregate, timestamp_1, timestamp_2).await.unwrap(), 2);
+        let day_1 = AggregatePeriod::Daily.truncate(timestamp_1);
+        let fleet = resource.list_data_by_range(fleet_id, model_id, day_1, timestamp_2, Some(tag::GROUP_DAILY)).await.unwrap();
+        assert_eq!(fleet.len(), 2);
+        assert_eq!((fleet[0].timestamp, fleet[0].tag), (day_1, tag::GROUP_DAILY));
+        let speed_device1 = (speed1 as f64 + speed_sample as f64) / 2.0;
+        let direction_device1 = (direction1 as f64 + direction_sample as f64) / 2.0;
+        assert_eq!(fleet[0].data, [F32((speed_device1 + speed2 as f64) as f32), F32(((direction_device1 + direction2 as f64) / 2.0) as f32)]);
+        // range covering part of a day is extended to the whole day
+        assert_eq!(resource.aggregate_group_data(&aggregate, timestamp_sample, timestamp_sample).await.unwrap(), 1);
+        assert_eq!(resource.list_data_by_range(fleet_id, model_id, day_1, timestamp_2, Some(tag::GROUP_DAILY)).await.unwrap(), fleet);
+        resource.delete_data(device_id1, model_id, timestamp_sample, None).await.unwrap();
+        for data in &fleet {
+            resource.delete_data(fleet_id, model_id, data.timestamp, Some(tag::GROUP_DAILY)).await.unwrap();
+        }
+        resource.delete_device(fleet_id).await.unwrap();
+
+        // move a device to another group and read data with the membership valid at each timestamp
+        let moved: DateTime<Utc> = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap().into();
+        let group_moved_id = resource.create_group_device(Uuid::new_v4(), "moved", "APPLICATION", None).await.unwrap();
+        resource.remove_group_device_member_at(group_device_id, device_id2, moved).await.unwrap();
+        resource.add_group_device_member_at(group_moved_id, device_id2, moved).await.unwrap();
+        assert_eq!(resource.read_group_device_at(group_device_id, timestamp_1).await.unwrap().device_ids.len(), 2);
+        assert_eq!(resource.read_group_device_at(group_device_id, timestamp_2).await.unwrap().device_ids, [device_id1]);
+        assert!(resource.read_group_device_at(group_moved_id, timestamp_1).await.unwrap().device_ids.is_empty());
+        assert_eq!(resource.read_group_device_at(group_moved_id, timestamp_2).await.unwrap().device_ids, [device_id2]);
+        assert_eq!(resource.read_group_device(group_device_id).await.unwrap().device_ids, [device_id1]);
+        assert!(resource.read_group_device_at(Uuid::new_v4(), timestamp_1).await.is_err());
+        assert_eq!(resource.list_data_by_group_time(group_device_id, group_model_id, timestamp_1, None).await.unwrap().len(), 2);
+        assert!(resource.list_data_by_group_time(group_moved_id, group_model_id, timestamp_1, None).await.unwrap().is_empty());
+        resource.delete_group_device(group_moved_id).await.unwrap();
+        resource.add_group_device_member_at(group_device_id, device_id2, moved).await.unwrap();
+
+        // member added without timestamp starts its period now, so data written before it was added
+        // are only addressed by the group when the membership is backfilled with a timestamp
+        let group_late_id = resource.create_group_device(Uuid::new_v4(), "late", "APPLICATION", None).await.unwrap();
+        resource.add_group_device_member(group_late_id, device_id1).await.unwrap();
+        assert!(resource.list_data_by_group_time(group_late_id, group_model_id, timestamp_1, None).await.unwrap().is_empty());
+        assert_eq!(resource.read_group_device(group_late_id).await.unwrap().device_ids, [device_id1]);
+        // member removed without timestamp has its period closed now and the period is kept,
+        // so a backfill which overlaps the closed period is rejected
+        resource.remove_group_device_member(group_late_id, device_id1).await.unwrap();
+        assert!(resource.read_group_device(group_late_id).await.unwrap().device_ids.is_empty());
+        assert!(resource.add_group_device_member_at(group_late_id, device_id1, timestamp_1).await.is_err());
+        resource.add_group_device_member_at(group_late_id, device_id2, timestamp_1).await.unwrap();
+        assert_eq!(resource.list_data_by_group_time(group_late_id, group_model_id, timestamp_1, None).await.unwrap().len(), 1);
+        // periods of a member can not overlap and a period can not end before it starts
+        assert!(resource.remove_group_device_member_at(group_late_id, device_id2, member_since).await.is_err());
+        resource.remove_group_device_member_at(group_late_id, device_id2, timestamp_2).await.unwrap();
+        assert_eq!(resource.read_group_device_at(group_late_id, timestamp_1).await.unwrap().device_ids, [device_id2]);
+        assert!(resource.read_group_device_at(group_late_id, timestamp_2).await.unwrap().device_ids.is_empty());
+        assert!(resource.add_group_device_member_at(group_late_id, device_id2, timestamp_1).await.is_err());
+        resource.add_group_device_member(group_late_id, device_id2).await.unwrap();
+        assert_eq!(resource.read_group_device(group_late_id).await.unwrap().device_ids, [device_id2]);
+        resource.delete_group_device(group_late_id).await.unwrap();
+
+        // replace group members with a single call
+        let group_sync_id = resource.create_group_device(Uuid::new_v4(), "sync", "APPLICATION", None).await.unwrap();
+        let diff = resource.set_group_device_members(group_sync_id, &[device_id1, device_id2]).await.unwrap();
+        let mut added = vec![device_id1, device_id2];
+        added.sort();
+        assert_eq!((diff.added, diff.removed), (added, vec![]));
+        let diff = resource.set_group_device_members(group_sync_id, &[device_id2, device_id2]).await.unwrap();
+        assert_eq!((diff.added, diff.removed), (vec![], vec![device_id1]));
+        assert_eq!(resource.set_group_device_members(group_sync_id, &[device_id2]).await.unwrap(), GroupMemberDiff::default());
+        assert_eq!(resource.read_group_device(group_sync_id).await.unwrap().device_ids, [device_id2]);
+        // synced members start now and removed members keep their closed period
+        assert!(resource.list_data_by_group_time(group_sync_id, group_model_id, timestamp_1, None).await.unwrap().is_empty());
+        assert!(resource.add_group_device_member_at(group_sync_id, device_id1, timestamp_1).await.is_err());
+        let diff = resource.set_group_device_members(group_sync_id, &[device_id1, device_id2]).await.unwrap();
+        assert_eq!((diff.added, diff.removed), (vec![device_id1], vec![]));
+        assert_eq!(resource.set_group_model_members(group_model_id, &[model_id]).await.unwrap(), GroupMemberDiff::default());
+        assert!(resource.set_group_gateway_members(Uuid::new_v4(), &[gateway_id]).await.is_err());
+        resource.delete_group_device(group_sync_id).await.unwrap();
+
+        // read data from a nested device group subtree
+        let group_building_id = resource.create_group_device(Uuid::new_v4(), "building", "LOCATION", None).await.unwrap();
+        let group_floor_id = resource.create_group_device(Uuid::new_v4(), "floor", "LOCATION", None).await.unwrap();
+        resource.add_group_device_member(group_building_id, device_id1).await.unwrap();
+        resource.add_group_device_member(group_floor_id, device_id2).await.unwrap();
+        // every group of a multi group read is returned with its own members
+        let groups = resource.list_group_device_by_category("LOCATION").await.unwrap();
+        let members: Vec<(Uuid, Vec<Uuid>)> = groups.into_iter().map(|group| (group.id, group.device_ids)).collect();
+        let mut expected = vec![(group_building_id, vec![device_id1]), (group_floor_id, vec![device_id2])];
+        expected.sort();
+        assert_eq!(members, expected);
+        resource.add_group_device_child(group_building_id, group_floor_id).await.unwrap();
+        assert!(resource.add_group_device_child(group_floor_id, group_building_id).await.is_err());
+        assert!(resource.add_group_device_child(group_floor_id, group_floor_id).await.is_err());
+        let children = resource.list_group_device_children(group_building_id).await.unwrap();
+        assert_eq!(children.iter().map(|g| g.id).collect::<Vec<Uuid>>(), [group_floor_id]);
+        let group_expanded = resource.read_group_device_expanded(group_building_id).await.unwrap();
+        assert_eq!(group_expanded.device_ids.len(), 2);
+        let data_group = resource.list_data_group_by_time(&group_expanded.device_ids, &[model_id], timestamp_1, None).await.unwrap();
+        assert_eq!(data_group.len(), 2);
+        // group addressed data of a parent group include members of its child groups
+        let group_site_id = resource.create_group_device(Uuid::new_v4(), "site", "LOCATION", None).await.unwrap();
+        resource.add_group_device_child(group_site_id, group_device_id).await.unwrap();
+        assert_eq!(resource.list_data_by_group_time(group_site_id, group_model_id, timestamp_1, None).await.unwrap().len(), 2);
+        assert_eq!(resource.count_data_by_group(group_site_id, group_model_id, None).await.unwrap(), resource.count_data_by_group(group_device_id, group_model_id, None).await.unwrap());
+        resource.delete_group_device(group_site_id).await.unwrap();
+        resource.remove_group_device_child(group_building_id, group_floor_id).await.unwrap();
+        assert_eq!(resource.read_group_device_expanded(group_building_id).await.unwrap().device_ids, [device_id1]);
+        resource.delete_group_device(group_floor_id).await.unwrap();
+        resource.delete_group_device(group_building_id).await.unwrap();
+
         // read data set
         let data_set = resource.read_data_set(set_id, timestamp_1, None).await.unwrap();
         assert_eq!(data_set.data[0], F32(direction1));
@@ -304,6 +616,274 @@ mod tests {
         let configs = resource.list_model_config_by_model(model_id).await.unwrap();
         assert_eq!(configs.len(), 0);
 
+        // create multi level topology: site controller -> sub gateway -> device
+        let site_id = Uuid::new_v4();
+        let sub_id = Uuid::new_v4();
+        resource.create_device(site_id, site_id, type_id, "SITE01", "Site controller", None).await.unwrap();
+        resource.create_device(sub_id, site_id, type_id, "SUB01", "Sub gateway", None).await.unwrap();
+        resource.update_device_parent(device_id1, Some(sub_id)).await.unwrap();
+        // read device tree
+        let tree = resource.list_device_tree(site_id, None).await.unwrap();
+        assert_eq!(tree.device.parent_id, None);
+        assert_eq!(tree.children[0].device.id, sub_id);
+        assert_eq!(tree.children[0].children[0].device.id, device_id1);
+        assert_eq!(tree.children[0].children[0].depth, 2);
+        let tree = resource.list_device_tree(site_id, Some(1)).await.unwrap();
+        assert_eq!(tree.children[0].children.len(), 0);
+        let devices = resource.list_device_by_gateway_descendant(site_id).await.unwrap();
+        assert_eq!(devices.len(), 3);
+        // parent update which create a cycle is rejected
+        let result = resource.update_device_parent(site_id, Some(device_id1)).await;
+        assert!(result.is_err());
+        // gateway change keeps a parent which was set explicitly and moves a parent which follow the gateway
+        resource.update_device(device_id1, Some(site_id), None, None, None, None).await.unwrap();
+        assert_eq!(resource.read_device(device_id1).await.unwrap().parent_id, Some(sub_id));
+        // gateway change which moves the parent to a descendant is rejected
+        assert!(resource.update_device(sub_id, Some(device_id1), None, None, None, None).await.is_err());
+        resource.update_device(device_id1, Some(gateway_id), None, None, None, None).await.unwrap();
+        let site_next_id = resource.create_gateway(Uuid::new_v4(), type_id, "SITE02", "Site controller", None).await.unwrap();
+        resource.update_device(sub_id, Some(site_next_id), None, None, None, None).await.unwrap();
+        assert_eq!(resource.read_device(sub_id).await.unwrap().parent_id, Some(site_next_id));
+        // deleted parent leaves its children as root devices
+        resource.delete_device(site_next_id).await.unwrap();
+        assert_eq!(resource.read_device(sub_id).await.unwrap().parent_id, None);
+        assert_eq!(resource.read_device(device_id1).await.unwrap().parent_id, Some(sub_id));
+        resource.delete_device(sub_id).await.unwrap();
+        assert_eq!(resource.read_device(device_id1).await.unwrap().parent_id, None);
+        resource.delete_device(site_id).await.unwrap();
+
+        // provision devices from a manifest, nothing is written when a row is invalid
+        let gateway_prov_id = Uuid::new_v4();
+        let csv = format!("id,gateway_id,type_id,serial_number,name,group_ids,configs\n\
+            {gateway_prov_id},,{type_id},PROV01,Provisioned gateway,,\n\
+            ,{gateway_prov_id},{type_id},PROV02,Provisioned sensor,{group_device_id},NETWORK:period=30\n\
+            ,{gateway_prov_id},{type_id},TEST01,Duplicate sensor,,\n");
+        let manifest = DeviceManifest::from_csv(&csv).unwrap();
+        let reports = resource.provision_devices(&manifest).await.unwrap();
+        assert_eq!(reports[2].status, ProvisionStatus::Invalid("duplicate serial number".to_owned()));
+        assert!(resource.read_device(gateway_prov_id).await.is_err());
+        let manifest = DeviceManifest { rows: manifest.rows[..2].to_vec() };
+        let reports = resource.provision_devices(&manifest).await.unwrap();
+        assert!(reports.iter().all(|report| report.status == ProvisionStatus::Created));
+        let device = resource.read_device_by_sn("PROV02").await.unwrap();
+        assert_eq!((device.gateway_id, device.configs[0].value.clone()), (gateway_prov_id, I32(30)));
+        let group = resource.read_group_device(group_device_id).await.unwrap();
+        assert!(group.device_ids.contains(&device.id));
+        // a device row can not join a gateway group and serial number of another type is allowed in per type scope
+        let group_prov_id = resource.create_group_gateway(Uuid::new_v4(), "provisioned gateway", "PROVISION", None).await.unwrap();
+        let type_prov_id = resource.create_type(Uuid::new_v4(), "Provision Type", None).await.unwrap();
+        let csv = format!("type_id,gateway_id,serial_number,name,group_ids\n\
+            {type_id},{gateway_prov_id},PROV03,Provisioned sensor,{group_prov_id}\n\
+            {type_prov_id},{gateway_prov_id},TEST01,Sensor of another type,\n");
+        let manifest = DeviceManifest::from_csv(&csv).unwrap();
+        let reports = resource.provision_devices(&manifest).await.unwrap();
+        assert_eq!(reports[0].status, ProvisionStatus::Invalid(format!("group {} has another kind", group_prov_id)));
+        assert_eq!(reports[1].status, ProvisionStatus::Valid);
+        let manifest = DeviceManifest { rows: manifest.rows[1..].to_vec() };
+        let reports = resource.provision_devices(&manifest).await.unwrap();
+        assert_eq!(reports[0].status, ProvisionStatus::Created);
+        resource.delete_device(reports[0].id).await.unwrap();
+        resource.delete_type(type_prov_id).await.unwrap();
+        resource.delete_group_gateway(group_prov_id).await.unwrap();
+        resource.delete_device(device.id).await.unwrap();
+        resource.delete_device(gateway_prov_id).await.unwrap();
+
+        // serial number is unique and upsert by serial number return the stable device id
+        let result = resource.create_device(Uuid::new_v4(), gateway_id, type_id, "TEST01", "Duplicate", None).await;
+        assert!(result.is_err());
+        let id = resource.upsert_device_by_sn(Uuid::new_v4(), gateway_id, type_id, "TEST01", "Speedometer Compass 1 reflash", None).await.unwrap();
+        assert_eq!(id, device_id1);
+        assert_eq!(resource.read_device(device_id1).await.unwrap().name, "Speedometer Compass 1 reflash");
+        resource.update_device(device_id1, None, None, None, Some("Speedometer Compass 1"), None).await.unwrap();
+        let gateway_sn_id = Uuid::new_v4();
+        let id = resource.upsert_gateway_by_sn(gateway_sn_id, type_id, "GATEWAY01", "Gateway", None).await.unwrap();
+        assert_eq!(id, gateway_sn_id);
+        let id = resource.upsert_gateway_by_sn(Uuid::new_v4(), type_id, "GATEWAY01", "Gateway reflash", None).await.unwrap();
+        assert_eq!(id, gateway_sn_id);
+        assert_eq!(resource.read_gateway_by_sn("GATEWAY01").await.unwrap().name, "Gateway reflash");
+        // serial number of a device of the other kind is not upserted
+        assert!(resource.upsert_gateway_by_sn(Uuid::new_v4(), type_id, "TEST01", "Gateway", None).await.is_err());
+        assert_eq!(resource.read_device(device_id1).await.unwrap().gateway_id, gateway_id);
+        assert!(resource.upsert_device_by_sn(Uuid::new_v4(), gateway_id, type_id, "GATEWAY01", "Sensor", None).await.is_err());
+        assert_eq!(resource.read_gateway(gateway_sn_id).await.unwrap().name, "Gateway reflash");
+        resource.delete_gateway(gateway_sn_id).await.unwrap();
+
+        // labels on device, gateway, model and set with selector queries
+        resource.add_device_label(device_id1, "site", "north").await.unwrap();
+        resource.add_device_label(device_id1, "line", "3").await.unwrap();
+        resource.add_device_label(device_id2, "site", "south").await.unwrap();
+        resource.add_device_label(device_id2, "calibrated", "").await.unwrap();
+        let gateway_label_id = resource.create_gateway(Uuid::new_v4(), type_id, "GATEWAY02", "Gateway", None).await.unwrap();
+        resource.add_gateway_label(gateway_label_id, "site", "north").await.unwrap();
+        resource.add_device_label(device_id1, "line", "4").await.unwrap();
+        let labels = resource.list_device_label(device_id1).await.unwrap();
+        assert_eq!(labels.iter().map(|l| (l.key.as_str(), l.value.as_str())).collect::<Vec<_>>(), [("line", "4"), ("site", "north")]);
+        let selector = LabelSelector::parse("site in (north,south),!calibrated").unwrap();
+        let devices = resource.list_device_by_label(&selector).await.unwrap();
+        assert!(devices.iter().any(|d| d.id == device_id1));
+        assert!(devices.iter().all(|d| d.id != device_id2));
+        let gateways = resource.list_gateway_by_label(&LabelSelector::parse("site=north").unwrap()).await.unwrap();
+        assert_eq!(gateways.iter().map(|g| g.id).collect::<Vec<Uuid>>(), [gateway_label_id]);
+        let device_ids = resource.list_device_id_by_label(&LabelSelector::parse("site!=north").unwrap()).await.unwrap();
+        assert!(device_ids.contains(&device_id2));
+        assert!(!device_ids.contains(&device_id1));
+        resource.add_set_label(set_id, "purpose", "compass").await.unwrap();
+        let sets = resource.list_set_by_label(&LabelSelector::parse("purpose").unwrap()).await.unwrap();
+        assert_eq!(sets.len(), 1);
+        resource.add_model_label(model_buf_id, "format", "raw").await.unwrap();
+        let models = resource.list_model_by_label(&LabelSelector::parse("format notin (json)").unwrap()).await.unwrap();
+        assert_eq!(models.iter().map(|m| m.id).collect::<Vec<Uuid>>(), [model_buf_id]);
+
+        // dynamic groups with membership computed from rules and materialized to static members
+        let group_rule_id = resource.create_group_device(Uuid::new_v4(), "north speedometer", "RULE", None).await.unwrap();
+        let rule = GroupRuleSchema { type_id: Some(type_id), name: Some("Speedometer%".to_owned()), labels: LabelSelector::parse("site=north").unwrap(), ..Default::default() };
+        resource.set_group_device_rule(group_rule_id, &rule).await.unwrap();
+        assert_eq!(resource.read_group_device_rule(group_rule_id).await.unwrap(), rule);
+        assert_eq!(resource.read_group_device(group_rule_id).await.unwrap().device_ids, [device_id1]);
+        // selector which can't be read back from its text is rejected
+        let labels = LabelSelector { requirements: vec![LabelRequirement::In("site".to_owned(), vec!["north,south".to_owned()])] };
+        assert!(resource.set_group_device_rule(group_rule_id, &GroupRuleSchema { labels, ..Default::default() }).await.is_err());
+        let rule = GroupRuleSchema { gateway_id: Some(gateway_id), labels: LabelSelector::parse("site").unwrap(), ..Default::default() };
+        resource.set_group_device_rule(group_rule_id, &rule).await.unwrap();
+        let mut device_ids = vec![device_id1, device_id2];
+        device_ids.sort();
+        assert_eq!(resource.read_group_device(group_rule_id).await.unwrap().device_ids, device_ids);
+        let group_rule_gateway_id = resource.create_group_gateway(Uuid::new_v4(), "north gateway", "RULE", None).await.unwrap();
+        resource.set_group_gateway_rule(group_rule_gateway_id, &GroupRuleSchema { labels: LabelSelector::parse("site=north").unwrap(), ..Default::default() }).await.unwrap();
+        assert_eq!(resource.read_group_gateway(group_rule_gateway_id).await.unwrap().gateway_ids, [gateway_label_id]);
+        let group_rule_model_id = resource.create_group_model(Uuid::new_v4(), "raw", "RULE", None).await.unwrap();
+        resource.set_group_model_rule(group_rule_model_id, &GroupRuleSchema { labels: LabelSelector::parse("format=raw").unwrap(), ..Default::default() }).await.unwrap();
+        assert_eq!(resource.read_group_model(group_rule_model_id).await.unwrap().model_ids, [model_buf_id]);
+        // group addressed data of rule groups use the members resolved from the rules
+        resource.create_data(device_id1, model_buf_id, timestamp_1, &[I32(1), I32(2)], None).await.unwrap();
+        resource.create_data(device_id2, model_buf_id, timestamp_1, &[I32(3), I32(4)], None).await.unwrap();
+        let data_group = resource.list_data_by_group_time(group_rule_id, group_rule_model_id, timestamp_1, None).await.unwrap();
+        let mut data_device_ids: Vec<Uuid> = data_group.iter().map(|d| d.device_id).collect();
+        data_device_ids.sort();
+        assert_eq!(data_device_ids, device_ids);
+        assert_eq!(resource.count_data_by_group(group_rule_id, group_rule_model_id, None).await.unwrap(), 2);
+        resource.delete_data(device_id1, model_buf_id, timestamp_1, None).await.unwrap();
+        resource.delete_data(device_id2, model_buf_id, timestamp_1, None).await.unwrap();
+        assert_eq!(resource.materialize_group_device(group_rule_id).await.unwrap(), device_ids);
+        assert!(resource.read_group_device_rule(group_rule_id).await.is_err());
+        resource.remove_device_label(device_id2, "site").await.unwrap();
+        assert_eq!(resource.read_group_device(group_rule_id).await.unwrap().device_ids, device_ids);
+        resource.remove_group_gateway_rule(group_rule_gateway_id).await.unwrap();
+        assert!(resource.read_group_gateway(group_rule_gateway_id).await.unwrap().gateway_ids.is_empty());
+        resource.delete_group_device(group_rule_id).await.unwrap();
+        resource.delete_group_gateway(group_rule_gateway_id).await.unwrap();
+        resource.delete_group_model(group_rule_model_id).await.unwrap();
+        resource.add_device_label(device_id2, "site", "south").await.unwrap();
+        resource.remove_device_label(device_id1, "line").await.unwrap();
+        assert_eq!(resource.list_device_label(device_id1).await.unwrap().len(), 1);
+        resource.delete_gateway(gateway_label_id).await.unwrap();
+
+        // device location, location history and radius and bounding box queries
+        resource.update_device_location(device_id1, -6.2, 106.8, Some(10.0)).await.unwrap();
+        let location_time = DateTime::parse_from_str("2025-06-01 08:00:00 +0000", "%Y-%m-%d %H:%M:%S %z").unwrap().into();
+        resource.create_device_location(device_id2, location_time, -6.21, 106.8, None).await.unwrap();
+        let location_time_old = DateTime::parse_from_str("2025-06-01 07:00:00 +0000", "%Y-%m-%d %H:%M:%S %z").unwrap().into();
+        resource.create_device_location(device_id2, location_time_old, -6.5, 107.0, None).await.unwrap();
+        let device = resource.read_device(device_id2).await.unwrap();
+        assert_eq!((device.latitude, device.longitude), (Some(-6.21), Some(106.8)));
+        let locations = resource.list_device_location(device_id2).await.unwrap();
+        assert_eq!(locations.iter().map(|l| l.timestamp).collect::<Vec<DateTime<Utc>>>(), [location_time_old, location_time]);
+        let devices = resource.list_device_within_radius(-6.2, 106.8, 500.0).await.unwrap();
+        assert_eq!(devices.iter().map(|d| d.id).collect::<Vec<Uuid>>(), [device_id1]);
+        let devices = resource.list_device_within_radius(-6.2095, 106.8, 2000.0).await.unwrap();
+        assert_eq!(devices.iter().map(|d| d.id).collect::<Vec<Uuid>>(), [device_id2, device_id1]);
+        let devices = resource.list_device_in_bbox(-6.205, 106.7, -6.0, 106.9).await.unwrap();
+        assert_eq!(devices.iter().map(|d| d.id).collect::<Vec<Uuid>>(), [device_id1]);
+        assert!(resource.update_device_location(device_id1, 91.0, 106.8, None).await.is_err());
+        resource.remove_device_location(device_id1).await.unwrap();
+        assert_eq!(resource.read_device(device_id1).await.unwrap().latitude, None);
+
+        // config keys declared by a device type validate device configs
+        let type_key_id = resource.create_type(Uuid::new_v4(), "Config Schema Type", None).await.unwrap();
+        resource.set_type_config_key(&TypeConfigKeySchema {
+            type_id: type_key_id, name: "period".to_owned(), category: "NETWORK".to_owned(), type_: I32T,
+            required: true, default: Some(I32(60)), min: Some(1.0), max: Some(3600.0)
+        }).await.unwrap();
+        resource.set_type_config_key(&TypeConfigKeySchema {
+            type_id: type_key_id, name: "coef_0".to_owned(), category: "CONVERSION".to_owned(), type_: F64T,
+            required: true, ..Default::default()
+        }).await.unwrap();
+        assert_eq!(resource.list_type_config_key(type_key_id).await.unwrap().len(), 2);
+        let device_key_id = resource.create_device(Uuid::new_v4(), gateway_id, type_key_id, "TESTKEY01", "Config schema device", None).await.unwrap();
+        let configs = resource.list_device_config_by_device(device_key_id).await.unwrap();
+        assert_eq!(configs.iter().map(|c| (c.name.as_str(), c.value.clone())).collect::<Vec<_>>(), [("period", I32(60))]);
+        let violations = resource.validate_device_configs(type_key_id).await.unwrap();
+        assert_eq!(violations.iter().map(|v| (v.name.as_str(), v.violation.clone())).collect::<Vec<_>>(), [("coef_0", ConfigViolation::Missing)]);
+        assert!(resource.create_device_config(device_key_id, "coef_0", I32(1), "CONVERSION").await.is_err());
+        assert!(resource.create_device_config(device_key_id, "coef_O", F64(1.0), "CONVERSION").await.is_err());
+        resource.create_device_config(device_key_id, "coef_0", F64(1.0), "CONVERSION").await.unwrap();
+        assert!(resource.update_device_config(configs[0].id, None, Some(I32(0)), None).await.is_err());
+        resource.update_device_config(configs[0].id, None, Some(I32(30)), None).await.unwrap();
+        assert!(resource.validate_device_configs(type_key_id).await.unwrap().is_empty());
+        // config of a required key is only deleted when the type has that config too
+        assert!(matches!(resource.delete_device_config(configs[0].id).await, Err(Error::InvalidArgument(_))));
+        let type_config_id = resource.create_type_config(type_key_id, "period", I32(60), "NETWORK").await.unwrap();
+        resource.delete_device_config(configs[0].id).await.unwrap();
+        assert!(resource.validate_device_configs(type_key_id).await.unwrap().is_empty());
+        resource.delete_type_config(type_config_id).await.unwrap();
+        resource.delete_device(device_key_id).await.unwrap();
+        // type change by upsert rejects configs undeclared by the new type then creates its defaults,
+        // serial number is matched across types only with global scope
+        resource.set_serial_scope(SerialScope::Global);
+        let device_move_id = resource.create_device(Uuid::new_v4(), gateway_id, type_id, "TESTKEY02", "Type change device", None).await.unwrap();
+        let config_move_id = resource.create_device_config(device_move_id, "note", I32(1), "OTHER").await.unwrap();
+        assert!(resource.upsert_device_by_sn(Uuid::new_v4(), gateway_id, type_key_id, "TESTKEY02", "Type change device", None).await.is_err());
+        resource.delete_device_config(config_move_id).await.unwrap();
+        resource.upsert_device_by_sn(Uuid::new_v4(), gateway_id, type_key_id, "TESTKEY02", "Type change device", None).await.unwrap();
+        let device = resource.read_device(device_move_id).await.unwrap();
+        assert_eq!((device.type_.id, device.configs.len()), (type_key_id, 1));
+        resource.delete_device(device_move_id).await.unwrap();
+        resource.set_serial_scope(SerialScope::Type);
+        // per type scope allows the same serial number in another type
+        let device_same_sn_id = resource.upsert_device_by_sn(Uuid::new_v4(), gateway_id, type_key_id, "TEST01", "Same serial", None).await.unwrap();
+        assert_ne!(device_same_sn_id, device_id1);
+        // serial number shared across types is read with a type
+        assert!(resource.read_device_by_sn("TEST01").await.is_err());
+        assert_eq!(resource.read_device_by_type_sn(type_id, "TEST01").await.unwrap().id, device_id1);
+        assert_eq!(resource.read_device_by_type_sn(type_key_id, "TEST01").await.unwrap().id, device_same_sn_id);
+        resource.delete_device(device_same_sn_id).await.unwrap();
+        resource.delete_type(type_key_id).await.unwrap();
+
+        // desired config is in drift until the device reports the applied value
+        let drift = resource.list_device_config_drift_by_device(device_id1).await.unwrap();
+        assert_eq!(drift.len(), 3);
+        for config in &drift {
+            resource.report_device_config(device_id1, &config.name, config.value.clone(), &config.category).await.unwrap();
+        }
+        assert!(resource.list_device_config_drift_by_device(device_id1).await.unwrap().is_empty());
+        let period = drift.iter().find(|c| c.name == "period").unwrap();
+        resource.update_device_config(period.id, None, Some(I32(30)), None).await.unwrap();
+        let drift = resource.list_device_config_drift().await.unwrap();
+        let period = drift.iter().find(|c| c.device_id == device_id1).unwrap();
+        assert_eq!((period.value.clone(), period.reported.clone()), (I32(30), Some(I32(60))));
+        assert!(period.reported_timestamp.unwrap() <= period.desired_timestamp);
+        assert!(resource.report_device_config(device_id1, "unknown", I32(0), "NETWORK").await.is_err());
+        resource.report_device_config(device_id1, "period", I32(30), "NETWORK").await.unwrap();
+        assert!(resource.list_device_config_drift_by_device(device_id1).await.unwrap().is_empty());
+
+        // decommission device and filter devices by lifecycle
+        resource.decommission_device(device_id2).await.unwrap();
+        let device = resource.read_device(device_id2).await.unwrap();
+        assert_eq!(device.lifecycle, DeviceLifecycle::Decommissioned);
+        let devices = resource.list_device_option_lifecycle(None, Some(type_id), None, Some(&[DeviceLifecycle::Active]), &[]).await.unwrap();
+        assert!(devices.iter().all(|device| device.id != device_id2));
+        let devices = resource.list_device_option_lifecycle(None, Some(type_id), None, None, &[DeviceLifecycle::Decommissioned]).await.unwrap();
+        assert!(devices.iter().any(|device| device.id == device_id1) && devices.iter().all(|device| device.id != device_id2));
+        let devices = resource.list_device_by_lifecycle(&[DeviceLifecycle::Decommissioned]).await.unwrap();
+        assert_eq!(devices.len(), 1);
+        // decommissioned devices are only hidden from other list methods when excluded by the resource option
+        assert!(resource.list_device_by_type(type_id).await.unwrap().iter().any(|device| device.id == device_id2));
+        resource.set_lifecycle_exclude(&[DeviceLifecycle::Decommissioned]);
+        assert!(resource.list_device_by_type(type_id).await.unwrap().iter().all(|device| device.id != device_id2));
+        assert!(resource.list_device_option(None, Some(type_id), None).await.unwrap().iter().all(|device| device.id != device_id2));
+        assert!(resource.read_device(device_id2).await.is_ok());
+        resource.set_lifecycle_exclude(&[]);
+
         // delete device config
         let config_id = device_configs.iter().next().map(|el| el.id).unwrap();
         resource.delete_device_config(config_id).await.unwrap();
@@ -317,6 +897,78 @@ mod tests {
         let configs = resource.list_device_config_by_device(device_id1).await.unwrap();
         assert_eq!(configs.len(), 0);
 
+        // dry run purge type only report rows to be deleted
+        let report = resource.purge_type(type_id, true).await.unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.device_ids, [device_id2]);
+        assert_eq!(report.row_count("device"), 1);
+        assert_eq!(report.row_count("device_type"), 1);
+        assert!(resource.read_device(device_id2).await.is_ok());
+
+        // derived model computed on the fly from aligned source data and materialized with an analysis tag
+        let model_voltage_id = resource.create_model(Uuid::new_v4(), &[F64T], "UPLINK", "voltage", None).await.unwrap();
+        let model_current_id = resource.create_model(Uuid::new_v4(), &[F64T], "UPLINK", "current", None).await.unwrap();
+        let model_power_id = resource.create_model(Uuid::new_v4(), &[F64T], "ANALYSIS", "power", None).await.unwrap();
+        let sources = [DerivedSource { model_id: model_voltage_id, index: 0 }, DerivedSource { model_id: model_current_id, index: 0 }];
+        resource.create_derived_model(model_power_id, &sources, &["$0 * $1"]).await.unwrap();
+        assert_eq!(resource.read_derived_model(model_power_id).await.unwrap().sources, sources);
+        for (timestamp, voltage, current) in [(timestamp_1, 220.0, 2.0), (timestamp_2, 230.0, 3.0)] {
+            resource.create_data(device_id2, model_voltage_id, timestamp, &[F64(voltage)], None).await.unwrap();
+            resource.create_data(device_id2, model_current_id, timestamp, &[F64(current)], None).await.unwrap();
+        }
+        // source row with another tag is not aligned with rows of the default tag
+        resource.create_data(device_id2, model_current_id, timestamp_1, &[F64(5.0)], Some(tag::MINUTELY)).await.unwrap();
+        let data_vec = resource.list_data_by_range(device_id2, model_power_id, timestamp_1, timestamp_2, None).await.unwrap();
+        let values: Vec<(DateTime<Utc>, Vec<DataValue>)> = data_vec.into_iter().map(|data| (data.timestamp, data.data)).collect();
+        assert_eq!(values, [(timestamp_1, vec![F64(440.0)]), (timestamp_2, vec![F64(690.0)])]);
+        assert!(resource.materialize_derived_data(device_id2, model_power_id, timestamp_1, timestamp_2, tag::DEFAULT).await.is_err());
+        let count = resource.materialize_derived_data(device_id2, model_power_id, timestamp_1, timestamp_2, tag::ANALYSIS_1).await.unwrap();
+        assert_eq!(count, 2);
+        let data = resource.read_data(device_id2, model_power_id, timestamp_2, Some(tag::ANALYSIS_1)).await.unwrap();
+        assert_eq!(data.data, [F64(690.0)]);
+        // materialized rows are read along with computed rows of other tags and data of a derived model can't be written
+        assert_eq!(resource.list_data_by_range(device_id2, model_power_id, timestamp_1, timestamp_2, None).await.unwrap().len(), 4);
+        assert!(resource.create_data(device_id2, model_power_id, timestamp_1, &[F64(1.0)], None).await.is_err());
+        resource.delete_derived_model(model_power_id).await.unwrap();
+        assert!(resource.read_derived_model(model_power_id).await.is_err());
+        for id in [model_power_id, model_voltage_id, model_current_id] {
+            resource.purge_model(id, false).await.unwrap();
+        }
+
+        // purge a gateway together with devices attached to it and archive their data
+        let gateway_purge_id = resource.create_gateway(Uuid::new_v4(), type_id, "GATEWAY_PURGE", "Purged gateway", None).await.unwrap();
+        let device_purge_id = resource.create_device(Uuid::new_v4(), gateway_purge_id, type_id, "PURGE01", "Purged device", None).await.unwrap();
+        resource.create_data(device_purge_id, model_buf_id, timestamp_1, &[I32(1), I32(2)], None).await.unwrap();
+        resource.create_buffer(device_purge_id, model_buf_id, timestamp_1, &[I32(1), I32(2)], None).await.unwrap();
+        resource.create_slice(device_purge_id, model_buf_id, timestamp_1, timestamp_2, "purged slice", None).await.unwrap();
+        let report = resource.purge_device(gateway_purge_id, true).await.unwrap();
+        let mut device_purge_ids = vec![gateway_purge_id, device_purge_id];
+        device_purge_ids.sort();
+        assert_eq!(report.device_ids, device_purge_ids);
+        assert_eq!((report.row_count("device"), report.row_count("data")), (2, 1));
+        assert_eq!((report.row_count("data_buffer"), report.row_count("slice_data")), (1, 1));
+        let report = resource.purge_device_with_archive(gateway_purge_id, false).await.unwrap();
+        assert!(report.archive);
+        assert!(resource.read_device(device_purge_id).await.is_err());
+        let archived: i64 = sqlx::query_scalar("SELECT count(*) FROM data_archive WHERE device_id = $1")
+            .bind(device_purge_id).fetch_one(&resource.pool).await.unwrap();
+        assert_eq!(archived, 1);
+        let archived: i64 = sqlx::query_scalar("SELECT count(*) FROM data_buffer_archive WHERE device_id = $1")
+            .bind(device_purge_id).fetch_one(&resource.pool).await.unwrap();
+        assert_eq!(archived, 1);
+        let archived: i64 = sqlx::query_scalar("SELECT count(*) FROM slice_data_archive WHERE device_id = $1")
+            .bind(device_purge_id).fetch_one(&resource.pool).await.unwrap();
+        assert_eq!(archived, 1);
+
+        // purge model report derived models which use the model as a source
+        let model_source_id = resource.create_model(Uuid::new_v4(), &[F32T], "UPLINK", "source", None).await.unwrap();
+        let model_derived_id = resource.create_model(Uuid::new_v4(), &[F32T], "UPLINK", "derived", None).await.unwrap();
+        resource.create_derived_model(model_derived_id, &[DerivedSource { model_id: model_source_id, index: 0 }], &["$0 * 2"]).await.unwrap();
+        let report = resource.purge_model(model_source_id, true).await.unwrap();
+        assert_eq!(report.derived_ids, [model_derived_id]);
+        resource.purge_model(model_source_id, false).await.unwrap();
+        resource.purge_model(model_derived_id, false).await.unwrap();
+
         // delete type
         let result = resource.delete_type(type_id).await;
         assert!(result.is_err()); // error because a device associated with the type still exists